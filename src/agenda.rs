@@ -0,0 +1,223 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration as Intervalo;
+
+use colored::*;
+
+use crate::convertidos::Convertidos;
+use crate::{converter_lote, listar_xml, OpcoesCli};
+
+/// Maior espera de uma vez; assim um ajuste no relógio não atrasa muito a execução.
+const ESPERA_MAXIMA: Intervalo = Intervalo::from_secs(60);
+
+/// Nomes aceitos nos campos de mês e de dia da semana.
+const MESES: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const DIAS_SEMANA: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Expressão do cron com os cinco campos: minuto, hora, dia do mês, mês e dia da semana
+/// (0 ou 7 é domingo). Cada campo aceita `*`, listas (`1,15`), intervalos (`1-5`), passos
+/// (`*/15`, `8-18/2`) e, no mês e no dia da semana, os nomes em inglês (`jan`, `mon`).
+#[derive(Debug)]
+pub struct Cron {
+    minutos: Vec<bool>,
+    horas: Vec<bool>,
+    dias: Vec<bool>,
+    meses: Vec<bool>,
+    dias_semana: Vec<bool>,
+    /// Como no cron, com o dia do mês e o da semana restritos basta um deles coincidir.
+    qualquer_dia: bool,
+}
+
+impl Cron {
+    pub fn from_expressao(expressao: &str) -> Result<Cron, String> {
+        let campos: Vec<&str> = expressao.split_whitespace().collect();
+        let [minutos, horas, dias, meses, dias_semana] = campos[..] else {
+            return Err(tr!("Expressão do cron inválida: '{}'. Use os cinco campos: minuto hora dia mês dia-da-semana, ex.: \"0 7 * * 1\".", expressao));
+        };
+        let mut semana = campo(dias_semana, 0, 7, &DIAS_SEMANA, 0, tr!("dia da semana"))?;
+        // 7 também é domingo.
+        semana[0] |= semana[7];
+        Ok(Cron {
+            minutos: campo(minutos, 0, 59, &[], 0, tr!("minuto"))?,
+            horas: campo(horas, 0, 23, &[], 0, tr!("hora"))?,
+            dias: campo(dias, 1, 31, &[], 1, tr!("dia"))?,
+            meses: campo(meses, 1, 12, &MESES, 1, tr!("mês"))?,
+            dias_semana: semana,
+            qualquer_dia: !dias.starts_with('*') && !dias_semana.starts_with('*'),
+        })
+    }
+
+    /// Primeiro instante, em minutos inteiros, depois de `instante` que atende à expressão;
+    /// `None` se não houver nenhum nos próximos anos (ex.: `0 0 31 2 *`).
+    pub fn proxima(&self, instante: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut atual = instante.naive_local().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limite = atual + Duration::days(366 * 5);
+        while atual < limite {
+            if !self.meses[atual.month() as usize] {
+                let (ano, mes) = if atual.month() == 12 { (atual.year() + 1, 1) } else { (atual.year(), atual.month() + 1) };
+                atual = NaiveDate::from_ymd_opt(ano, mes, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.dia(atual) {
+                atual = atual.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !self.horas[atual.hour() as usize] {
+                atual = atual.with_minute(0)? + Duration::hours(1);
+            } else if !self.minutos[atual.minute() as usize] {
+                atual += Duration::minutes(1);
+            } else {
+                // Um horário que não existe no fuso local (início do horário de verão) é pulado.
+                match Local.from_local_datetime(&atual).earliest() {
+                    Some(proxima) => return Some(proxima),
+                    None => atual += Duration::minutes(1),
+                }
+            }
+        }
+        None
+    }
+
+    fn dia(&self, data: NaiveDateTime) -> bool {
+        let dia = self.dias[data.day() as usize];
+        let semana = self.dias_semana[data.weekday().num_days_from_sunday() as usize];
+        if self.qualquer_dia {
+            dia || semana
+        } else {
+            dia && semana
+        }
+    }
+}
+
+/// Valores permitidos de um campo, indexados pelo próprio valor.
+fn campo(texto: &str, minimo: u32, maximo: u32, nomes: &[&str], primeiro_nome: u32, descricao: &str) -> Result<Vec<bool>, String> {
+    let invalido = || tr!("Campo {} inválido na expressão do cron: '{}'.", descricao, texto);
+    let valor = |parte: &str| -> Result<u32, String> {
+        let numero = match nomes.iter().position(|nome| nome.eq_ignore_ascii_case(parte)) {
+            Some(posicao) => posicao as u32 + primeiro_nome,
+            None => parte.parse().map_err(|_| invalido())?,
+        };
+        if numero < minimo || numero > maximo {
+            return Err(invalido());
+        }
+        Ok(numero)
+    };
+
+    let mut permitidos = vec![false; maximo as usize + 1];
+    for parte in texto.split(',') {
+        let (intervalo, passo) = match parte.split_once('/') {
+            Some((intervalo, passo)) => (intervalo, passo.parse::<u32>().ok().filter(|passo| *passo > 0).ok_or_else(invalido)?),
+            None => (parte, 1),
+        };
+        let (inicio, fim) = match intervalo {
+            "*" => (minimo, maximo),
+            _ => match intervalo.split_once('-') {
+                Some((inicio, fim)) => (valor(inicio)?, valor(fim)?),
+                // "5/15" vai de 5 até o fim, a cada 15.
+                None if passo > 1 => (valor(intervalo)?, maximo),
+                None => (valor(intervalo)?, valor(intervalo)?),
+            },
+        };
+        if inicio > fim {
+            return Err(invalido());
+        }
+        for numero in (inicio..=fim).step_by(passo as usize) {
+            permitidos[numero as usize] = true;
+        }
+    }
+    Ok(permitidos)
+}
+
+/// Converte os XML novos de `pasta` a cada horário do `cron`, até o processo ser interrompido.
+///
+/// Um XML é novo quando não está na lista de convertidos de `--all` e `watch`
+/// ([`ARQUIVO_CONVERTIDOS`](crate::convertidos::ARQUIVO_CONVERTIDOS)), foi alterado depois da
+/// conversão ou teve os arquivos gerados apagados; os que falharam são tentados de novo a
+/// cada horário.
+pub fn agendar(pasta: &Path, cron: &Cron, opcoes: &OpcoesCli) -> Result<(), Box<dyn Error>> {
+    if !pasta.is_dir() {
+        return Err(tr!("{} não é uma pasta.", pasta.display()).into());
+    }
+    let destino = opcoes.destino_convertidos();
+    loop {
+        let proxima = cron.proxima(Local::now()).ok_or(tr!("A expressão do cron não tem nenhum horário nos próximos anos."))?;
+        println!("{}", tr!("Próxima verificação de {} em {} (Ctrl+C para sair).", pasta.display(), proxima.format("%d/%m/%Y %H:%M")).bright_cyan());
+        while let Ok(restante) = (proxima - Local::now()).to_std() {
+            thread::sleep(restante.min(ESPERA_MAXIMA));
+        }
+
+        // Com --reconvert todos são convertidos; os demais são filtrados de novo por
+        // `converter_lote`, depois da trava.
+        let convertidos = match &opcoes.convertidos {
+            Some(arquivo) if !opcoes.reconverter => Some(Convertidos::carregar(arquivo)?),
+            _ => None,
+        };
+        let novos: Vec<PathBuf> = listar_xml(pasta, opcoes)?
+            .into_iter()
+            .filter(|arquivo| convertidos.as_ref().is_none_or(|convertidos| !convertidos.contem(arquivo, &destino).unwrap_or(false)))
+            .collect();
+        if novos.is_empty() {
+            println!("{}", tr!("Nenhum XML novo para converter.").bright_yellow());
+            continue;
+        }
+
+        log::info!("Execução agendada: {} XML novo(s) em {}", novos.len(), pasta.display());
+        if let Err(erro) = converter_lote(&novos, opcoes) {
+            eprintln!("{}", tr!("Erro na execução agendada: {}", erro).bright_red());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instante(ano: i32, mes: u32, dia: u32, hora: u32, minuto: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(ano, mes, dia, hora, minuto, 0).unwrap()
+    }
+
+    fn proxima(expressao: &str, depois_de: DateTime<Local>) -> Option<DateTime<Local>> {
+        Cron::from_expressao(expressao).unwrap().proxima(depois_de)
+    }
+
+    #[test]
+    fn campos_com_listas_intervalos_e_passos() {
+        let permitidos = |texto, minimo, maximo, nomes: &[&str], primeiro| -> Vec<u32> {
+            let valores = campo(texto, minimo, maximo, nomes, primeiro, "teste").unwrap();
+            (0..=maximo).filter(|numero| valores[*numero as usize]).collect()
+        };
+        assert_eq!(permitidos("*/15", 0, 59, &[], 0), [0, 15, 30, 45]);
+        assert_eq!(permitidos("8-18/4", 0, 23, &[], 0), [8, 12, 16]);
+        assert_eq!(permitidos("5/20", 0, 59, &[], 0), [5, 25, 45]);
+        assert_eq!(permitidos("1,15,31", 1, 31, &[], 1), [1, 15, 31]);
+        assert_eq!(permitidos("MON-wed,fri", 0, 7, &DIAS_SEMANA, 0), [1, 2, 3, 5]);
+        assert_eq!(permitidos("nov-dec", 1, 12, &MESES, 1), [11, 12]);
+    }
+
+    #[test]
+    fn expressoes_invalidas() {
+        for expressao in ["", "0 7 * *", "0 7 * * 1 2", "60 * * * *", "* 24 * * *", "* * 0 * *", "* * * 13 *", "* * * * 8", "*/0 * * * *", "5-1 * * * *", "a * * * *", "* * * foo *"] {
+            assert!(Cron::from_expressao(expressao).is_err(), "{:?}", expressao);
+        }
+    }
+
+    #[test]
+    fn proximo_horario() {
+        let sexta = instante(2024, 3, 1, 10, 30);
+        assert_eq!(proxima("* * * * *", sexta), Some(instante(2024, 3, 1, 10, 31)));
+        assert_eq!(proxima("*/15 * * * *", sexta), Some(instante(2024, 3, 1, 10, 45)));
+        assert_eq!(proxima("30 10 * * *", sexta), Some(instante(2024, 3, 2, 10, 30)));
+        assert_eq!(proxima("0 7 * * 1", sexta), Some(instante(2024, 3, 4, 7, 0)));
+        assert_eq!(proxima("0 7 * * 7", sexta), Some(instante(2024, 3, 3, 7, 0)));
+        assert_eq!(proxima("0 0 1 jan *", sexta), Some(instante(2025, 1, 1, 0, 0)));
+        assert_eq!(proxima("0 0 29 2 *", sexta), Some(instante(2028, 2, 29, 0, 0)));
+        assert_eq!(proxima("0 0 31 2 *", sexta), None);
+    }
+
+    #[test]
+    fn dia_do_mes_ou_da_semana() {
+        // Com os dois restritos, basta um deles: dia 15 ou segunda-feira.
+        let sexta = instante(2024, 3, 1, 10, 30);
+        assert_eq!(proxima("0 0 15 * mon", sexta), Some(instante(2024, 3, 4, 0, 0)));
+        assert_eq!(proxima("0 0 15 * mon", instante(2024, 3, 12, 0, 0)), Some(instante(2024, 3, 15, 0, 0)));
+        // Com `*` no dia da semana, só o dia do mês vale.
+        assert_eq!(proxima("0 0 15 * *", sexta), Some(instante(2024, 3, 15, 0, 0)));
+    }
+}
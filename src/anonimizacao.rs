@@ -0,0 +1,116 @@
+use crate::documento::{completar_cnpj, completar_cpf};
+use crate::erro::ErroConversor;
+use crate::saida::Destino;
+use crate::sha256::sha256;
+
+/// Como `--anonymize` esconde os dados pessoais dos arquivos gerados.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anonimizacao {
+    /// `***.***.***-00`: mantém só os dígitos verificadores do CPF.
+    Mascarar,
+    /// Troca o CPF pelo início do SHA-256 dos dígitos, o mesmo em todos os arquivos.
+    Hash,
+    /// Troca CPF, CNPJ, `Fantasia` e `Razao` por valores fictícios (os documentos com
+    /// dígitos verificadores válidos), sempre os mesmos para o mesmo valor original.
+    Ficticio,
+}
+
+impl Anonimizacao {
+    /// Nomes aceitos por `--anonymize`.
+    pub const NOMES: [&'static str; 3] = ["mask", "hash", "fake"];
+
+    pub fn from_nome(nome: &str) -> Option<Anonimizacao> {
+        match nome.to_lowercase().as_str() {
+            "mask" => Some(Anonimizacao::Mascarar),
+            "hash" => Some(Anonimizacao::Hash),
+            "fake" => Some(Anonimizacao::Ficticio),
+            _ => None,
+        }
+    }
+}
+
+/// Anonimização de `--anonymize`, com o texto de `--anonymize-salt` misturado aos hashes.
+///
+/// Sem o sal, quem conhece o método consegue achar o CPF de um hash testando todos os CPFs
+/// possíveis; com ele, os hashes só se repetem entre conversões que usam o mesmo sal.
+#[derive(Debug, Clone)]
+pub struct Anonimizador {
+    pub modo: Anonimizacao,
+    pub sal: String,
+}
+
+impl Anonimizador {
+    /// Colunas alteradas pelo modo.
+    fn anonimiza(&self, coluna: &str) -> bool {
+        match self.modo {
+            Anonimizacao::Mascarar | Anonimizacao::Hash => coluna == "CPF",
+            Anonimizacao::Ficticio => matches!(coluna, "CPF" | "CNPJ" | "Fantasia" | "Razao" | "FantasiaOriginal" | "RazaoOriginal"),
+        }
+    }
+
+    /// Valor gravado no lugar de `valor` na `coluna`; valores vazios continuam vazios.
+    fn substituir(&self, coluna: &str, valor: &str) -> String {
+        if valor.trim().is_empty() {
+            return String::new();
+        }
+        let digitos: String = valor.chars().filter(char::is_ascii_digit).collect();
+        match (self.modo, coluna) {
+            (Anonimizacao::Mascarar, _) => {
+                let verificadores = digitos.get(digitos.len().saturating_sub(2)..).unwrap_or_default();
+                format!("***.***.***-{:0>2}", verificadores)
+            }
+            (Anonimizacao::Hash, _) => self.hash(coluna, &digitos)[..16].to_string(),
+            (Anonimizacao::Ficticio, "CPF") => completar_cpf(&self.digitos(coluna, &digitos)),
+            (Anonimizacao::Ficticio, "CNPJ") => completar_cnpj(&self.digitos(coluna, &digitos)),
+            // `FantasiaOriginal` usa o hash de `Fantasia`, para que o mesmo nome tenha o mesmo
+            // fictício nas duas colunas.
+            (Anonimizacao::Ficticio, coluna) => {
+                let coluna = coluna.trim_end_matches("Original");
+                format!("{} {}", coluna, self.hash(coluna, valor.trim())[..6].to_uppercase())
+            }
+        }
+    }
+
+    /// SHA-256 de `valor` com o sal e a coluna, para que o mesmo texto tenha hashes
+    /// diferentes em `Fantasia` e `Razao`.
+    fn hash(&self, coluna: &str, valor: &str) -> String {
+        let texto = format!("{}\u{0}{}\u{0}{}", self.sal, coluna, valor);
+        sha256(texto.as_bytes()).unwrap_or_default()
+    }
+
+    /// Dígitos tirados do hash de `valor`, usados como base de um documento fictício.
+    fn digitos(&self, coluna: &str, valor: &str) -> Vec<u32> {
+        let digitos: Vec<u32> = self.hash(coluna, valor).chars().filter_map(|letra| letra.to_digit(16)).map(|digito| digito % 10).collect();
+        // A matriz `0001` dos CNPJ fictícios e um primeiro dígito diferente de zero no CPF
+        // deixam os documentos com cara de reais.
+        match coluna {
+            "CNPJ" => digitos[..8].iter().copied().chain([0, 0, 0, 1]).collect(),
+            _ => std::iter::once(digitos[0].max(1)).chain(digitos[1..9].iter().copied()).collect(),
+        }
+    }
+}
+
+/// Troca os valores das colunas com dados pessoais antes de repassá-los a `destino`.
+pub(crate) struct DestinoAnonimo<'a> {
+    pub(crate) destino: &'a mut dyn Destino,
+    pub(crate) anonimizador: &'a Anonimizador,
+    /// Nome de cada coluna do cabeçalho, quando ela é anonimizada.
+    pub(crate) colunas: Vec<Option<String>>,
+}
+
+impl Destino for DestinoAnonimo<'_> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.colunas = cabecalho.iter().map(|coluna| self.anonimizador.anonimiza(coluna).then(|| coluna.to_string())).collect();
+        self.destino.iniciar(cabecalho)
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        let substituidos: Vec<Option<String>> = registro
+            .iter()
+            .zip(self.colunas.iter().chain(std::iter::repeat(&None)))
+            .map(|(valor, coluna)| coluna.as_ref().map(|coluna| self.anonimizador.substituir(coluna, valor)))
+            .collect();
+        let anonimo: Vec<&str> = registro.iter().zip(&substituidos).map(|(valor, substituido)| substituido.as_deref().unwrap_or(valor)).collect();
+        self.destino.gravar(&anonimo)
+    }
+}
@@ -0,0 +1,58 @@
+use chrono::Local;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Pasta de `--archive` (ou de `--done-dir` no `watch`), para onde vão os XML convertidos
+/// com sucesso; os XML com erro ficam onde estão, para serem corrigidos e convertidos de novo.
+#[derive(Debug, Clone)]
+pub struct Arquivamento {
+    pub pasta: PathBuf,
+    /// `--archive-copy`: copia o XML, com a data e a hora no nome, em vez de movê-lo.
+    pub copiar: bool,
+}
+
+impl Arquivamento {
+    /// Cria a pasta, se preciso, e devolve o caminho absoluto dela, usado para não
+    /// converter de novo os XML já arquivados.
+    pub fn preparar(&self) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.pasta)?;
+        fs::canonicalize(&self.pasta)
+    }
+
+    /// Move ou copia `arquivo` para a pasta e devolve o caminho gravado.
+    ///
+    /// As cópias levam a data e a hora no nome; ao mover, isso só acontece quando a pasta
+    /// já possui um arquivo com o mesmo nome, que nunca é sobrescrito.
+    pub fn arquivar(&self, arquivo: &Path) -> io::Result<PathBuf> {
+        let nome = PathBuf::from(arquivo.file_name().unwrap_or_default());
+        let mut destino = self.pasta.join(&nome);
+        if self.copiar || destino.exists() {
+            destino = nome_livre(&self.pasta, &nome);
+        }
+
+        if self.copiar {
+            fs::copy(arquivo, &destino)?;
+        } else if fs::rename(arquivo, &destino).is_err() {
+            // A pasta pode ficar em outro sistema de arquivos.
+            fs::copy(arquivo, &destino)?;
+            fs::remove_file(arquivo)?;
+        }
+        Ok(destino)
+    }
+}
+
+/// `nome_AAAAMMDD-HHMMSS.xml` na pasta, com `-2`, `-3`... se o arquivo já existir.
+fn nome_livre(pasta: &Path, nome: &Path) -> PathBuf {
+    let base = nome.file_stem().unwrap_or_default().to_string_lossy();
+    let extensao = nome.extension().map(|extensao| format!(".{}", extensao.to_string_lossy())).unwrap_or_default();
+    let carimbo = Local::now().format("%Y%m%d-%H%M%S");
+
+    let mut destino = pasta.join(format!("{}_{}{}", base, carimbo, extensao));
+    let mut numero = 2;
+    while destino.exists() {
+        destino = pasta.join(format!("{}_{}-{}{}", base, carimbo, numero, extensao));
+        numero += 1;
+    }
+    destino
+}
@@ -0,0 +1,257 @@
+use serde::Deserialize;
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use converterxmlcsv::sha256::sha256_arquivo;
+
+/// Cliente usado para consultar a API do GitHub e baixar o executável da nova versão.
+const PROGRAMA: &str = "curl";
+
+/// Repositório cujas releases trazem os executáveis do conversor.
+const REPOSITORIO: &str = "JorgeBeserra/converterxmlcsv";
+
+/// Versão deste executável, comparada com a tag da última release.
+pub const VERSAO_ATUAL: &str = env!("CARGO_PKG_VERSION");
+
+/// Última release publicada no GitHub.
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    tag_name: String,
+    #[serde(default)]
+    assets: Vec<Anexo>,
+}
+
+/// Arquivo anexado à release: o executável de um sistema, sozinho ou num `.zip`.
+#[derive(Debug, Deserialize)]
+struct Anexo {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    /// Consulta a última release do [`REPOSITORIO`].
+    pub fn ultima() -> Result<Release, Box<dyn Error>> {
+        let endereco = format!("https://api.github.com/repos/{}/releases/latest", REPOSITORIO);
+        let resposta = curl(&["--header", "Accept: application/vnd.github+json", &endereco])?;
+        serde_json::from_slice(&resposta).map_err(|erro| tr!("Resposta inesperada do GitHub ao consultar a última versão: {}", erro).into())
+    }
+
+    /// Versão da release, sem o `v` inicial da tag.
+    pub fn versao(&self) -> &str {
+        self.tag_name.trim_start_matches(['v', 'V'])
+    }
+
+    /// Indica se a release é mais nova que [`VERSAO_ATUAL`], comparando os números da versão.
+    pub fn mais_nova(&self) -> bool {
+        numeros(self.versao()) > numeros(VERSAO_ATUAL)
+    }
+
+    /// Baixa o executável deste sistema e substitui o que está em execução. O executável
+    /// anterior só é trocado depois que o novo foi baixado por completo e conferido com o
+    /// SHA-256 publicado na release.
+    pub fn instalar(&self) -> Result<PathBuf, Box<dyn Error>> {
+        let anexo = self.anexo().ok_or_else(|| {
+            tr!("A versão {} não tem um executável para {} ({}); baixe-a em https://github.com/{}/releases.", self.versao(), env::consts::OS, env::consts::ARCH, REPOSITORIO)
+        })?;
+        let esperado = self.sha256_publicado(anexo)?;
+        let atual = env::current_exe()?.canonicalize()?;
+        let pasta = atual.parent().ok_or(tr!("Não foi possível localizar a pasta do executável."))?;
+        let baixado = pasta.join(format!(".{}.novo", anexo.name));
+        let novo = pasta.join(format!(".{}.executavel", anexo.name));
+
+        let resultado = baixar(anexo, &esperado, &baixado, &novo)
+            .and_then(|()| substituir(&atual, &novo).map_err(|erro| tr!("Não foi possível substituir {}: {}", atual.display(), erro).into()));
+        let _ = fs::remove_file(&baixado);
+        let _ = fs::remove_file(&novo);
+        resultado?;
+        log::info!("Conversor atualizado de {} para {} em {}", VERSAO_ATUAL, self.versao(), atual.display());
+        Ok(atual)
+    }
+
+    /// Anexo com o executável do sistema e da arquitetura atuais, pelo nome
+    /// (ex.: `converterxmlcsv-windows-x86_64.zip`). Só o executável sozinho ou num `.zip`
+    /// é aceito: um `.tar.gz` ou pacote do sistema não pode ser instalado no lugar dele.
+    fn anexo(&self) -> Option<&Anexo> {
+        let sistemas: &[&str] = match env::consts::OS {
+            "windows" => &["windows", "win64"],
+            "macos" => &["macos", "darwin", "apple"],
+            sistema => &[sistema][..],
+        };
+        let arquiteturas: &[&str] = match env::consts::ARCH {
+            "x86_64" => &["x86_64", "amd64", "x64"],
+            "aarch64" => &["aarch64", "arm64"],
+            arquitetura => &[arquitetura][..],
+        };
+        self.assets.iter().filter(|anexo| anexo.eh_instalavel()).find(|anexo| {
+            let nome = anexo.name.to_lowercase();
+            sistemas.iter().any(|sistema| contem_palavra(&nome, sistema)) && arquiteturas.iter().any(|arquitetura| contem_palavra(&nome, arquitetura))
+        })
+    }
+
+    /// SHA-256 de `anexo` publicado na release, num `<anexo>.sha256` ou numa linha de um
+    /// `SHA256SUMS` no formato do `sha256sum`. Sem ele, o executável não é instalado.
+    fn sha256_publicado(&self, anexo: &Anexo) -> Result<String, Box<dyn Error>> {
+        let individual = format!("{}.sha256", anexo.name).to_lowercase();
+        let soma = self
+            .assets
+            .iter()
+            .find(|outro| outro.name.to_lowercase() == individual)
+            .or_else(|| self.assets.iter().find(|outro| matches!(outro.name.to_lowercase().as_str(), "sha256sums" | "sha256sums.txt")))
+            .ok_or_else(|| tr!("A versão {} não publica o SHA-256 de {}; baixe-a em https://github.com/{}/releases.", self.versao(), anexo.name, REPOSITORIO))?;
+        let so_do_anexo = soma.name.to_lowercase() == individual;
+        let conteudo = String::from_utf8_lossy(&curl(&[&soma.browser_download_url])?).into_owned();
+        conteudo
+            .lines()
+            .filter_map(|linha| {
+                // No `<anexo>.sha256` o nome é opcional; no SHA256SUMS, `*` marca o modo binário.
+                let mut campos = linha.split_whitespace();
+                let hash = campos.next()?;
+                let nome = campos.next().map(|nome| nome.trim_start_matches('*'));
+                (so_do_anexo || nome == Some(anexo.name.as_str())).then_some(hash)
+            })
+            .find(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+            .map(str::to_lowercase)
+            .ok_or_else(|| tr!("{} não traz o SHA-256 de {}.", soma.name, anexo.name).into())
+    }
+}
+
+impl Anexo {
+    /// Indica se o anexo é um arquivo de somas SHA-256, e não um executável.
+    fn eh_soma(&self) -> bool {
+        let nome = self.name.to_lowercase();
+        nome.ends_with(".sha256") || nome.starts_with("sha256sums")
+    }
+
+    /// Indica se o anexo pode substituir o executável: um `.zip`, de onde ele é extraído,
+    /// ou o próprio executável (`.exe` no Windows, sem extensão nos demais sistemas).
+    fn eh_instalavel(&self) -> bool {
+        let nome = self.name.to_lowercase();
+        if self.eh_soma() {
+            return false;
+        }
+        match extensao(&nome) {
+            Some("zip") => true,
+            Some("exe") => cfg!(windows),
+            Some(_) => false,
+            None => !cfg!(windows),
+        }
+    }
+}
+
+/// Extensão de `nome`: o trecho depois do último ponto, quando tem só letras e dígitos.
+/// Em `converterxmlcsv-1.2.0-linux-x86_64` o ponto é da versão, e não há extensão.
+fn extensao(nome: &str) -> Option<&str> {
+    nome.rsplit_once('.').map(|(_, extensao)| extensao).filter(|extensao| !extensao.is_empty() && extensao.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Indica se `palavra` aparece em `nome` separada por caracteres que não são letras nem
+/// dígitos, para que `win` não seja encontrado em `darwin`.
+fn contem_palavra(nome: &str, palavra: &str) -> bool {
+    let separador = |caractere: Option<char>| caractere.is_none_or(|caractere| !caractere.is_ascii_alphanumeric());
+    nome.match_indices(palavra).any(|(inicio, _)| separador(nome[..inicio].chars().next_back()) && separador(nome[inicio + palavra.len()..].chars().next()))
+}
+
+/// Números da versão (`1.2.10` → `[1, 2, 10]`), para comparar `1.10` depois de `1.9`.
+fn numeros(versao: &str) -> Vec<u64> {
+    versao.split(['.', '-', '+']).map_while(|parte| parte.parse().ok()).collect()
+}
+
+/// Baixa o anexo para `baixado`, confere o SHA-256 `esperado` e deixa em `novo` o executável,
+/// extraído do `.zip` quando for o caso.
+fn baixar(anexo: &Anexo, esperado: &str, baixado: &Path, novo: &Path) -> Result<(), Box<dyn Error>> {
+    curl(&["--output", &baixado.to_string_lossy(), &anexo.browser_download_url])?;
+    let obtido = sha256_arquivo(baixado)?;
+    if obtido != esperado {
+        return Err(tr!("O SHA-256 de {} baixado ({}) não confere com o publicado na release ({}); o executável não foi substituído.", anexo.name, obtido, esperado).into());
+    }
+    if !anexo.name.to_lowercase().ends_with(".zip") {
+        fs::rename(baixado, novo)?;
+        return Ok(());
+    }
+
+    let mut zip = zip::ZipArchive::new(File::open(baixado)?).map_err(|erro| tr!("{} não é um .zip válido: {}", anexo.name, erro))?;
+    let nome_executavel = format!("{}{}", env!("CARGO_PKG_NAME"), env::consts::EXE_SUFFIX);
+    let indice = (0..zip.len())
+        .find(|&indice| zip.by_index(indice).ok().and_then(|entrada| entrada.enclosed_name()).is_some_and(|caminho| caminho.file_name().is_some_and(|nome| nome == nome_executavel.as_str())))
+        .ok_or_else(|| tr!("{} não contém o executável {}.", anexo.name, nome_executavel))?;
+    io::copy(&mut zip.by_index(indice)?, &mut File::create(novo)?)?;
+    Ok(())
+}
+
+/// Troca o executável `atual` por `novo`. No Windows o executável em uso não pode ser
+/// sobrescrito, mas pode ser renomeado: ele fica como `.old` até a próxima atualização.
+fn substituir(atual: &Path, novo: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(novo, fs::Permissions::from_mode(0o755))?;
+    }
+    if cfg!(windows) {
+        let anterior = atual.with_extension("old");
+        let _ = fs::remove_file(&anterior);
+        fs::rename(atual, &anterior)?;
+        if let Err(erro) = fs::rename(novo, atual) {
+            let _ = fs::rename(&anterior, atual);
+            return Err(erro);
+        }
+        return Ok(());
+    }
+    fs::rename(novo, atual)
+}
+
+/// Executa o curl com `argumentos` e devolve a saída; com `--fail`, uma resposta de erro do
+/// servidor também é uma falha.
+fn curl(argumentos: &[&str]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let saida = Command::new(PROGRAMA)
+        .args(["--silent", "--show-error", "--fail", "--location", "--user-agent", env!("CARGO_PKG_NAME")])
+        .args(argumentos)
+        .output()
+        .map_err(|erro| tr!("Não foi possível executar o {}, necessário para update: {}.", PROGRAMA, erro))?;
+    if !saida.status.success() {
+        return Err(tr!("Não foi possível acessar o GitHub ({}): {}", saida.status, String::from_utf8_lossy(&saida.stderr).trim()).into());
+    }
+    Ok(saida.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{contem_palavra, Anexo, Release};
+    use std::env;
+
+    fn release(nomes: &[String]) -> Release {
+        let assets = nomes.iter().map(|nome| Anexo { name: nome.clone(), browser_download_url: format!("https://example.com/{}", nome) }).collect();
+        Release { tag_name: "v9.9.9".to_string(), assets }
+    }
+
+    #[test]
+    fn sistema_pelo_nome_inteiro() {
+        assert!(!contem_palavra("converterxmlcsv-darwin-arm64.zip", "win"));
+        assert!(contem_palavra("converterxmlcsv-darwin-arm64.zip", "darwin"));
+        assert!(contem_palavra("converterxmlcsv_linux_x86_64", "x86_64"));
+        assert!(!contem_palavra("converterxmlcsv-windows-x86_64.zip", "win64"));
+    }
+
+    #[test]
+    fn recusa_tarball() {
+        let nome = format!("converterxmlcsv-{}-{}", env::consts::OS, env::consts::ARCH);
+        let tarball = release(&[format!("{}.tar.gz", nome), format!("{}.tar.gz.sha256", nome)]);
+        assert!(tarball.anexo().is_none());
+
+        let zip = release(&[format!("{}.tar.gz", nome), format!("{}.zip", nome), format!("{}.zip.sha256", nome)]);
+        assert_eq!(zip.anexo().map(|anexo| anexo.name.as_str()), Some(format!("{}.zip", nome).as_str()));
+    }
+
+    #[test]
+    fn executavel_sem_extensao() {
+        let anexo = |nome: &str| Anexo { name: nome.to_string(), browser_download_url: String::new() };
+        assert_eq!(anexo("converterxmlcsv-1.2.0-linux-x86_64").eh_instalavel(), !cfg!(windows));
+        assert_eq!(anexo("converterxmlcsv-windows-x86_64.exe").eh_instalavel(), cfg!(windows));
+        assert!(anexo("converterxmlcsv-windows-x86_64.zip").eh_instalavel());
+        assert!(!anexo("converterxmlcsv-linux-x86_64.deb").eh_instalavel());
+        assert!(!anexo("converterxmlcsv-linux-x86_64.tgz").eh_instalavel());
+    }
+}
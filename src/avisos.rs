@@ -0,0 +1,49 @@
+/// Aviso emitido durante a conversão, como um texto truncado ou uma moeda sem câmbio.
+#[derive(Debug, Clone)]
+pub struct Aviso {
+    /// Descrição no plural usada no resumo, ex.: `textos truncados`.
+    pub categoria: &'static str,
+    pub mensagem: String,
+}
+
+/// Avisos emitidos durante a conversão de um arquivo, na ordem em que ocorreram.
+#[derive(Debug, Default)]
+pub struct Avisos {
+    avisos: Vec<Aviso>,
+}
+
+impl Avisos {
+    pub fn registrar(&mut self, categoria: &'static str, mensagem: String) {
+        log::warn!("Aviso ({}): {}", categoria, mensagem);
+        self.avisos.push(Aviso { categoria, mensagem });
+    }
+
+    /// Acrescenta os avisos de outra conversão, mantendo a ordem.
+    pub fn juntar(&mut self, outros: Avisos) {
+        self.avisos.extend(outros.avisos);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.avisos.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.avisos.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Aviso> {
+        self.avisos.iter()
+    }
+
+    /// Quantidade de avisos por categoria, na ordem em que cada categoria apareceu.
+    pub fn por_categoria(&self) -> Vec<(&'static str, usize)> {
+        let mut categorias: Vec<(&'static str, usize)> = Vec::new();
+        for aviso in &self.avisos {
+            match categorias.iter_mut().find(|(nome, _)| *nome == aviso.categoria) {
+                Some((_, quantidade)) => *quantidade += 1,
+                None => categorias.push((aviso.categoria, 1)),
+            }
+        }
+        categorias
+    }
+}
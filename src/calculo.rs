@@ -0,0 +1,187 @@
+use crate::modelo::COLUNAS;
+use crate::valor::{Decimal, Quantia};
+
+/// Colunas calculadas conhecidas, aceitas em `--computed` só pelo nome.
+pub const PREDEFINIDAS: [(&str, &str); 1] = [("PercentualMeta", "MetaPremio/Valor*100")];
+
+/// Coluna acrescentada por `--computed`, calculada a partir das colunas numéricas de cada
+/// funcionário, ex.: `PercentualMeta=MetaPremio/Valor*100`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColunaCalculada {
+    pub nome: String,
+    expressao: Expressao,
+}
+
+/// Expressão com números, colunas, `+`, `-`, `*`, `/` e parênteses.
+#[derive(Debug, Clone, PartialEq)]
+enum Expressao {
+    Numero(Quantia),
+    Coluna(&'static str),
+    Negativo(Box<Expressao>),
+    Operacao(Box<Expressao>, char, Box<Expressao>),
+}
+
+impl ColunaCalculada {
+    /// Lê uma coluna no formato `Nome=EXPRESSAO` ou o nome de uma das [`PREDEFINIDAS`].
+    pub fn from_texto(texto: &str) -> Result<ColunaCalculada, String> {
+        let (nome, expressao) = match texto.split_once('=') {
+            Some((nome, expressao)) => (nome.trim(), expressao),
+            None => PREDEFINIDAS.iter().find(|(nome, _)| nome.eq_ignore_ascii_case(texto.trim())).copied().ok_or_else(|| {
+                let nomes: Vec<&str> = PREDEFINIDAS.iter().map(|(nome, _)| *nome).collect();
+                tr!("Coluna calculada inválida: '{}'. Use NOME=EXPRESSAO ou uma de: {}.", texto.trim(), nomes.join(", "))
+            })?,
+        };
+        if nome.is_empty() || !nome.chars().all(|caractere| caractere.is_alphanumeric() || caractere == '_') {
+            return Err(tr!("Nome inválido para coluna calculada: '{}'. Use apenas letras, números e _.", nome));
+        }
+        if COLUNAS.iter().any(|coluna| coluna.eq_ignore_ascii_case(nome)) {
+            return Err(tr!("A coluna calculada '{}' tem o nome de uma coluna da conversão.", nome));
+        }
+
+        let mut leitor = Leitor { simbolos: simbolos(expressao)?, posicao: 0 };
+        let expressao_lida = leitor.soma()?;
+        if let Some(simbolo) = leitor.simbolos.get(leitor.posicao) {
+            return Err(tr!("Expressão inválida em --computed '{}': '{}' inesperado.", expressao.trim(), simbolo));
+        }
+        Ok(ColunaCalculada { nome: nome.to_string(), expressao: expressao_lida })
+    }
+
+    /// Resultado para os valores de `coluna`; `None` quando alguma coluna usada está vazia ou
+    /// não é número, ou quando há divisão por zero, para que a célula fique vazia em vez de
+    /// mostrar um cálculo feito com zero.
+    ///
+    /// O cálculo é feito em [`Quantia`], como os totais: `Valor*1` com `Valor` `1.005` dá
+    /// `1.01` com duas casas, e não `1.00` como em `f64`.
+    pub(crate) fn calcular(&self, coluna: &dyn Fn(&str) -> Option<Quantia>) -> Option<Quantia> {
+        avaliar(&self.expressao, coluna)
+    }
+}
+
+fn avaliar(expressao: &Expressao, coluna: &dyn Fn(&str) -> Option<Quantia>) -> Option<Quantia> {
+    match expressao {
+        Expressao::Numero(numero) => Some(*numero),
+        Expressao::Coluna(nome) => coluna(nome),
+        Expressao::Negativo(expressao) => avaliar(expressao, coluna).map(|valor| -valor),
+        Expressao::Operacao(esquerda, operador, direita) => {
+            let (esquerda, direita) = (avaliar(esquerda, coluna)?, avaliar(direita, coluna)?);
+            match operador {
+                '+' => Some(esquerda + direita),
+                '-' => Some(esquerda - direita),
+                '*' => esquerda.vezes(direita),
+                _ => esquerda.dividida_por(direita),
+            }
+        }
+    }
+}
+
+/// Números, nomes de coluna, operadores e parênteses da expressão.
+fn simbolos(expressao: &str) -> Result<Vec<String>, String> {
+    let mut simbolos = Vec::new();
+    let mut caracteres = expressao.chars().peekable();
+    while let Some(&caractere) = caracteres.peek() {
+        if caractere.is_whitespace() {
+            caracteres.next();
+        } else if "+-*/()".contains(caractere) {
+            simbolos.push(caractere.to_string());
+            caracteres.next();
+        } else if caractere.is_alphanumeric() || caractere == '.' || caractere == '_' {
+            let mut simbolo = String::new();
+            while let Some(&caractere) = caracteres.peek().filter(|caractere| caractere.is_alphanumeric() || **caractere == '.' || **caractere == '_') {
+                simbolo.push(caractere);
+                caracteres.next();
+            }
+            simbolos.push(simbolo);
+        } else {
+            return Err(tr!("Caractere inválido em --computed '{}': '{}'.", expressao.trim(), caractere));
+        }
+    }
+    Ok(simbolos)
+}
+
+/// Leitura da expressão por descida recursiva, com `*` e `/` antes de `+` e `-`.
+struct Leitor {
+    simbolos: Vec<String>,
+    posicao: usize,
+}
+
+impl Leitor {
+    fn proximo(&mut self) -> Option<String> {
+        let simbolo = self.simbolos.get(self.posicao)?.clone();
+        self.posicao += 1;
+        Some(simbolo)
+    }
+
+    fn operador(&mut self, operadores: &str) -> Option<char> {
+        let operador = self.simbolos.get(self.posicao).and_then(|simbolo| simbolo.chars().next()).filter(|caractere| operadores.contains(*caractere))?;
+        self.posicao += 1;
+        Some(operador)
+    }
+
+    fn soma(&mut self) -> Result<Expressao, String> {
+        let mut expressao = self.produto()?;
+        while let Some(operador) = self.operador("+-") {
+            expressao = Expressao::Operacao(Box::new(expressao), operador, Box::new(self.produto()?));
+        }
+        Ok(expressao)
+    }
+
+    fn produto(&mut self) -> Result<Expressao, String> {
+        let mut expressao = self.fator()?;
+        while let Some(operador) = self.operador("*/") {
+            expressao = Expressao::Operacao(Box::new(expressao), operador, Box::new(self.fator()?));
+        }
+        Ok(expressao)
+    }
+
+    fn fator(&mut self) -> Result<Expressao, String> {
+        match self.proximo().as_deref() {
+            Some("-") => Ok(Expressao::Negativo(Box::new(self.fator()?))),
+            Some("(") => {
+                let expressao = self.soma()?;
+                match self.proximo().as_deref() {
+                    Some(")") => Ok(expressao),
+                    _ => Err(tr!("Expressão inválida em --computed: falta fechar um parêntese.").to_string()),
+                }
+            }
+            Some(simbolo) if simbolo.starts_with(|caractere: char| caractere.is_ascii_digit() || caractere == '.') => {
+                Quantia::ler(simbolo, Some(Decimal::Ponto)).map(Expressao::Numero).ok_or_else(|| tr!("Número inválido em --computed: '{}'.", simbolo))
+            }
+            Some(simbolo) => COLUNAS
+                .iter()
+                .copied()
+                .find(|coluna| coluna.eq_ignore_ascii_case(simbolo))
+                .map(Expressao::Coluna)
+                .ok_or_else(|| tr!("Coluna desconhecida em --computed: '{}'. Use uma de: {}.", simbolo, COLUNAS.join(", "))),
+            None => Err(tr!("Expressão incompleta em --computed.").to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calcular(texto: &str, valor: &str, meta: &str) -> Option<String> {
+        let coluna = |nome: &str| match nome {
+            "Valor" => Quantia::ler(valor, None),
+            "MetaPremio" => Quantia::ler(meta, None),
+            _ => None,
+        };
+        ColunaCalculada::from_texto(texto).unwrap().calcular(&coluna).map(|resultado| format!("{:.2}", resultado))
+    }
+
+    #[test]
+    fn coluna_calculada_exata() {
+        assert_eq!(calcular("PercentualMeta", "200", "50").as_deref(), Some("25.00"));
+        assert_eq!(calcular("Total=Valor*1", "1.005", "").as_deref(), Some("1.01"));
+        assert_eq!(calcular("Total=(Valor + MetaPremio) * 3", "0.1", "0.2").as_deref(), Some("0.90"));
+        assert_eq!(calcular("Negativo=-Valor/2", "1.01", "").as_deref(), Some("-0.51"));
+    }
+
+    #[test]
+    fn coluna_calculada_sem_resultado() {
+        assert_eq!(calcular("PercentualMeta", "0", "50"), None);
+        assert_eq!(calcular("PercentualMeta", "abc", "50"), None);
+        assert_eq!(calcular("PercentualMeta", "100", ""), None);
+    }
+}
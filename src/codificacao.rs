@@ -0,0 +1,214 @@
+use std::io::{self, BufRead, Read, Write};
+
+/// Caracteres de 0x80 a 0x9F no Windows-1252; nessa faixa o ISO-8859-1 só tem caracteres
+/// de controle, que não aparecem em texto. Os cinco bytes sem caractere ficam como no
+/// ISO-8859-1.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// Marca de ordem de bytes do UTF-8, que o Excel usa para reconhecer a codificação do CSV.
+const BOM_UTF8: &[u8] = b"\xEF\xBB\xBF";
+
+fn decodificar_byte(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => WINDOWS_1252[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+fn codificar_caractere(caractere: char) -> u8 {
+    match caractere as u32 {
+        codigo @ (0..=0x7F | 0xA0..=0xFF) => codigo as u8,
+        _ => WINDOWS_1252.iter().position(|item| *item == caractere).map_or(b'?', |indice| 0x80 + indice as u8),
+    }
+}
+
+/// Codificação dos CSVs gerados, escolhida por `--output-encoding`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Codificacao {
+    #[default]
+    Utf8,
+    /// UTF-8 com BOM, para que o Excel no Windows reconheça os acentos ao abrir o CSV.
+    Utf8Bom,
+    /// ISO-8859-1, com os caracteres extras do Windows-1252 (€, aspas curvas, travessões);
+    /// os demais caracteres são gravados como `?`.
+    Latin1,
+}
+
+impl Codificacao {
+    /// Nomes aceitos por `--output-encoding`.
+    pub const NOMES: [&'static str; 3] = ["utf8", "utf8-bom", "latin1"];
+
+    pub fn from_nome(nome: &str) -> Option<Codificacao> {
+        match nome.to_lowercase().as_str() {
+            "utf8" | "utf-8" => Some(Codificacao::Utf8),
+            "utf8-bom" | "utf-8-bom" => Some(Codificacao::Utf8Bom),
+            "latin1" | "iso-8859-1" => Some(Codificacao::Latin1),
+            _ => None,
+        }
+    }
+}
+
+/// Escritor que converte o texto UTF-8 gravado nele para a [`Codificacao`] do CSV.
+pub struct Codificado<W: Write> {
+    interno: W,
+    codificacao: Codificacao,
+    /// O BOM ainda não foi gravado.
+    bom: bool,
+    /// Bytes do início de um caractere que chegou dividido entre duas gravações.
+    pendente: Vec<u8>,
+}
+
+impl<W: Write> Codificado<W> {
+    /// `inicio_do_arquivo` indica que nada foi gravado antes em `interno`; só então o BOM
+    /// de [`Codificacao::Utf8Bom`] é gravado.
+    pub fn new(interno: W, codificacao: Codificacao, inicio_do_arquivo: bool) -> Codificado<W> {
+        Codificado {
+            interno,
+            codificacao,
+            bom: inicio_do_arquivo && codificacao == Codificacao::Utf8Bom,
+            pendente: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.interno
+    }
+}
+
+impl<W: Write> Write for Codificado<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.bom {
+            self.interno.write_all(BOM_UTF8)?;
+            self.bom = false;
+        }
+        if self.codificacao != Codificacao::Latin1 {
+            return self.interno.write(buf);
+        }
+
+        self.pendente.extend_from_slice(buf);
+        let valido = match std::str::from_utf8(&self.pendente) {
+            Ok(texto) => texto.len(),
+            Err(erro) if erro.error_len().is_none() => erro.valid_up_to(),
+            Err(_) => return Err(io::Error::new(io::ErrorKind::InvalidData, tr!("texto gravado no CSV não é UTF-8"))),
+        };
+        let texto = std::str::from_utf8(&self.pendente[..valido]).expect("trecho já validado");
+        let convertido: Vec<u8> = texto.chars().map(codificar_caractere).collect();
+        self.interno.write_all(&convertido)?;
+        self.pendente.drain(..valido);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.interno.flush()
+    }
+}
+
+/// Leitor que entrega o XML em UTF-8, convertendo os arquivos declarados como ISO-8859-1
+/// ou Windows-1252 (`<?xml version="1.0" encoding="ISO-8859-1"?>`). Sem declaração, o
+/// XML é lido como UTF-8.
+///
+/// Como o ERP que declara ISO-8859-1 costuma gravar também os caracteres do Windows-1252,
+/// os dois são lidos como Windows-1252, como fazem os navegadores.
+pub(crate) struct Decodificado<R: BufRead> {
+    interno: R,
+    /// `None` até a declaração ser lida; `Some(true)` quando o XML precisa ser convertido.
+    converter: Option<bool>,
+    convertido: Vec<u8>,
+    posicao: usize,
+    /// Linha do XML onde está a leitura, contando as quebras de linha já consumidas.
+    linha: usize,
+}
+
+impl<R: BufRead> Decodificado<R> {
+    pub(crate) fn new(interno: R) -> Decodificado<R> {
+        Decodificado { interno, converter: None, convertido: Vec::new(), posicao: 0, linha: 1 }
+    }
+
+    /// Para textos já em UTF-8, como uma `String`, cuja declaração não vale mais.
+    pub(crate) fn utf8(interno: R) -> Decodificado<R> {
+        Decodificado { interno, converter: Some(false), convertido: Vec::new(), posicao: 0, linha: 1 }
+    }
+
+    /// Linha do XML onde está a leitura, a partir de 1.
+    pub(crate) fn linha(&self) -> usize {
+        self.linha
+    }
+}
+
+impl<R: BufRead> Read for Decodificado<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let disponivel = self.fill_buf()?;
+        let quantidade = disponivel.len().min(buf.len());
+        buf[..quantidade].copy_from_slice(&disponivel[..quantidade]);
+        self.consume(quantidade);
+        Ok(quantidade)
+    }
+}
+
+impl<R: BufRead> BufRead for Decodificado<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let converter = match self.converter {
+            Some(converter) => converter,
+            None => {
+                let converter = declarado_latin1(self.interno.fill_buf()?)?;
+                self.converter = Some(converter);
+                converter
+            }
+        };
+        if !converter {
+            return self.interno.fill_buf();
+        }
+
+        if self.posicao >= self.convertido.len() {
+            let bytes = self.interno.fill_buf()?;
+            let lidos = bytes.len();
+            self.convertido = bytes.iter().map(|byte| decodificar_byte(*byte)).collect::<String>().into_bytes();
+            self.posicao = 0;
+            self.interno.consume(lidos);
+        }
+        Ok(&self.convertido[self.posicao..])
+    }
+
+    fn consume(&mut self, quantidade: usize) {
+        let quebras = |bytes: &[u8]| bytes.iter().filter(|byte| **byte == b'\n').count();
+        if self.converter == Some(true) {
+            let fim = (self.posicao + quantidade).min(self.convertido.len());
+            self.linha += quebras(&self.convertido[self.posicao.min(fim)..fim]);
+            self.posicao += quantidade;
+        } else {
+            if quantidade > 0 {
+                if let Ok(bytes) = self.interno.fill_buf() {
+                    self.linha += quebras(&bytes[..quantidade.min(bytes.len())]);
+                }
+            }
+            self.interno.consume(quantidade);
+        }
+    }
+}
+
+/// Indica se a declaração no início do XML pede ISO-8859-1 ou Windows-1252. Codificações
+/// que não são lidas pelo conversor resultam em erro.
+fn declarado_latin1(inicio: &[u8]) -> io::Result<bool> {
+    if !inicio.starts_with(b"<?xml") {
+        return Ok(false);
+    }
+    let fim = inicio.windows(2).position(|par| par == b"?>").unwrap_or(inicio.len());
+    let declaracao = String::from_utf8_lossy(&inicio[..fim]);
+    let Some((_, resto)) = declaracao.split_once("encoding") else {
+        return Ok(false);
+    };
+    let resto = resto.trim_start().trim_start_matches('=').trim_start();
+    let nome: String = resto.chars().skip(1).take_while(|caractere| *caractere != '"' && *caractere != '\'').collect();
+
+    match nome.to_lowercase().as_str() {
+        "utf-8" | "utf8" | "us-ascii" | "ascii" => Ok(false),
+        "iso-8859-1" | "iso8859-1" | "iso_8859-1" | "latin1" | "latin-1" | "l1" | "windows-1252" | "cp1252" | "x-cp1252" => Ok(true),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            tr!("A codificação '{}' declarada no XML não é suportada. Use UTF-8, ISO-8859-1 ou Windows-1252.", nome),
+        )),
+    }
+}
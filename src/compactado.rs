@@ -0,0 +1,135 @@
+use chrono::{Datelike, Local, Timelike};
+use flate2::read::MultiGzDecoder;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Cursor, Read};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+use converterxmlcsv::sha256::{sha256, sha256_arquivo};
+
+use crate::progresso::ProgressoArquivo;
+
+/// XML a converter: um arquivo `.xml`, um `.xml.gz` ou um dos XML de dentro de um `.zip`.
+pub enum Fonte {
+    Arquivo(PathBuf),
+    Gzip(PathBuf),
+    /// XML na posição `indice` do `.zip`; ele só é descompactado ao ser aberto.
+    Zip { arquivo: PathBuf, indice: usize, nome: String },
+}
+
+impl Fonte {
+    /// Caminho do XML que dá nome aos arquivos gerados: sem o `.gz` ou, no `.zip`, o nome do
+    /// XML interno na pasta do `.zip`.
+    pub fn caminho(&self) -> PathBuf {
+        match self {
+            Fonte::Arquivo(caminho) => caminho.clone(),
+            Fonte::Gzip(caminho) => caminho.with_extension(""),
+            Fonte::Zip { arquivo, nome, .. } => arquivo.with_file_name(Path::new(nome).file_name().unwrap_or_default()),
+        }
+    }
+
+    /// Identificação do XML nas mensagens, no CSV de `--merge` e no manifest; no `.zip`,
+    /// `pasta/arquivo.zip/interno.xml`.
+    pub fn origem(&self) -> PathBuf {
+        match self {
+            Fonte::Arquivo(caminho) | Fonte::Gzip(caminho) => caminho.clone(),
+            Fonte::Zip { arquivo, nome, .. } => arquivo.join(nome),
+        }
+    }
+
+    /// Abre o XML já descompactado. Com `progresso`, a barra avança pelos bytes lidos do
+    /// arquivo em disco, comprimidos no `.xml.gz`.
+    pub fn abrir<'a>(&self, progresso: Option<ProgressoArquivo<'a>>) -> io::Result<BufReader<Box<dyn Read + 'a>>> {
+        let contar = |leitor: File| -> Box<dyn Read + 'a> {
+            match progresso {
+                Some(progresso) => Box::new(progresso.ler(leitor)),
+                None => Box::new(leitor),
+            }
+        };
+        let leitor: Box<dyn Read + 'a> = match self {
+            Fonte::Arquivo(caminho) => contar(File::open(caminho)?),
+            Fonte::Gzip(caminho) => Box::new(MultiGzDecoder::new(contar(File::open(caminho)?))),
+            Fonte::Zip { arquivo, indice, .. } => {
+                let mut zip = ZipArchive::new(File::open(arquivo)?)?;
+                let mut conteudo = Vec::new();
+                zip.by_index(*indice)?.read_to_end(&mut conteudo)?;
+                Box::new(Cursor::new(conteudo))
+            }
+        };
+        Ok(BufReader::new(leitor))
+    }
+
+    /// SHA-256 do que foi lido: do arquivo em disco ou, no `.zip`, do XML descompactado.
+    pub fn sha256(&self) -> io::Result<String> {
+        match self {
+            Fonte::Arquivo(caminho) | Fonte::Gzip(caminho) => sha256_arquivo(caminho),
+            Fonte::Zip { .. } => sha256(self.abrir(None)?),
+        }
+    }
+}
+
+/// XML de `caminho`: os de dentro de um `.zip`, na ordem em que foram compactados, ou o
+/// próprio arquivo.
+pub fn fontes(caminho: &Path) -> Result<Vec<Fonte>, Box<dyn Error>> {
+    if tem_extensao(caminho, "zip") {
+        let zip = ZipArchive::new(File::open(caminho)?).map_err(|erro| tr!("Não foi possível abrir {}: {}", caminho.display(), erro))?;
+        // As pastas `__MACOSX` dos .zip do macOS trazem cópias ocultas com a extensão .xml.
+        let fontes: Vec<Fonte> = (0..zip.len())
+            .filter_map(|indice| zip.name_for_index(indice).map(|nome| (indice, nome)))
+            .filter(|(_, nome)| eh_xml(Path::new(nome)) && !nome.starts_with("__MACOSX/"))
+            .map(|(indice, nome)| Fonte::Zip { arquivo: caminho.to_path_buf(), indice, nome: nome.to_string() })
+            .collect();
+        if fontes.is_empty() {
+            return Err(tr!("{} não possui arquivos XML.", caminho.display()).into());
+        }
+        return Ok(fontes);
+    }
+    if eh_gzip(caminho) {
+        return Ok(vec![Fonte::Gzip(caminho.to_path_buf())]);
+    }
+    Ok(vec![Fonte::Arquivo(caminho.to_path_buf())])
+}
+
+/// Compacta os arquivos gerados em `destino` (`--zip-output`), cada um com o caminho em que
+/// foi gravado; os originais são mantidos.
+pub fn compactar(destino: &Path, arquivos: &[&Path]) -> Result<(), Box<dyn Error>> {
+    let resultado = gravar_zip(destino, arquivos);
+    if resultado.is_err() {
+        let _ = fs::remove_file(destino);
+    }
+    resultado
+}
+
+fn gravar_zip(destino: &Path, arquivos: &[&Path]) -> Result<(), Box<dyn Error>> {
+    let agora = Local::now();
+    let data = DateTime::from_date_and_time(agora.year() as u16, agora.month() as u8, agora.day() as u8, agora.hour() as u8, agora.minute() as u8, agora.second() as u8)
+        .unwrap_or_default();
+    let opcoes = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated).last_modified_time(data);
+
+    let mut zip = ZipWriter::new(File::create(destino)?);
+    for arquivo in arquivos {
+        zip.start_file_from_path(arquivo, opcoes)?;
+        io::copy(&mut File::open(arquivo)?, &mut zip)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Indica se o arquivo pode ser convertido: `.xml`, `.xml.gz` ou `.zip`.
+pub fn eh_entrada(caminho: &Path) -> bool {
+    eh_xml(caminho) || eh_gzip(caminho) || tem_extensao(caminho, "zip")
+}
+
+fn eh_xml(caminho: &Path) -> bool {
+    tem_extensao(caminho, "xml")
+}
+
+fn eh_gzip(caminho: &Path) -> bool {
+    tem_extensao(caminho, "gz") && eh_xml(&caminho.with_extension(""))
+}
+
+fn tem_extensao(caminho: &Path, extensao: &str) -> bool {
+    caminho.extension().is_some_and(|atual| atual.eq_ignore_ascii_case(extensao))
+}
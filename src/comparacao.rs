@@ -0,0 +1,199 @@
+use colored::*;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use converterxmlcsv::saida::novo_writer;
+use converterxmlcsv::valor::{Decimal, Quantia};
+use converterxmlcsv::{Destino, ErroConversor};
+
+use crate::compactado;
+use crate::{abrir_entrada, converter_entrada, Convertido, OpcoesCli, ENTRADA_PADRAO};
+
+const CABECALHO: [&str; 7] = ["CPF", "CNPJ", "Situacao", "ValorAnterior", "ValorAtual", "Diferenca", "Empresa"];
+
+/// Valor de um funcionário num XML; as linhas de mesmo CPF (ex.: parcelas do décimo
+/// terceiro) são somadas.
+#[derive(Debug)]
+struct Funcionario {
+    cpf: String,
+    cnpj: String,
+    empresa: String,
+    valor: Quantia,
+}
+
+/// Destino de `diff`: soma o valor de cada CPF, na ordem em que aparecem no XML. Os valores
+/// chegam com ponto decimal, pois `diff` usa `decimal_saida` [`Decimal::Ponto`].
+struct Valores {
+    colunas: [Option<usize>; 4],
+    funcionarios: Vec<Funcionario>,
+    posicoes: HashMap<String, usize>,
+}
+
+impl Destino for Valores {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.colunas = ["CPF", "CNPJ", "Razao", "Valor"].map(|nome| cabecalho.iter().position(|coluna| *coluna == nome));
+        if self.colunas[0].is_none() || self.colunas[3].is_none() {
+            return Err(ErroConversor::EntradaInvalida(tr!("O XML não possui as colunas CPF e Valor necessárias para a comparação.").to_string()));
+        }
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        let campo = |coluna: Option<usize>| coluna.and_then(|indice| registro.get(indice)).copied().unwrap_or_default();
+        let cpf = campo(self.colunas[0]);
+        let texto = campo(self.colunas[3]);
+        let valor = Quantia::ler(texto, Some(Decimal::Ponto))
+            .ok_or_else(|| ErroConversor::ValorInvalido(tr!("Valor não numérico '{}' no CPF {}.", texto, cpf)))?;
+        let chave: String = cpf.chars().filter(char::is_ascii_digit).collect();
+        match self.posicoes.get(&chave) {
+            Some(&posicao) => self.funcionarios[posicao].valor += valor,
+            None => {
+                self.posicoes.insert(chave, self.funcionarios.len());
+                self.funcionarios.push(Funcionario { cpf: cpf.to_string(), cnpj: campo(self.colunas[1]).to_string(), empresa: campo(self.colunas[2]).to_string(), valor });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Diferença de um funcionário entre os dois XML.
+#[derive(Debug)]
+struct Alteracao<'a> {
+    funcionario: &'a Funcionario,
+    situacao: &'static str,
+    anterior: Option<Quantia>,
+    atual: Option<Quantia>,
+}
+
+impl Alteracao<'_> {
+    fn campos(&self) -> [String; 7] {
+        let valor = |valor: Option<Quantia>| valor.map(|valor| format!("{:.2}", valor)).unwrap_or_default();
+        [
+            self.funcionario.cpf.clone(),
+            self.funcionario.cnpj.clone(),
+            self.situacao.to_string(),
+            valor(self.anterior),
+            valor(self.atual),
+            format!("{:.2}", self.atual.unwrap_or_default() - self.anterior.unwrap_or_default()),
+            self.funcionario.empresa.clone(),
+        ]
+    }
+}
+
+/// `diff`: compara os funcionários de dois XML pelo CPF, listando os incluídos, os removidos
+/// e os que tiveram o valor alterado, na tela ou no CSV de `saida` (`-` é a saída padrão).
+pub fn comparar(anterior: &Path, atual: &Path, saida: Option<&str>, opcoes: &OpcoesCli) -> Result<(), Box<dyn Error>> {
+    let (tipo_anterior, antes) = ler(anterior, opcoes)?;
+    let (tipo_atual, depois) = ler(atual, opcoes)?;
+    let mut console: Box<dyn Write> = match saida {
+        Some(ENTRADA_PADRAO) => Box::new(io::stderr()),
+        _ => Box::new(io::stdout()),
+    };
+    if tipo_anterior != tipo_atual {
+        writeln!(console, "{}", tr!("Atenção: {} é de {} e {} é de {}.", anterior.display(), tipo_anterior, atual.display(), tipo_atual).bright_yellow())?;
+    }
+
+    let chave = |funcionario: &Funcionario| funcionario.cpf.chars().filter(char::is_ascii_digit).collect::<String>();
+    let indice_antes: HashMap<String, &Funcionario> = antes.iter().map(|funcionario| (chave(funcionario), funcionario)).collect();
+    let indice_depois: HashMap<String, &Funcionario> = depois.iter().map(|funcionario| (chave(funcionario), funcionario)).collect();
+
+    let mut alteracoes = Vec::new();
+    let mut iguais = 0;
+    for funcionario in &depois {
+        match indice_antes.get(&chave(funcionario)) {
+            None => alteracoes.push(Alteracao { funcionario, situacao: "incluido", anterior: None, atual: Some(funcionario.valor) }),
+            // Centavos arredondados, para que somas em ponto flutuante não apareçam como alteração.
+            Some(antigo) if antigo.valor.centavos() != funcionario.valor.centavos() => {
+                alteracoes.push(Alteracao { funcionario, situacao: "alterado", anterior: Some(antigo.valor), atual: Some(funcionario.valor) })
+            }
+            Some(_) => iguais += 1,
+        }
+    }
+    for funcionario in antes.iter().filter(|funcionario| !indice_depois.contains_key(&chave(funcionario))) {
+        alteracoes.push(Alteracao { funcionario, situacao: "removido", anterior: Some(funcionario.valor), atual: None });
+    }
+
+    match saida {
+        Some(caminho) => {
+            let destino: Box<dyn Write> = if caminho == ENTRADA_PADRAO { Box::new(io::stdout()) } else { Box::new(File::create(caminho)?) };
+            let mut writer = novo_writer(destino, &opcoes.conversao.formato);
+            writer.write_record(CABECALHO)?;
+            for alteracao in &alteracoes {
+                writer.write_record(alteracao.campos())?;
+            }
+            writer.flush()?;
+        }
+        None => exibir(&mut console, &alteracoes)?,
+    }
+
+    let contar = |situacao: &str| alteracoes.iter().filter(|alteracao| alteracao.situacao == situacao).count();
+    let total = |funcionarios: &[Funcionario]| funcionarios.iter().map(|funcionario| funcionario.valor).sum::<Quantia>();
+    writeln!(
+        console,
+        "{}",
+        tr!(
+            "Incluídos: {}. Removidos: {}. Alterados: {}. Sem alteração: {}.\nTotal anterior: R$ {}\nTotal atual: R$ {}",
+            contar("incluido"),
+            contar("removido"),
+            contar("alterado"),
+            iguais,
+            format!("{:.2}", total(&antes)),
+            format!("{:.2}", total(&depois))
+        )
+        .bright_green()
+    )?;
+    Ok(())
+}
+
+/// Tipo e funcionários de um XML; os de um `.zip` são somados.
+fn ler(arquivo: &Path, opcoes: &OpcoesCli) -> Result<(String, Vec<Funcionario>), Box<dyn Error>> {
+    let mut valores = Valores { colunas: [None; 4], funcionarios: Vec::new(), posicoes: HashMap::new() };
+    let mut tipo = String::new();
+    for fonte in compactado::fontes(arquivo)? {
+        let abrir = || -> Result<_, Box<dyn Error>> { Ok(fonte.abrir(None)?) };
+        let convertido = abrir_entrada(abrir, Some(&fonte.caminho()), opcoes)
+            .and_then(|entrada| converter_entrada(entrada, opcoes, &mut valores))
+            .map_err(|erro| format!("{}: {}", arquivo.display(), erro))?;
+        if let Convertido::Tipado(resumo) = &convertido {
+            tipo = resumo.tipo.nome().to_string();
+        }
+    }
+    Ok((tipo, valores.funcionarios))
+}
+
+/// Tabela das alterações, com o texto alinhado à esquerda e os números à direita.
+fn exibir(console: &mut dyn Write, alteracoes: &[Alteracao]) -> io::Result<()> {
+    if alteracoes.is_empty() {
+        return writeln!(console, "{}", tr!("Nenhuma diferença entre os funcionários dos dois arquivos.").bright_cyan());
+    }
+    let todas: Vec<[String; 7]> = alteracoes.iter().map(Alteracao::campos).collect();
+    let larguras: Vec<usize> = (0..CABECALHO.len())
+        .map(|coluna| todas.iter().map(|campos| campos[coluna].chars().count()).chain([CABECALHO[coluna].len()]).max().unwrap_or(0))
+        .collect();
+    let formatar = |campos: &[&str]| -> String {
+        campos
+            .iter()
+            .zip(&larguras)
+            .enumerate()
+            .map(|(coluna, (campo, largura))| if (3..6).contains(&coluna) { format!("{:>largura$}", campo) } else { format!("{:<largura$}", campo) })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    writeln!(console, "{}", formatar(&CABECALHO).bold())?;
+    for (alteracao, campos) in alteracoes.iter().zip(&todas) {
+        let linha = formatar(&campos.iter().map(String::as_str).collect::<Vec<_>>());
+        let linha = match alteracao.situacao {
+            "incluido" => linha.green(),
+            "removido" => linha.red(),
+            _ => linha.normal(),
+        };
+        writeln!(console, "{}", linha)?;
+    }
+    Ok(())
+}
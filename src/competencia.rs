@@ -0,0 +1,44 @@
+//! Competência (`MesAno`) dos XML, que chega como `05/2024`, `2024-05`, `052024`...
+
+/// Competência no formato `MM/AAAA`, usado em todos os arquivos gerados.
+///
+/// Aceita mês e ano separados por `/`, `-` ou `.`, em qualquer ordem (`05/2024`, `5-2024`,
+/// `2024-05`, `2024/5`), e só com dígitos (`052024`, `202405`). Um texto que não é uma
+/// competência válida é mantido como veio, sem os espaços das pontas.
+pub fn normalizar_mes_ano(texto: &str) -> String {
+    match ano_mes(texto) {
+        Some((ano, mes)) => format!("{:02}/{}", mes, ano),
+        None => texto.trim().to_string(),
+    }
+}
+
+/// Ano e mês da competência, ou `None` quando o texto não é reconhecido.
+pub fn ano_mes(texto: &str) -> Option<(u32, u32)> {
+    let texto = texto.trim();
+    let partes: Vec<&str> = texto.split(['/', '-', '.']).collect();
+    match partes.as_slice() {
+        [primeira, segunda] => {
+            let (ano, mes) = if primeira.len() == 4 { (primeira, segunda) } else { (segunda, primeira) };
+            valida(numero(ano, 4, 4)?, numero(mes, 1, 2)?)
+        }
+        [digitos] if digitos.len() == 6 && digitos.bytes().all(|byte| byte.is_ascii_digit()) => {
+            // `MMAAAA` tem precedência; `AAAAMM` vale quando os dois primeiros dígitos não
+            // formam um mês.
+            valida(numero(&digitos[2..], 4, 4)?, numero(&digitos[..2], 2, 2)?)
+                .or_else(|| valida(numero(&digitos[..4], 4, 4)?, numero(&digitos[4..], 2, 2)?))
+        }
+        _ => None,
+    }
+}
+
+fn numero(texto: &str, minimo: usize, maximo: usize) -> Option<u32> {
+    let texto = texto.trim();
+    if texto.len() < minimo || texto.len() > maximo || !texto.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    texto.parse().ok()
+}
+
+fn valida(ano: u32, mes: u32) -> Option<(u32, u32)> {
+    ((1..=12).contains(&mes) && (1900..=2999).contains(&ano)).then_some((ano, mes))
+}
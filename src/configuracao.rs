@@ -0,0 +1,234 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Nome do arquivo de configuração procurado na pasta atual e na pasta de configuração do usuário.
+pub const ARQUIVO_CONFIGURACAO: &str = "converterxmlcsv.toml";
+
+/// Valores padrão lidos de `converterxmlcsv.toml`.
+///
+/// As chaves têm os mesmos nomes das opções de linha de comando (sem `--`), e as opções
+/// informadas na linha de comando prevalecem sobre as do arquivo. Exemplo:
+///
+/// ```toml
+/// delimiter = ","
+/// format = "xlsx"
+/// require-fields = ["CPF", "MesAno"]
+///
+/// [cambio]
+/// USD = 5.10
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Configuracao {
+    pub delimiter: Option<String>,
+    pub quote_style: Option<String>,
+    pub crlf: Option<bool>,
+    pub output_encoding: Option<String>,
+    pub format: Option<String>,
+    #[serde(rename = "type")]
+    pub tipo: Option<String>,
+    pub max_rows_per_file: Option<u64>,
+    pub with_totals: Option<bool>,
+    pub no_header: Option<bool>,
+    pub header_names: Option<String>,
+    pub jobs: Option<u64>,
+    pub cambio: HashMap<String, f64>,
+    pub truncate_razao: Option<usize>,
+    pub truncate_fantasia: Option<usize>,
+    pub truncate_ellipsis: Option<bool>,
+    pub trim: Option<bool>,
+    pub collapse_whitespace: Option<bool>,
+    pub doc_format: Option<String>,
+    pub keep_original: Option<bool>,
+    pub keep_raw_valor: Option<bool>,
+    pub currency_column: Option<bool>,
+    pub require_fields: Option<Vec<String>>,
+    pub require_fields_warn: Option<bool>,
+    pub after_hook: Option<String>,
+    pub after_hook_strict: Option<bool>,
+    pub manifest: Option<PathBuf>,
+    pub zip_output: Option<PathBuf>,
+    pub recursive: Option<bool>,
+    pub archive: Option<PathBuf>,
+    pub archive_copy: Option<bool>,
+    pub reconvert: Option<bool>,
+    pub out_dir: Option<PathBuf>,
+    pub name_template: Option<String>,
+    pub done_dir: Option<PathBuf>,
+    pub summary: Option<PathBuf>,
+    pub pdf: Option<PathBuf>,
+    pub report: Option<String>,
+    pub report_file: Option<PathBuf>,
+    pub errors_file: Option<PathBuf>,
+    pub strict: Option<bool>,
+    pub strict_values: Option<bool>,
+    pub decimal: Option<String>,
+    pub out_decimal: Option<String>,
+    pub money_format: Option<String>,
+    pub split_mesano: Option<bool>,
+    pub anonymize: Option<String>,
+    pub anonymize_salt: Option<String>,
+    pub quiet: Option<bool>,
+    pub pause: Option<bool>,
+    pub open: Option<bool>,
+    pub no_progress: Option<bool>,
+    pub no_color: Option<bool>,
+    pub verbose: Option<u8>,
+    pub log_file: Option<PathBuf>,
+    pub generic: Option<bool>,
+    pub mapping: Option<PathBuf>,
+    pub template: Option<PathBuf>,
+    pub layout: Option<PathBuf>,
+    pub xsd: Option<PathBuf>,
+    pub split_by_empresa: Option<bool>,
+    pub split_by: Option<String>,
+    pub columns: Option<Vec<String>>,
+    pub merge: Option<PathBuf>,
+    pub dedupe: Option<String>,
+    pub dedupe_strategy: Option<String>,
+    pub filter_cpf: Option<Vec<String>>,
+    pub cnpj: Option<Vec<String>>,
+    pub min_valor: Option<f64>,
+    pub max_valor: Option<f64>,
+    pub sort: Option<Vec<String>>,
+    pub computed: Option<Vec<String>>,
+    pub force: Option<bool>,
+    pub no_clobber_rename: Option<bool>,
+    pub email_to: Option<Vec<String>>,
+    pub cron: Option<String>,
+    pub lang: Option<String>,
+    pub sftp: ConfiguracaoSftp,
+    pub smtp: ConfiguracaoSmtp,
+    pub http: ConfiguracaoHttp,
+}
+
+/// Servidor de e-mail de `--email-to`, na seção `[smtp]`:
+///
+/// ```toml
+/// [smtp]
+/// host = "smtp.empresa.com.br"
+/// user = "conversor@empresa.com.br"
+/// password = "..."
+/// from = "conversor@empresa.com.br"
+/// subject = "Folha {mesano}: {funcionarios} funcionário(s), total {total}"
+/// ```
+///
+/// `tls` é `starttls` (o padrão, na porta 587), `smtps` (porta 465) ou `none`. A senha
+/// também pode vir da variável `SMTP_PASSWORD`, que prevalece sobre a do arquivo.
+#[derive(Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfiguracaoSmtp {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub from: Option<String>,
+    pub tls: Option<String>,
+    pub subject: Option<String>,
+}
+
+// Sem a senha, para que ela não apareça em mensagens de depuração.
+impl std::fmt::Debug for ConfiguracaoSmtp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ConfiguracaoSmtp")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("from", &self.from)
+            .field("tls", &self.tls)
+            .field("subject", &self.subject)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Servidor SFTP de `fetch`, na seção `[sftp]`:
+///
+/// ```toml
+/// [sftp]
+/// host = "sftp.fornecedor.com.br"
+/// user = "empresa"
+/// identity-file = "~/.ssh/fornecedor"
+/// remote-dir = "/exportacoes"
+/// upload-dir = "/retorno"
+/// ```
+///
+/// A autenticação é feita pelo `ssh`, com a chave de `identity-file`, o agente ou o
+/// `~/.ssh/config`; senhas não são aceitas, pois não há como informá-las sem interação.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfiguracaoSftp {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_file: Option<PathBuf>,
+    pub remote_dir: Option<String>,
+    pub upload_dir: Option<String>,
+}
+
+/// Credenciais dos XML informados como endereços `http://` ou `https://`, na seção `[http]`:
+///
+/// ```toml
+/// [http]
+/// user = "integracao"
+/// password = "..."
+/// headers = ["X-Api-Key: ..."]
+/// ```
+///
+/// `user` e `password` são enviados como autenticação básica; a senha também pode vir da
+/// variável `HTTP_PASSWORD`, que prevalece sobre a do arquivo.
+#[derive(Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfiguracaoHttp {
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub headers: Option<Vec<String>>,
+}
+
+// Sem a senha nem os cabeçalhos, que podem conter tokens.
+impl std::fmt::Debug for ConfiguracaoHttp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ConfiguracaoHttp").field("user", &self.user).finish_non_exhaustive()
+    }
+}
+
+impl Configuracao {
+    /// Lê o arquivo informado em `--config` ou, se nenhum for informado, o primeiro
+    /// encontrado entre a pasta atual e a pasta de configuração do usuário
+    /// (`$XDG_CONFIG_HOME/converterxmlcsv`, `~/.config/converterxmlcsv` ou `%APPDATA%\converterxmlcsv`).
+    ///
+    /// Sem arquivo de configuração, todos os valores ficam vazios.
+    pub fn carregar(explicito: Option<&Path>) -> Result<Configuracao, Box<dyn Error>> {
+        let caminho = match explicito {
+            Some(caminho) => Some(caminho.to_path_buf()),
+            None => candidatos().into_iter().find(|caminho| caminho.is_file()),
+        };
+        let Some(caminho) = caminho else {
+            return Ok(Configuracao::default());
+        };
+
+        let conteudo = fs::read_to_string(&caminho)
+            .map_err(|erro| tr!("Não foi possível ler o arquivo de configuração '{}': {}", caminho.display(), erro))?;
+        let configuracao = toml::from_str(&conteudo)
+            .map_err(|erro| tr!("Arquivo de configuração '{}' inválido: {}", caminho.display(), erro))?;
+        Ok(configuracao)
+    }
+}
+
+/// Locais onde o arquivo de configuração é procurado, em ordem de prioridade.
+fn candidatos() -> Vec<PathBuf> {
+    let mut candidatos = vec![PathBuf::from(ARQUIVO_CONFIGURACAO)];
+
+    let pasta_usuario = env::var_os("XDG_CONFIG_HOME")
+        .filter(|pasta| !pasta.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from));
+    if let Some(pasta) = pasta_usuario {
+        candidatos.push(pasta.join("converterxmlcsv").join(ARQUIVO_CONFIGURACAO));
+    }
+    candidatos
+}
@@ -0,0 +1,87 @@
+use colored::*;
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use converterxmlcsv::saida::{novo_writer, writer_continuacao};
+use converterxmlcsv::{Destino, ErroConversor, FormatoCsv};
+
+/// Coluna do CSV consolidado com o XML de origem de cada linha.
+pub const COLUNA_ORIGEM: &str = "ArquivoOrigem";
+
+/// Linhas convertidas de um XML, guardadas para serem acrescentadas ao CSV de `--merge`.
+#[derive(Debug, Default)]
+pub struct LinhasConvertidas {
+    cabecalho: Vec<String>,
+    linhas: Vec<Vec<String>>,
+}
+
+impl Destino for LinhasConvertidas {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.cabecalho = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        self.linhas.push(registro.iter().map(|valor| valor.to_string()).collect());
+        Ok(())
+    }
+}
+
+/// Acrescenta as linhas de `origem` ao CSV consolidado, retornando quantas foram gravadas.
+///
+/// Um CSV novo recebe o cabeçalho do primeiro XML, precedido de `ArquivoOrigem`. Em um CSV
+/// existente (de outro XML ou de uma execução anterior) as linhas são gravadas sob o
+/// cabeçalho dele, coluna a coluna pelo nome; as colunas que ele não possui são descartadas
+/// com um aviso em `console`.
+pub fn anexar_consolidado(
+    caminho: &Path,
+    origem: &Path,
+    convertidas: &LinhasConvertidas,
+    formato: &FormatoCsv,
+    console: &mut dyn Write,
+) -> Result<usize, Box<dyn Error>> {
+    if convertidas.linhas.is_empty() {
+        return Ok(0);
+    }
+
+    let existente = caminho.metadata().is_ok_and(|metadados| metadados.len() > 0);
+    let cabecalho: Vec<String> = if existente {
+        let mut reader = csv::ReaderBuilder::new().delimiter(formato.delimitador).from_path(caminho)?;
+        reader.headers()?.iter().map(str::to_string).collect()
+    } else {
+        std::iter::once(COLUNA_ORIGEM.to_string()).chain(convertidas.cabecalho.iter().cloned()).collect()
+    };
+
+    let descartadas: Vec<&str> = convertidas
+        .cabecalho
+        .iter()
+        .filter(|coluna| !cabecalho.contains(coluna))
+        .map(String::as_str)
+        .collect();
+    if !descartadas.is_empty() {
+        writeln!(console, "{}", tr!("Atenção: {} não possui as colunas {}; elas não foram gravadas para {}.", caminho.display(), descartadas.join(", "), origem.display()).bright_yellow())?;
+    }
+
+    let mut writer = if existente {
+        writer_continuacao(OpenOptions::new().append(true).open(caminho)?, formato)
+    } else {
+        let mut writer = novo_writer(File::create(caminho)?, formato);
+        writer.write_record(&cabecalho)?;
+        writer
+    };
+
+    let origem = origem.display().to_string();
+    let indices: Vec<Option<usize>> = cabecalho.iter().map(|coluna| convertidas.cabecalho.iter().position(|nome| nome == coluna)).collect();
+    for linha in &convertidas.linhas {
+        let registro = cabecalho.iter().zip(&indices).map(|(coluna, indice)| match indice {
+            _ if coluna == COLUNA_ORIGEM => origem.as_str(),
+            Some(indice) => linha.get(*indice).map_or("", String::as_str),
+            None => "",
+        });
+        writer.write_record(registro)?;
+    }
+    writer.flush()?;
+    Ok(convertidas.linhas.len())
+}
@@ -0,0 +1,327 @@
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::anonimizacao::DestinoAnonimo;
+use crate::avisos::Avisos;
+use crate::documento::DocumentoInvalido;
+use crate::duplicados::deduplicar;
+use crate::erro::ErroConversor;
+use crate::leitura::{abrir_xml, ArquivoXml};
+use crate::modelo::{Empresa, Funcionario, Tipo, TipoArquivo};
+use crate::opcoes::Opcoes;
+use crate::ordenacao::ordenar;
+use crate::saida::Destino;
+use crate::tipos::{self, LeitorTipo};
+use crate::valor::{Quantia, ValorNaoNumerico};
+
+/// Resultado da conversão de uma empresa ou, somados com [`Resumo::somar`], de todas as
+/// empresas de um XML; nesse caso os dados da empresa são os da primeira.
+#[derive(Debug)]
+pub struct Resumo {
+    pub tipo: Tipo,
+    /// Quantidade de `<Empresa>` convertidas.
+    pub empresas: usize,
+    pub fantasia: String,
+    pub razao: String,
+    pub cnpj: String,
+    /// Competência (`MesAno`) da primeira empresa do XML.
+    pub mes_ano: String,
+    /// Quantidade de funcionários no XML; zero quando nenhuma linha foi gravada.
+    pub funcionarios: usize,
+    /// Linhas gravadas, que podem ser menos que os funcionários com `delta`.
+    pub linhas: usize,
+    /// Soma dos valores, convertidos para reais.
+    pub total_valor: Quantia,
+    /// Soma das metas (apenas comissões).
+    pub total_meta: Quantia,
+    /// Soma dos valores, em reais, por parcela (apenas décimo terceiro), na ordem em que
+    /// as parcelas aparecem no XML.
+    pub totais_parcela: Vec<(String, Quantia)>,
+    pub avisos: Avisos,
+    /// CPFs e CNPJ cujos dígitos verificadores não conferem.
+    pub documentos_invalidos: Vec<DocumentoInvalido>,
+    /// Valores gravados como vieram, mas somados como zero por não serem números.
+    pub valores_invalidos: Vec<ValorNaoNumerico>,
+    /// Funcionários repetidos removidos por `--dedupe`.
+    pub duplicados: usize,
+    /// Funcionários que não atendem aos filtros e não foram gravados.
+    pub fora_do_filtro: usize,
+}
+
+impl Resumo {
+    fn new(tipo: Tipo, empresa: &Empresa) -> Resumo {
+        Resumo {
+            tipo,
+            empresas: 1,
+            fantasia: empresa.fantasia.clone(),
+            razao: empresa.razao.clone(),
+            cnpj: empresa.cnpj.clone(),
+            mes_ano: empresa.mes_ano.clone(),
+            funcionarios: 0,
+            linhas: 0,
+            total_valor: Quantia::ZERO,
+            total_meta: Quantia::ZERO,
+            totais_parcela: Vec::new(),
+            avisos: Avisos::default(),
+            documentos_invalidos: Vec::new(),
+            valores_invalidos: Vec::new(),
+            duplicados: 0,
+            fora_do_filtro: 0,
+        }
+    }
+
+    /// Acumula o resultado de outra empresa do mesmo XML.
+    pub fn somar(&mut self, outro: Resumo) {
+        self.empresas += outro.empresas;
+        self.funcionarios += outro.funcionarios;
+        self.linhas += outro.linhas;
+        self.total_valor += outro.total_valor;
+        self.total_meta += outro.total_meta;
+        for (parcela, valor) in outro.totais_parcela {
+            match self.totais_parcela.iter_mut().find(|(nome, _)| *nome == parcela) {
+                Some((_, total)) => *total += valor,
+                None => self.totais_parcela.push((parcela, valor)),
+            }
+        }
+        self.avisos.juntar(outro.avisos);
+        self.documentos_invalidos.extend(outro.documentos_invalidos);
+        self.valores_invalidos.extend(outro.valores_invalidos);
+        self.duplicados += outro.duplicados;
+        self.fora_do_filtro += outro.fora_do_filtro;
+    }
+
+    /// Guarda o CPF ou CNPJ inválido, também contado no resumo de avisos.
+    fn documento_invalido(&mut self, documento: Option<DocumentoInvalido>) {
+        let Some(documento) = documento else {
+            return;
+        };
+        let categoria = if documento.funcionario.is_some() { "CPFs inválidos" } else { "CNPJs inválidos" };
+        self.avisos.registrar(categoria, documento.to_string());
+        self.documentos_invalidos.push(documento);
+    }
+}
+
+/// Repassa o cabeçalho só da primeira empresa, para que as linhas de todas as empresas de
+/// um XML fiquem sob um único cabeçalho.
+struct CabecalhoUnico<'a> {
+    destino: &'a mut dyn Destino,
+    iniciado: bool,
+}
+
+impl Destino for CabecalhoUnico<'_> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        if !self.iniciado {
+            self.iniciado = true;
+            self.destino.iniciar(cabecalho)?;
+        }
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        self.destino.gravar(registro)
+    }
+}
+
+/// Converte o arquivo já desserializado, gravando as linhas de todas as empresas em `destino`.
+pub fn converter_empresa(arquivo: TipoArquivo, opcoes: &Opcoes, destino: &mut dyn Destino) -> Result<Resumo, ErroConversor> {
+    let (tipo, empresas) = match arquivo {
+        TipoArquivo::Comissao(comissao) => (Tipo::Comissao, comissao.empresas),
+        TipoArquivo::Vales(vale) => (Tipo::Vales, vale.empresas),
+        TipoArquivo::Ferias(ferias) => (Tipo::Ferias, ferias.empresas),
+        TipoArquivo::DecimoTerceiro(decimo) => (Tipo::DecimoTerceiro, decimo.empresas),
+    };
+    let mut destino = CabecalhoUnico { destino, iniciado: false };
+    let mut total: Option<Resumo> = None;
+    for mut empresa in empresas {
+        let funcionarios = empresa.funcionarios.take().unwrap_or_default();
+        let resumo = converter_funcionarios(tipo, &empresa, funcionarios.into_iter().map(Ok), opcoes, &mut destino)?;
+        match &mut total {
+            Some(total) => total.somar(resumo),
+            None => total = Some(resumo),
+        }
+    }
+    total.ok_or_else(|| ErroConversor::EntradaInvalida(tr!("O XML não possui o elemento <Empresa>.").to_string()))
+}
+
+/// Converte os funcionários de todas as empresas do XML aberto, gravando-os em `destino`
+/// sob um único cabeçalho.
+pub fn converter_empresas<R: BufRead>(mut arquivo: ArquivoXml<R>, opcoes: &Opcoes, destino: &mut dyn Destino) -> Result<Resumo, ErroConversor> {
+    let mut destino = CabecalhoUnico { destino, iniciado: false };
+    let mut resumo = converter_funcionarios(arquivo.tipo, &arquivo.empresa, arquivo.funcionarios.by_ref(), opcoes, &mut destino)?;
+    while arquivo.proxima_empresa()? {
+        let outra = converter_funcionarios(arquivo.tipo, &arquivo.empresa, arquivo.funcionarios.by_ref(), opcoes, &mut destino)?;
+        resumo.somar(outra);
+    }
+    Ok(resumo)
+}
+
+/// Lê o XML em fluxo e converte os funcionários à medida que são lidos, mantendo o uso de
+/// memória constante mesmo em arquivos muito grandes.
+///
+/// Com `opcoes.tipo` igual a `None`, o tipo é detectado pelo conteúdo ou por `caminho`.
+pub fn converter_xml<R: BufRead>(reader: R, caminho: Option<&Path>, opcoes: &Opcoes, destino: &mut dyn Destino) -> Result<Resumo, ErroConversor> {
+    let arquivo = abrir_xml(reader, caminho, opcoes.tipo)?;
+    converter_empresas(arquivo, opcoes, destino)
+}
+
+/// Converte os funcionários da empresa, gravando cada linha em `destino` assim que ela é lida.
+///
+/// Como as linhas são gravadas durante a leitura, um erro (XML inválido ou campos exigidos
+/// ausentes, verificados ao final) pode acontecer depois de parte delas já ter sido gravada.
+///
+/// Com `opcoes.deduplicar` ou `opcoes.ordenar`, os funcionários da empresa são lidos todos
+/// antes da conversão, pois a última ocorrência de um CPF pode mudar a linha da primeira e a
+/// ordem só é conhecida ao final. A ordenação vale dentro de cada empresa.
+pub fn converter_funcionarios<I>(tipo: Tipo, empresa: &Empresa, funcionarios: I, opcoes: &Opcoes, destino: &mut dyn Destino) -> Result<Resumo, ErroConversor>
+where
+    I: Iterator<Item = Result<Funcionario, ErroConversor>>,
+{
+    log::debug!("Convertendo a empresa {} (CNPJ {}) do tipo {}", empresa.fantasia, empresa.cnpj, tipo.nome());
+    let mut fora_do_filtro = 0;
+    let funcionarios = funcionarios.filter(|funcionario| match funcionario {
+        Ok(funcionario) if !opcoes.filtro.aceita(empresa, funcionario, opcoes) => {
+            fora_do_filtro += 1;
+            false
+        }
+        _ => true,
+    });
+
+    let mut resumo = if opcoes.deduplicar.is_none() && opcoes.ordenar.is_empty() {
+        converter_linhas(tipo, empresa, funcionarios, opcoes, destino)?
+    } else {
+        let mut todos = funcionarios.collect::<Result<Vec<_>, _>>()?;
+        let mut repetidos = Vec::new();
+        let mut duplicados = 0;
+        if let Some(estrategia) = opcoes.deduplicar {
+            let lidos = todos.len();
+            (todos, repetidos) = deduplicar(todos, estrategia, opcoes)?;
+            duplicados = lidos - todos.len();
+        }
+        ordenar(&mut todos, &opcoes.ordenar, opcoes);
+        let mut resumo = converter_linhas(tipo, empresa, todos.into_iter().map(Ok), opcoes, destino)?;
+        for repetido in repetidos {
+            resumo.avisos.registrar("CPFs duplicados", repetido);
+        }
+        resumo.duplicados = duplicados;
+        resumo
+    };
+    resumo.fora_do_filtro = fora_do_filtro;
+    Ok(resumo)
+}
+
+fn converter_linhas<I>(tipo: Tipo, empresa: &Empresa, funcionarios: I, opcoes: &Opcoes, destino: &mut dyn Destino) -> Result<Resumo, ErroConversor>
+where
+    I: Iterator<Item = Result<Funcionario, ErroConversor>>,
+{
+    let mut anonimo;
+    let destino: &mut dyn Destino = match &opcoes.anonimizar {
+        Some(anonimizador) => {
+            anonimo = DestinoAnonimo { destino, anonimizador, colunas: Vec::new() };
+            &mut anonimo
+        }
+        None => destino,
+    };
+    let mut selecao;
+    let destino: &mut dyn Destino = if opcoes.colunas.is_empty() {
+        destino
+    } else {
+        selecao = SelecaoColunas { destino, colunas: &opcoes.colunas, indices: Vec::new() };
+        &mut selecao
+    };
+
+    handle_arquivo(tipos::leitor(tipo), empresa, funcionarios, opcoes, destino)
+}
+
+/// Grava apenas as colunas de `--columns`, na ordem pedida. As que não existem no tipo do
+/// XML (ex.: `MetaPremio` em vales) ficam vazias, para que a mesma lista sirva a todos os tipos.
+struct SelecaoColunas<'a> {
+    destino: &'a mut dyn Destino,
+    colunas: &'a [String],
+    /// Posição de cada coluna pedida no cabeçalho completo.
+    indices: Vec<Option<usize>>,
+}
+
+impl Destino for SelecaoColunas<'_> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.indices = self.colunas.iter().map(|coluna| cabecalho.iter().position(|nome| nome == coluna)).collect();
+        self.destino.iniciar(&self.colunas.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        let selecionado: Vec<&str> = self
+            .indices
+            .iter()
+            .map(|indice| indice.and_then(|indice| registro.get(indice).copied()).unwrap_or(""))
+            .collect();
+        self.destino.gravar(&selecionado)
+    }
+}
+
+fn handle_arquivo<I>(leitor: &dyn LeitorTipo, empresa: &Empresa, funcionarios: I, opcoes: &Opcoes, destino: &mut dyn Destino) -> Result<Resumo, ErroConversor>
+where
+    I: Iterator<Item = Result<Funcionario, ErroConversor>>,
+{
+    let mut resumo = Resumo::new(leitor.tipo(), empresa);
+
+    // Verifica se a empresa possui funcionários
+    let mut funcionarios = funcionarios.peekable();
+    if funcionarios.peek().is_none() {
+        return Ok(resumo);
+    }
+    let mut pendencias = Vec::new();
+    resumo.documento_invalido(DocumentoInvalido::verificar_cnpj(&empresa.cnpj));
+
+    let fantasia = opcoes.truncar("Fantasia", &opcoes.espacos(&empresa.fantasia), opcoes.truncar_fantasia, &mut resumo.avisos);
+    let razao = opcoes.truncar("Razao", &opcoes.espacos(&empresa.razao), opcoes.truncar_razao, &mut resumo.avisos);
+    let cnpj = opcoes.cnpj(&empresa.cnpj);
+
+    // Escreve o cabeçalho no arquivo CSV
+    let mut cabecalho = vec!["Fantasia", "Razao", "CNPJ", "MesAno", "CPF"];
+    cabecalho.extend(leitor.colunas_antes());
+    cabecalho.push("Valor");
+    cabecalho.extend(leitor.colunas_depois());
+    cabecalho.extend(opcoes.colunas_moeda());
+    cabecalho.extend(opcoes.colunas_competencia());
+    cabecalho.extend(opcoes.colunas_originais());
+    cabecalho.extend(opcoes.colunas_calculadas());
+    cabecalho.extend(opcoes.colunas_delta());
+    destino.iniciar(&cabecalho)?;
+
+    // Itera sobre os funcionários da empresa e escreve seus dados no arquivo CSV
+    for (indice, funcionario) in funcionarios.enumerate() {
+        let funcionario = funcionario?;
+        pendencias.extend(opcoes.pendencia_campos_exigidos(indice, empresa, &funcionario));
+        resumo.documento_invalido(DocumentoInvalido::verificar_cpf(indice, &funcionario.cpf));
+        for valor in opcoes.valores_nao_numericos(indice, &funcionario) {
+            resumo.avisos.registrar("valores não numéricos", valor.to_string());
+            resumo.valores_invalidos.push(valor);
+        }
+
+        let valor_em_reais = opcoes.valor_em_reais(&funcionario, &mut resumo.avisos);
+        resumo.total_valor += valor_em_reais;
+        resumo.funcionarios += 1;
+        leitor.somar(&mut resumo, &funcionario, valor_em_reais, opcoes);
+
+        let valor = opcoes.texto_numero(&funcionario.valor.valor);
+        let cpf = opcoes.cpf(&funcionario.cpf);
+        let (antes, depois) = leitor.valores(&funcionario, opcoes);
+        let mut registro = vec![fantasia.as_str(), razao.as_str(), &cnpj, funcionario.mes_ano(empresa), &cpf];
+        registro.extend(antes.iter().map(|valor| valor.as_ref()));
+        registro.push(valor.as_str());
+        registro.extend(depois.iter().map(|valor| valor.as_ref()));
+        registro.extend(opcoes.valores_moeda(&funcionario));
+        registro.extend(opcoes.valores_competencia(funcionario.mes_ano(empresa)));
+        registro.extend(opcoes.valores_originais(empresa, &funcionario));
+        let calculados = opcoes.valores_calculados(&funcionario, &cabecalho, &registro);
+        registro.extend(calculados.iter().map(String::as_str));
+        if opcoes.aplicar_delta(&cabecalho, &mut registro) {
+            destino.gravar(&registro)?;
+            resumo.linhas += 1;
+        }
+    }
+
+    opcoes.verificar_campos_exigidos(pendencias, &mut resumo.avisos)?;
+    opcoes.verificar_documentos(&resumo.documentos_invalidos)?;
+    opcoes.verificar_valores(&resumo.valores_invalidos)?;
+    Ok(resumo)
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use converterxmlcsv::sha256::sha256_arquivo;
+
+/// Arquivo, na pasta atual, com os XML já convertidos por `--all`, `watch` e `schedule`.
+pub const ARQUIVO_CONVERTIDOS: &str = ".converterxmlcsv-convertidos";
+
+/// Conversão registrada de um XML para um destino.
+struct Conversao {
+    sha256: String,
+    /// Arquivos gerados; se algum deles foi apagado, o XML é convertido de novo.
+    saidas: Vec<PathBuf>,
+}
+
+/// XML convertidos com sucesso em execuções anteriores, pelo caminho absoluto, pelo destino
+/// (formato e pasta dos arquivos gerados) e pelo SHA-256 do conteúdo, para que sejam
+/// ignorados até serem alterados, até os arquivos gerados serem apagados ou até
+/// `--reconvert`.
+///
+/// Cada linha do arquivo é `SHA256<tab>destino<tab>caminho<tab>saídas...`; as novas
+/// conversões são acrescentadas ao final e, para um mesmo caminho e destino, vale a última
+/// linha.
+pub struct Convertidos {
+    caminho: PathBuf,
+    arquivos: HashMap<(PathBuf, String), Conversao>,
+}
+
+impl Convertidos {
+    /// Lê o arquivo de estado; sem ele, nenhum XML foi convertido ainda.
+    pub fn carregar(caminho: &Path) -> io::Result<Convertidos> {
+        let conteudo = match fs::read_to_string(caminho) {
+            Ok(conteudo) => conteudo,
+            Err(erro) if erro.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(erro) => return Err(erro),
+        };
+        let arquivos = conteudo
+            .lines()
+            .filter_map(|linha| {
+                let mut campos = linha.split('\t');
+                let (sha256, destino, arquivo) = (campos.next()?, campos.next()?, campos.next()?);
+                let conversao = Conversao { sha256: sha256.to_string(), saidas: campos.map(PathBuf::from).collect() };
+                Some(((PathBuf::from(arquivo), destino.to_string()), conversao))
+            })
+            .collect();
+        Ok(Convertidos { caminho: caminho.to_path_buf(), arquivos })
+    }
+
+    /// Indica se `arquivo` já foi convertido para `destino` com o conteúdo atual e se os
+    /// arquivos gerados ainda existem.
+    pub fn contem(&self, arquivo: &Path, destino: &str) -> io::Result<bool> {
+        match self.arquivos.get(&(fs::canonicalize(arquivo)?, destino.to_string())) {
+            Some(conversao) => Ok(conversao.saidas.iter().all(|saida| saida.exists()) && conversao.sha256 == sha256_arquivo(arquivo)?),
+            None => Ok(false),
+        }
+    }
+
+    /// Registra a conversão de `arquivo` para `destino`, com os arquivos gerados, antes de
+    /// ele ser movido por `--archive`.
+    pub fn registrar(&mut self, arquivo: &Path, destino: &str, saidas: &[PathBuf]) -> io::Result<()> {
+        let caminho = fs::canonicalize(arquivo)?;
+        let sha256 = sha256_arquivo(arquivo)?;
+        let saidas: Vec<PathBuf> = saidas.iter().map(|saida| fs::canonicalize(saida).unwrap_or_else(|_| saida.clone())).collect();
+        let mut linha = format!("{}\t{}\t{}", sha256, destino, caminho.display());
+        for saida in &saidas {
+            linha.push('\t');
+            linha.push_str(&saida.display().to_string());
+        }
+        writeln!(OpenOptions::new().append(true).create(true).open(&self.caminho)?, "{}", linha)?;
+        self.arquivos.insert((caminho, destino.to_string()), Conversao { sha256, saidas });
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use converterxmlcsv::{Destino, ErroConversor};
+
+/// Coluna de `--split-by` cujo valor separa as linhas de um XML em arquivos próprios.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Divisao {
+    Cnpj,
+    MesAno,
+}
+
+impl Divisao {
+    /// Nomes aceitos por `--split-by`; `empresa` equivale a `--split-by-empresa`.
+    pub const NOMES: [&'static str; 3] = ["empresa", "cnpj", "mes_ano"];
+
+    /// `None` também para `empresa`, que é tratada por `--split-by-empresa`.
+    pub fn from_nome(nome: &str) -> Option<Divisao> {
+        match nome.to_lowercase().as_str() {
+            "cnpj" => Some(Divisao::Cnpj),
+            "mes_ano" | "mesano" => Some(Divisao::MesAno),
+            _ => None,
+        }
+    }
+
+    fn coluna(self) -> &'static str {
+        match self {
+            Divisao::Cnpj => "CNPJ",
+            Divisao::MesAno => "MesAno",
+        }
+    }
+
+    /// Trecho acrescentado ao nome do arquivo do grupo: os dígitos do CNPJ ou o `MesAno`
+    /// com `-` no lugar da barra.
+    fn sufixo(self, valor: &str) -> String {
+        let sufixo: String = match self {
+            Divisao::Cnpj => valor.chars().filter(char::is_ascii_digit).collect(),
+            Divisao::MesAno => valor.trim().chars().map(|letra| if letra.is_alphanumeric() { letra } else { '-' }).collect(),
+        };
+        if sufixo.is_empty() {
+            format!("sem-{}", self.coluna().to_lowercase())
+        } else {
+            sufixo
+        }
+    }
+}
+
+/// Destino de `--split-by`: guarda as linhas separadas pelo valor da coluna, na ordem em que
+/// cada valor aparece pela primeira vez no XML. Valores escritos de formas diferentes que
+/// resultam no mesmo nome de arquivo ficam no mesmo grupo.
+pub struct LinhasPorGrupo {
+    divisao: Divisao,
+    indice: usize,
+    cabecalho: Vec<String>,
+    grupos: Vec<(String, Vec<Vec<String>>)>,
+}
+
+impl LinhasPorGrupo {
+    pub fn new(divisao: Divisao) -> LinhasPorGrupo {
+        LinhasPorGrupo { divisao, indice: 0, cabecalho: Vec::new(), grupos: Vec::new() }
+    }
+
+    /// Sufixo do nome do arquivo de cada grupo, de [`Divisao::sufixo`].
+    pub fn sufixos(&self) -> impl Iterator<Item = &str> {
+        self.grupos.iter().map(|(sufixo, _)| sufixo.as_str())
+    }
+
+    /// Grava em `destino` o cabeçalho e as linhas do grupo de posição `grupo`.
+    pub fn gravar_grupo(&self, grupo: usize, destino: &mut dyn Destino) -> Result<(), ErroConversor> {
+        let cabecalho: Vec<&str> = self.cabecalho.iter().map(String::as_str).collect();
+        destino.iniciar(&cabecalho)?;
+        for linha in &self.grupos[grupo].1 {
+            let registro: Vec<&str> = linha.iter().map(String::as_str).collect();
+            destino.gravar(&registro)?;
+        }
+        Ok(())
+    }
+
+    /// Quantidade de linhas do grupo de posição `grupo`.
+    pub fn linhas(&self, grupo: usize) -> usize {
+        self.grupos[grupo].1.len()
+    }
+}
+
+impl Destino for LinhasPorGrupo {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        let coluna = self.divisao.coluna();
+        self.indice = cabecalho.iter().position(|nome| *nome == coluna).ok_or_else(|| {
+            ErroConversor::EntradaInvalida(tr!("A saída não possui a coluna {} usada em --split-by (verifique --columns).", coluna))
+        })?;
+        self.cabecalho = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        let sufixo = self.divisao.sufixo(registro.get(self.indice).copied().unwrap_or_default());
+        let linha = registro.iter().map(|valor| valor.to_string()).collect();
+        match self.grupos.iter_mut().find(|(atual, _)| *atual == sufixo) {
+            Some((_, linhas)) => linhas.push(linha),
+            None => self.grupos.push((sufixo, vec![linha])),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+
+/// CPF ou CNPJ cujos dígitos verificadores não conferem.
+#[derive(Debug, Clone)]
+pub struct DocumentoInvalido {
+    /// Posição do funcionário no XML (a partir de 1); `None` para o CNPJ da empresa.
+    pub funcionario: Option<usize>,
+    /// `CPF` ou `CNPJ`.
+    pub campo: &'static str,
+    pub valor: String,
+}
+
+impl DocumentoInvalido {
+    /// Registro do CNPJ da empresa, quando ele é inválido.
+    pub fn verificar_cnpj(cnpj: &str) -> Option<DocumentoInvalido> {
+        (!cnpj_valido(cnpj)).then(|| DocumentoInvalido { funcionario: None, campo: "CNPJ", valor: cnpj.to_string() })
+    }
+
+    /// Registro do CPF do funcionário da posição `indice` (a partir de 0), quando ele é inválido.
+    pub fn verificar_cpf(indice: usize, cpf: &str) -> Option<DocumentoInvalido> {
+        (!cpf_valido(cpf)).then(|| DocumentoInvalido { funcionario: Some(indice + 1), campo: "CPF", valor: cpf.to_string() })
+    }
+}
+
+impl fmt::Display for DocumentoInvalido {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.funcionario {
+            Some(posicao) => write!(f, "{}", tr!("funcionário {}: {} inválido '{}'", posicao, self.campo, self.valor)),
+            None => write!(f, "{}", tr!("empresa: {} inválido '{}'", self.campo, self.valor)),
+        }
+    }
+}
+
+/// Escrita de CPF e CNPJ de `--doc-format`, a mesma em todo o arquivo, seja qual for a do XML.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormatoDocumento {
+    /// Só os dígitos: `12345678900`.
+    Digitos,
+    /// Com a máscara: `123.456.789-00` e `12.345.678/0001-99`.
+    Mascara,
+}
+
+impl FormatoDocumento {
+    /// Nomes aceitos por `--doc-format`.
+    pub const NOMES: [&'static str; 2] = ["digits", "masked"];
+
+    pub fn from_nome(nome: &str) -> Option<FormatoDocumento> {
+        match nome.to_lowercase().as_str() {
+            "digits" => Some(FormatoDocumento::Digitos),
+            "masked" => Some(FormatoDocumento::Mascara),
+            _ => None,
+        }
+    }
+
+    pub fn formatar_cpf(self, cpf: &str) -> String {
+        self.formatar(cpf, 11)
+    }
+
+    pub fn formatar_cnpj(self, cnpj: &str) -> String {
+        self.formatar(cnpj, 14)
+    }
+
+    /// `documento` no formato escolhido. Faltando dígitos (os zeros à esquerda que se perdem
+    /// quando o ERP guarda o documento como número), ele é completado com zeros; um texto com
+    /// letras, vazio ou com dígitos demais fica como veio.
+    fn formatar(self, documento: &str, tamanho: usize) -> String {
+        let formatacao = |c: char| matches!(c, '.' | '-' | '/' | ' ');
+        let digitos: String = documento.chars().filter(char::is_ascii_digit).collect();
+        if digitos.is_empty() || digitos.len() > tamanho || documento.chars().any(|c| !c.is_ascii_digit() && !formatacao(c)) {
+            return documento.to_string();
+        }
+        let d: Vec<char> = format!("{:0>1$}", digitos, tamanho).chars().collect();
+        match (self, tamanho) {
+            (FormatoDocumento::Digitos, _) => d.into_iter().collect(),
+            (FormatoDocumento::Mascara, 11) => format!("{}{}{}.{}{}{}.{}{}{}-{}{}", d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7], d[8], d[9], d[10]),
+            (FormatoDocumento::Mascara, _) => {
+                format!("{}{}.{}{}{}.{}{}{}/{}{}{}{}-{}{}", d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7], d[8], d[9], d[10], d[11], d[12], d[13])
+            }
+        }
+    }
+}
+
+/// Confere os dígitos verificadores do CPF; pontos, traço e espaços são ignorados.
+pub fn cpf_valido(cpf: &str) -> bool {
+    digitos_validos(cpf, 11, &[10, 9, 8, 7, 6, 5, 4, 3, 2])
+}
+
+/// Confere os dígitos verificadores do CNPJ; pontos, barra, traço e espaços são ignorados.
+pub fn cnpj_valido(cnpj: &str) -> bool {
+    digitos_validos(cnpj, 14, &[5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2])
+}
+
+/// Confere os dois dígitos verificadores (módulo 11), calculados com os `pesos` do primeiro dígito.
+fn digitos_validos(documento: &str, tamanho: usize, pesos: &[u32]) -> bool {
+    let formatacao = |c: char| matches!(c, '.' | '-' | '/' | ' ');
+    if documento.trim().chars().any(|c| !c.is_ascii_digit() && !formatacao(c)) {
+        return false;
+    }
+    let digitos: Vec<u32> = documento.chars().filter_map(|c| c.to_digit(10)).collect();
+    // Sequências repetidas (ex.: 000.000.000-00) passam no cálculo, mas não são documentos válidos.
+    if digitos.len() != tamanho || digitos.iter().all(|digito| *digito == digitos[0]) {
+        return false;
+    }
+
+    verificadores(&digitos[..tamanho - 2], pesos) == [digitos[tamanho - 2], digitos[tamanho - 1]]
+}
+
+/// CPF formatado (`000.000.000-00`) com os dígitos verificadores dos 9 primeiros `digitos`.
+pub(crate) fn completar_cpf(digitos: &[u32]) -> String {
+    let [primeiro, segundo] = verificadores(&digitos[..9], &[10, 9, 8, 7, 6, 5, 4, 3, 2]);
+    let d: Vec<char> = digitos[..9].iter().chain([&primeiro, &segundo]).filter_map(|digito| char::from_digit(*digito, 10)).collect();
+    format!("{}{}{}.{}{}{}.{}{}{}-{}{}", d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7], d[8], d[9], d[10])
+}
+
+/// CNPJ formatado (`00.000.000/0000-00`) com os dígitos verificadores dos 12 primeiros `digitos`.
+pub(crate) fn completar_cnpj(digitos: &[u32]) -> String {
+    let [primeiro, segundo] = verificadores(&digitos[..12], &[5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2]);
+    let d: Vec<char> = digitos[..12].iter().chain([&primeiro, &segundo]).filter_map(|digito| char::from_digit(*digito, 10)).collect();
+    format!("{}{}.{}{}{}.{}{}{}/{}{}{}{}-{}{}", d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7], d[8], d[9], d[10], d[11], d[12], d[13])
+}
+
+/// Os dois dígitos verificadores de `base`; os pesos do segundo são os do primeiro
+/// precedidos do próximo peso da sequência.
+fn verificadores(base: &[u32], pesos: &[u32]) -> [u32; 2] {
+    let verificador = |digitos: &[u32], pesos: &[u32]| {
+        let soma: u32 = digitos.iter().zip(pesos).map(|(digito, peso)| digito * peso).sum();
+        match soma % 11 {
+            0 | 1 => 0,
+            resto => 11 - resto,
+        }
+    };
+
+    let primeiro = verificador(base, pesos);
+    let pesos_segundo: Vec<u32> = std::iter::once(pesos[0] + 1).chain(pesos.iter().copied()).collect();
+    let com_primeiro: Vec<u32> = base.iter().copied().chain(std::iter::once(primeiro)).collect();
+    [primeiro, verificador(&com_primeiro, &pesos_segundo)]
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use crate::erro::ErroConversor;
+use crate::modelo::Funcionario;
+use crate::opcoes::Opcoes;
+use crate::valor::{Decimal, Quantia};
+
+/// O que fazer com os funcionários repetidos (mesmo CPF) de uma empresa, escolhido por
+/// `--dedupe-strategy`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deduplicacao {
+    /// Mantém a primeira ocorrência.
+    ManterPrimeiro,
+    /// Mantém os dados da última ocorrência, na posição da primeira.
+    ManterUltimo,
+    /// Mantém a primeira ocorrência com a soma de `Valor` e `MetaPremio` de todas.
+    Somar,
+}
+
+impl Deduplicacao {
+    /// Nomes aceitos por `--dedupe-strategy`.
+    pub const NOMES: [&'static str; 3] = ["keep-first", "keep-last", "sum-values"];
+
+    pub fn from_nome(nome: &str) -> Option<Deduplicacao> {
+        match nome.to_lowercase().as_str() {
+            "keep-first" => Some(Deduplicacao::ManterPrimeiro),
+            "keep-last" => Some(Deduplicacao::ManterUltimo),
+            "sum-values" => Some(Deduplicacao::Somar),
+            _ => None,
+        }
+    }
+}
+
+/// Junta os funcionários de mesmo CPF (comparado só pelos dígitos), na ordem da primeira
+/// ocorrência de cada um.
+///
+/// Retorna os funcionários restantes e, para o relatório, uma descrição de cada CPF repetido.
+pub(crate) fn deduplicar(funcionarios: Vec<Funcionario>, estrategia: Deduplicacao, opcoes: &Opcoes) -> Result<(Vec<Funcionario>, Vec<String>), ErroConversor> {
+    let mut unicos: Vec<Funcionario> = Vec::new();
+    let mut ocorrencias: Vec<usize> = Vec::new();
+    let mut posicoes: HashMap<String, usize> = HashMap::new();
+
+    for funcionario in funcionarios {
+        let chave: String = funcionario.cpf.chars().filter(char::is_ascii_digit).collect();
+        let Some(&posicao) = posicoes.get(&chave) else {
+            posicoes.insert(chave, unicos.len());
+            unicos.push(funcionario);
+            ocorrencias.push(1);
+            continue;
+        };
+
+        ocorrencias[posicao] += 1;
+        match estrategia {
+            Deduplicacao::ManterPrimeiro => {}
+            Deduplicacao::ManterUltimo => unicos[posicao] = funcionario,
+            Deduplicacao::Somar => somar(&mut unicos[posicao], &funcionario, opcoes)?,
+        }
+    }
+
+    let repetidos = unicos
+        .iter()
+        .zip(&ocorrencias)
+        .filter(|(_, quantidade)| **quantidade > 1)
+        .map(|(funcionario, quantidade)| tr!("o CPF {} aparece {} vezes; {} linha(s) removida(s).", funcionario.cpf, quantidade, quantidade - 1))
+        .collect();
+    Ok((unicos, repetidos))
+}
+
+/// Soma `Valor` e `MetaPremio` de `outro` em `funcionario`, com todas as casas decimais.
+/// Valores em moedas diferentes ou que não são números não são somados.
+fn somar(funcionario: &mut Funcionario, outro: &Funcionario, opcoes: &Opcoes) -> Result<(), ErroConversor> {
+    if !funcionario.valor.moeda().eq_ignore_ascii_case(outro.valor.moeda()) {
+        return Err(ErroConversor::ValorInvalido(tr!("O CPF {} aparece com moedas diferentes ({} e {}); os valores não podem ser somados.", funcionario.cpf, funcionario.valor.moeda(), outro.valor.moeda())));
+    }
+
+    // A soma é gravada com o mesmo separador decimal da entrada, para ser lida como os demais valores.
+    let decimal = opcoes.decimal_entrada.unwrap_or(Decimal::Ponto);
+    let cpf = &funcionario.cpf;
+    let numero = |campo: &str, texto: &str| match opcoes.ler_quantia(texto) {
+        Some(valor) => Ok(valor),
+        None if texto.trim().is_empty() => Ok(Quantia::default()),
+        None => Err(ErroConversor::ValorInvalido(tr!("O CPF {} aparece mais de uma vez com {} não numérico '{}'; os valores não podem ser somados.", cpf, campo, texto))),
+    };
+    let soma = |campo: &str, primeiro: &str, segundo: &str| -> Result<String, ErroConversor> {
+        let texto = (numero(campo, primeiro)? + numero(campo, segundo)?).completa();
+        Ok(match decimal {
+            Decimal::Virgula => texto.replace('.', ","),
+            Decimal::Ponto => texto,
+        })
+    };
+
+    let valor = soma("Valor", &funcionario.valor.valor, &outro.valor.valor)?;
+    let meta_premio = match (&funcionario.meta_premio, &outro.meta_premio) {
+        (None, None) => None,
+        (primeiro, segundo) => Some(soma("MetaPremio", primeiro.as_deref().unwrap_or(""), segundo.as_deref().unwrap_or(""))?),
+    };
+    funcionario.valor.valor = valor;
+    funcionario.meta_premio = meta_premio;
+    Ok(())
+}
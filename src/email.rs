@@ -0,0 +1,180 @@
+use chrono::Local;
+use converterxmlcsv::competencia::ano_mes;
+use std::env;
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::configuracao::ConfiguracaoSmtp;
+use crate::http::citar;
+use crate::relatorio::{total_geral, LinhaRelatorio};
+
+/// Cliente usado para enviar o e-mail por SMTP, com o anexo montado por ele (`--form`).
+const PROGRAMA: &str = "curl";
+
+/// Assunto usado quando a seção `[smtp]` não tem `subject`.
+const ASSUNTO_PADRAO: &str = "Arquivos convertidos {mesano}: {funcionarios} funcionário(s), total R$ {total}";
+
+/// Segurança da conexão com o servidor SMTP (`tls` da seção `[smtp]`).
+#[derive(Debug, Clone, Copy)]
+enum Seguranca {
+    StartTls,
+    Smtps,
+    Nenhuma,
+}
+
+impl Seguranca {
+    const NOMES: [&'static str; 3] = ["starttls", "smtps", "none"];
+
+    fn from_nome(nome: &str) -> Option<Seguranca> {
+        match nome.to_lowercase().as_str() {
+            "starttls" => Some(Seguranca::StartTls),
+            "smtps" => Some(Seguranca::Smtps),
+            "none" => Some(Seguranca::Nenhuma),
+            _ => None,
+        }
+    }
+}
+
+/// Envio dos arquivos gerados no lote para os endereços de `--email-to`.
+#[derive(Debug)]
+pub struct Email {
+    config: ConfiguracaoSmtp,
+    seguranca: Seguranca,
+    para: Vec<String>,
+}
+
+impl Email {
+    pub fn new(config: ConfiguracaoSmtp, para: Vec<String>) -> Result<Email, String> {
+        if config.host.is_none() || config.from.is_none() {
+            return Err(tr!("Informe o servidor em host e o remetente em from, na seção [smtp] do arquivo de configuração, para usar --email-to.").to_string());
+        }
+        let seguranca = match config.tls.as_deref() {
+            Some(nome) => Seguranca::from_nome(nome).ok_or_else(|| tr!("Valor inválido para tls: '{}'. Use um de: {}.", nome, Seguranca::NOMES.join(", ")))?,
+            None => Seguranca::StartTls,
+        };
+        Ok(Email { config, seguranca, para })
+    }
+
+    /// Envia os `anexos` com o assunto de `subject` e, no corpo, o resumo do `relatorio`.
+    pub fn enviar(&self, anexos: &[&Path], relatorio: &[LinhaRelatorio]) -> Result<(), Box<dyn Error>> {
+        let total = total_geral(relatorio);
+        let valor = total.total_comissao + total.total_vales + total.total_ferias + total.total_decimo_terceiro;
+        let mut competencias: Vec<&str> = relatorio.iter().map(|linha| linha.mes_ano.as_str()).filter(|mes_ano| !mes_ano.is_empty()).collect();
+        competencias.sort_by_key(|mes_ano| ano_mes(mes_ano));
+        competencias.dedup();
+        let competencias = competencias.join(", ");
+
+        let assunto = self
+            .config
+            .subject
+            .as_deref()
+            .unwrap_or(ASSUNTO_PADRAO)
+            .replace("{mesano}", &competencias)
+            .replace("{arquivos}", &anexos.len().to_string())
+            .replace("{funcionarios}", &total.funcionarios.to_string())
+            .replace("{total}", &format!("{:.2}", valor));
+        let nomes: Vec<String> = anexos.iter().map(|anexo| anexo.file_name().unwrap_or_default().to_string_lossy().into_owned()).collect();
+        let corpo = format!(
+            "Competência: {}\nArquivos convertidos: {}\nFuncionários: {}\nTotal: R$ {:.2}\n\nAnexos: {}\n",
+            competencias,
+            relatorio.len(),
+            total.funcionarios,
+            valor,
+            nomes.join(", ")
+        );
+
+        let (esquema, porta) = match self.seguranca {
+            Seguranca::Smtps => ("smtps", 465),
+            Seguranca::StartTls | Seguranca::Nenhuma => ("smtp", 587),
+        };
+        let host = self.config.host.as_deref().unwrap_or_default();
+        let remetente = self.config.from.as_deref().unwrap_or_default();
+
+        // As opções vão pela entrada padrão (`--config -`), para que a senha não apareça na
+        // lista de processos.
+        let mut opcoes = vec![
+            format!("url = {}", citar(&format!("{}://{}:{}", esquema, host, self.config.port.unwrap_or(porta)))),
+            format!("mail-from = {}", citar(remetente)),
+            format!("header = {}", citar(&format!("From: {}", remetente))),
+            format!("header = {}", citar(&format!("To: {}", self.para.join(", ")))),
+            format!("header = {}", citar(&format!("Subject: {}", cabecalho_codificado(&assunto)))),
+            format!("header = {}", citar(&format!("Date: {}", Local::now().to_rfc2822()))),
+            format!("form = {}", citar(&format!("={};type=text/plain; charset=utf-8", parte(&corpo)))),
+        ];
+        if let Seguranca::StartTls = self.seguranca {
+            opcoes.push("ssl-reqd".to_string());
+        }
+        if let Some(usuario) = &self.config.user {
+            let senha = env::var("SMTP_PASSWORD").ok().or_else(|| self.config.password.clone()).unwrap_or_default();
+            opcoes.push(format!("user = {}", citar(&format!("{}:{}", usuario, senha))));
+        }
+        for destinatario in &self.para {
+            opcoes.push(format!("mail-rcpt = {}", citar(destinatario)));
+        }
+        for anexo in anexos {
+            opcoes.push(format!("form = {}", citar(&format!("=@{};encoder=base64", parte(&anexo.to_string_lossy())))));
+        }
+
+        let mut processo = Command::new(PROGRAMA)
+            .args(["--silent", "--show-error", "--config", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|erro| tr!("Não foi possível executar o {}, necessário para --email-to: {}.", PROGRAMA, erro))?;
+        if let Some(mut entrada) = processo.stdin.take() {
+            entrada.write_all(opcoes.join("\n").as_bytes())?;
+        }
+        let saida = processo.wait_with_output()?;
+        if !saida.status.success() {
+            return Err(tr!("Não foi possível enviar o e-mail por {} ({}): {}", host, saida.status, String::from_utf8_lossy(&saida.stderr).trim()).into());
+        }
+        log::info!("E-mail \"{}\" enviado para {} com {} anexo(s)", assunto, self.para.join(", "), anexos.len());
+        Ok(())
+    }
+}
+
+/// Conteúdo ou caminho de uma parte de `form`, entre aspas para que `;` e `,` não sejam
+/// lidos como atributos.
+fn parte(texto: &str) -> String {
+    format!("\"{}\"", texto.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Valor de cabeçalho em UTF-8 codificado como manda a RFC 2047, quando não é ASCII.
+///
+/// Cada trecho codificado tem no máximo 45 bytes do texto (60 caracteres em base64), para
+/// ficar dentro do limite de 75 caracteres, sem dividir um caractere entre dois trechos.
+fn cabecalho_codificado(texto: &str) -> String {
+    if texto.is_ascii() {
+        return texto.to_string();
+    }
+    let mut trechos = Vec::new();
+    let mut inicio = 0;
+    for (posicao, caractere) in texto.char_indices() {
+        if posicao + caractere.len_utf8() - inicio > 45 {
+            trechos.push(&texto[inicio..posicao]);
+            inicio = posicao;
+        }
+    }
+    trechos.push(&texto[inicio..]);
+    trechos.iter().map(|trecho| format!("=?UTF-8?B?{}?=", base64(trecho.as_bytes()))).collect::<Vec<_>>().join(" ")
+}
+
+fn base64(dados: &[u8]) -> String {
+    const ALFABETO: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut codificado = String::with_capacity(dados.len().div_ceil(3) * 4);
+    for bloco in dados.chunks(3) {
+        let bytes = [bloco[0], *bloco.get(1).unwrap_or(&0), *bloco.get(2).unwrap_or(&0)];
+        let numero = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for indice in 0..4 {
+            if indice <= bloco.len() {
+                codificado.push(ALFABETO[(numero >> (18 - 6 * indice) & 0x3f) as usize] as char);
+            } else {
+                codificado.push('=');
+            }
+        }
+    }
+    codificado
+}
@@ -0,0 +1,41 @@
+use rust_xlsxwriter::XlsxError;
+use std::error::Error;
+use std::io;
+use thiserror::Error;
+
+use crate::leitura::ErroLeitura;
+
+/// Erro das funções da biblioteca, separado por categoria para que quem as usa possa
+/// tratar cada caso; a linha de comando escolhe o código de saída por ele.
+#[derive(Debug, Error)]
+pub enum ErroConversor {
+    /// Falha ao ler ou gravar um arquivo.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// XML mal formado ou sem os campos obrigatórios, com a linha do problema.
+    #[error(transparent)]
+    Xml(#[from] ErroLeitura),
+    /// XML de um tipo não reconhecido, que pode ser convertido com `--generic`.
+    #[error("{0}")]
+    TipoNaoReconhecido(String),
+    /// Valores que impedem a conversão: campos exigidos ausentes, documentos inválidos com
+    /// `--strict` ou duplicados que não podem ser somados.
+    #[error("{0}")]
+    ValorInvalido(String),
+    /// Arquivo de apoio inválido (mapeamento, XSD ou CSV base) ou CSV de entrada fora do
+    /// formato esperado.
+    #[error("{0}")]
+    EntradaInvalida(String),
+    /// Falha ao ler ou gravar CSV.
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    /// Falha ao gerar a planilha XLSX.
+    #[error(transparent)]
+    Xlsx(#[from] XlsxError),
+    /// Falha ao gerar JSON.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Erro de um [`crate::Destino`] implementado fora da biblioteca.
+    #[error(transparent)]
+    Destino(Box<dyn Error + Send + Sync>),
+}
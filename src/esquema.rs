@@ -0,0 +1,683 @@
+use chrono::NaiveDate;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::codificacao::Decodificado;
+use crate::erro::ErroConversor;
+use crate::generico::{ler_arvore, No};
+
+/// XML Schema (`--xsd`) usado para validar os XML antes da conversão.
+///
+/// É verificado o subconjunto do XSD usado nos layouts de comissões, vales, férias e
+/// décimo terceiro:
+///
+/// - elementos globais e locais, com `type`, `ref` ou tipo anônimo, `minOccurs` e `maxOccurs`;
+/// - `complexType` com `sequence`, `all` ou `choice` de elementos, `simpleContent` com
+///   atributos (ex.: `<Valor moeda="USD">`) e `mixed`;
+/// - atributos com `type` e `use="required"`;
+/// - os tipos `string`, `decimal`, `integer` (e variações), `date` e `boolean`, e
+///   `simpleType` com `restriction` por `enumeration`, `length`, `minLength`, `maxLength`,
+///   `minInclusive` e `maxInclusive`.
+///
+/// Os demais recursos (ex.: `pattern`, grupos aninhados) não são verificados e ficam
+/// listados em [`Esquema::ignorados`].
+#[derive(Debug, Default)]
+pub struct Esquema {
+    elementos: HashMap<String, Declaracao>,
+    complexos: HashMap<String, TipoComplexo>,
+    simples: HashMap<String, TipoSimples>,
+    /// Recursos do XSD que não são verificados, como `xs:pattern`.
+    pub ignorados: Vec<String>,
+}
+
+/// Violação do XSD encontrada no XML, com a posição do elemento.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violacao {
+    pub linha: usize,
+    pub coluna: usize,
+    pub mensagem: String,
+}
+
+impl fmt::Display for Violacao {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", tr!("linha {}, coluna {}: {}", self.linha, self.coluna, self.mensagem))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Declaracao {
+    nome: String,
+    tipo: Tipo,
+    minimo: usize,
+    /// `None` para `maxOccurs="unbounded"`.
+    maximo: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Tipo {
+    /// Sem tipo declarado: qualquer conteúdo.
+    Qualquer,
+    Nomeado(String),
+    /// `ref` para um elemento global.
+    Referencia(String),
+    Complexo(Box<TipoComplexo>),
+    Simples(TipoSimples),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Modelo {
+    Vazio,
+    Sequencia,
+    Todos,
+    Escolha,
+    /// Grupos aninhados: os elementos declarados são aceitos em qualquer ordem e quantidade.
+    Livre,
+}
+
+#[derive(Debug, Clone)]
+struct TipoComplexo {
+    modelo: Modelo,
+    particulas: Vec<Declaracao>,
+    atributos: Vec<Atributo>,
+    /// Aceita atributos não declarados (`anyAttribute`).
+    qualquer_atributo: bool,
+    /// Aceita elementos não declarados (`any`).
+    qualquer_elemento: bool,
+    /// Tipo do texto de um `simpleContent`.
+    texto: Option<TipoSimples>,
+    misto: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Atributo {
+    nome: String,
+    tipo: TipoSimples,
+    obrigatorio: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TipoSimples {
+    /// Nome do tipo base, sem prefixo (ex.: `decimal` ou um `simpleType` do XSD).
+    base: String,
+    enumeracao: Vec<String>,
+    tamanho_minimo: Option<usize>,
+    tamanho_maximo: Option<usize>,
+    valor_minimo: Option<f64>,
+    valor_maximo: Option<f64>,
+}
+
+/// Tipos primitivos do XSD reconhecidos, pelo nome sem prefixo.
+fn primitivo(nome: &str) -> Option<&'static str> {
+    match nome {
+        "string" | "normalizedString" | "token" | "anyURI" | "ID" | "IDREF" | "NMTOKEN" | "Name" | "NCName" | "language" | "anySimpleType" => Some("string"),
+        "decimal" | "double" | "float" => Some("decimal"),
+        "integer" | "int" | "long" | "short" | "byte" | "nonNegativeInteger" | "positiveInteger" | "nonPositiveInteger" | "negativeInteger"
+        | "unsignedInt" | "unsignedLong" | "unsignedShort" | "unsignedByte" => Some("integer"),
+        "date" => Some("date"),
+        "boolean" => Some("boolean"),
+        _ => None,
+    }
+}
+
+fn sem_prefixo(nome: &str) -> &str {
+    nome.rsplit(':').next().unwrap_or(nome)
+}
+
+fn atributo<'a>(no: &'a No, nome: &str) -> Option<&'a str> {
+    no.atributos.iter().find(|(chave, _)| chave == nome).map(|(_, valor)| valor.as_str())
+}
+
+impl Esquema {
+    /// Lê o XSD e confere se todos os tipos usados estão definidos.
+    pub fn carregar(caminho: &Path) -> Result<Esquema, ErroConversor> {
+        Esquema::ler(caminho).map_err(ErroConversor::EntradaInvalida)
+    }
+
+    fn ler(caminho: &Path) -> Result<Esquema, String> {
+        let arquivo = File::open(caminho).map_err(|erro| tr!("Não foi possível ler o XSD '{}': {}", caminho.display(), erro))?;
+        let raiz = ler_arvore(BufReader::new(arquivo)).map_err(|erro| tr!("XSD '{}' inválido: {}", caminho.display(), erro))?;
+        if raiz.nome != "schema" {
+            return Err(tr!("'{}' não é um XSD: o elemento raiz é <{}>, e não <xs:schema>.", caminho.display(), raiz.nome));
+        }
+
+        let mut esquema = Esquema::default();
+        for filho in &raiz.filhos {
+            match filho.nome.as_str() {
+                "element" => {
+                    let declaracao = esquema.declaracao(filho)?;
+                    esquema.elementos.insert(declaracao.nome.clone(), declaracao);
+                }
+                "complexType" => {
+                    let nome = atributo(filho, "name").ok_or(tr!("complexType global sem o atributo name no XSD."))?.to_string();
+                    let tipo = esquema.complexo(filho)?;
+                    esquema.complexos.insert(nome, tipo);
+                }
+                "simpleType" => {
+                    let nome = atributo(filho, "name").ok_or(tr!("simpleType global sem o atributo name no XSD."))?.to_string();
+                    let tipo = esquema.tipo_simples(filho);
+                    esquema.simples.insert(nome, tipo);
+                }
+                "annotation" => {}
+                outro => esquema.ignorar(outro),
+            }
+        }
+        if esquema.elementos.is_empty() {
+            return Err(tr!("O XSD '{}' não declara nenhum elemento global.", caminho.display()));
+        }
+        esquema.conferir_tipos()?;
+        Ok(esquema)
+    }
+
+    fn ignorar(&mut self, recurso: &str) {
+        let recurso = format!("xs:{}", recurso);
+        if !self.ignorados.contains(&recurso) {
+            self.ignorados.push(recurso);
+        }
+    }
+
+    fn declaracao(&mut self, no: &No) -> Result<Declaracao, String> {
+        let minimo = match atributo(no, "minOccurs") {
+            Some(texto) => texto.parse().map_err(|_| tr!("minOccurs inválido no XSD: '{}'.", texto))?,
+            None => 1,
+        };
+        let maximo = match atributo(no, "maxOccurs") {
+            Some("unbounded") => None,
+            Some(texto) => Some(texto.parse().map_err(|_| tr!("maxOccurs inválido no XSD: '{}'.", texto))?),
+            None => Some(1),
+        };
+
+        if let Some(referencia) = atributo(no, "ref") {
+            let nome = sem_prefixo(referencia).to_string();
+            return Ok(Declaracao { tipo: Tipo::Referencia(nome.clone()), nome, minimo, maximo });
+        }
+        let nome = atributo(no, "name").ok_or(tr!("element sem os atributos name ou ref no XSD."))?.to_string();
+        let tipo = if let Some(tipo) = atributo(no, "type") {
+            Tipo::Nomeado(sem_prefixo(tipo).to_string())
+        } else if let Some(complexo) = no.filhos.iter().find(|filho| filho.nome == "complexType") {
+            Tipo::Complexo(Box::new(self.complexo(complexo)?))
+        } else if let Some(simples) = no.filhos.iter().find(|filho| filho.nome == "simpleType") {
+            Tipo::Simples(self.tipo_simples(simples))
+        } else {
+            Tipo::Qualquer
+        };
+        Ok(Declaracao { nome, tipo, minimo, maximo })
+    }
+
+    fn complexo(&mut self, no: &No) -> Result<TipoComplexo, String> {
+        let mut tipo = TipoComplexo {
+            modelo: Modelo::Vazio,
+            particulas: Vec::new(),
+            atributos: Vec::new(),
+            qualquer_atributo: false,
+            qualquer_elemento: false,
+            texto: None,
+            misto: atributo(no, "mixed") == Some("true"),
+        };
+
+        for filho in &no.filhos {
+            match filho.nome.as_str() {
+                "sequence" | "all" | "choice" => {
+                    tipo.modelo = match filho.nome.as_str() {
+                        "sequence" => Modelo::Sequencia,
+                        "all" => Modelo::Todos,
+                        _ => Modelo::Escolha,
+                    };
+                    self.particulas(filho, &mut tipo)?;
+                }
+                "simpleContent" => {
+                    let derivacao = filho.filhos.iter().find(|neto| neto.nome == "extension" || neto.nome == "restriction");
+                    if let Some(derivacao) = derivacao {
+                        tipo.texto = Some(TipoSimples { base: atributo(derivacao, "base").map(sem_prefixo).unwrap_or("string").to_string(), ..TipoSimples::default() });
+                        self.atributos(derivacao, &mut tipo)?;
+                    }
+                }
+                "attribute" | "anyAttribute" => self.atributos(no, &mut tipo)?,
+                "annotation" => {}
+                outro => {
+                    // Ex.: complexContent; o conteúdo do elemento não é verificado.
+                    self.ignorar(outro);
+                    tipo.modelo = Modelo::Livre;
+                    tipo.qualquer_elemento = true;
+                    tipo.qualquer_atributo = true;
+                }
+            }
+        }
+        Ok(tipo)
+    }
+
+    /// Elementos de um `sequence`, `all` ou `choice`; grupos aninhados tornam o modelo livre.
+    fn particulas(&mut self, grupo: &No, tipo: &mut TipoComplexo) -> Result<(), String> {
+        for filho in &grupo.filhos {
+            match filho.nome.as_str() {
+                "element" => {
+                    let declaracao = self.declaracao(filho)?;
+                    tipo.particulas.push(declaracao);
+                }
+                "any" => tipo.qualquer_elemento = true,
+                "sequence" | "all" | "choice" => {
+                    self.ignorar(&tr!("{} aninhado", filho.nome));
+                    tipo.modelo = Modelo::Livre;
+                    self.particulas(filho, tipo)?;
+                }
+                "annotation" => {}
+                outro => {
+                    self.ignorar(outro);
+                    tipo.modelo = Modelo::Livre;
+                    tipo.qualquer_elemento = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn atributos(&mut self, no: &No, tipo: &mut TipoComplexo) -> Result<(), String> {
+        for filho in &no.filhos {
+            match filho.nome.as_str() {
+                "attribute" => {
+                    let nome = atributo(filho, "name").or(atributo(filho, "ref").map(sem_prefixo)).ok_or(tr!("attribute sem o atributo name no XSD."))?;
+                    let simples = match (atributo(filho, "type"), filho.filhos.iter().find(|neto| neto.nome == "simpleType")) {
+                        (Some(base), _) => TipoSimples { base: sem_prefixo(base).to_string(), ..TipoSimples::default() },
+                        (None, Some(simples)) => self.tipo_simples(simples),
+                        (None, None) => TipoSimples { base: "string".to_string(), ..TipoSimples::default() },
+                    };
+                    tipo.atributos.push(Atributo { nome: nome.to_string(), tipo: simples, obrigatorio: atributo(filho, "use") == Some("required") });
+                }
+                "anyAttribute" => tipo.qualquer_atributo = true,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn tipo_simples(&mut self, no: &No) -> TipoSimples {
+        let mut tipo = TipoSimples { base: "string".to_string(), ..TipoSimples::default() };
+        let Some(restricao) = no.filhos.iter().find(|filho| filho.nome == "restriction") else {
+            // `list` e `union`: o valor é aceito como texto.
+            for filho in no.filhos.iter().filter(|filho| filho.nome != "annotation") {
+                self.ignorar(&filho.nome);
+            }
+            return tipo;
+        };
+
+        if let Some(base) = atributo(restricao, "base") {
+            tipo.base = sem_prefixo(base).to_string();
+        }
+        for faceta in &restricao.filhos {
+            let valor = atributo(faceta, "value").unwrap_or_default();
+            match faceta.nome.as_str() {
+                "enumeration" => tipo.enumeracao.push(valor.to_string()),
+                "length" => {
+                    tipo.tamanho_minimo = valor.parse().ok();
+                    tipo.tamanho_maximo = valor.parse().ok();
+                }
+                "minLength" => tipo.tamanho_minimo = valor.parse().ok(),
+                "maxLength" => tipo.tamanho_maximo = valor.parse().ok(),
+                "minInclusive" => tipo.valor_minimo = valor.parse().ok(),
+                "maxInclusive" => tipo.valor_maximo = valor.parse().ok(),
+                "annotation" => {}
+                outro => self.ignorar(outro),
+            }
+        }
+        tipo
+    }
+
+    /// Confere se os tipos e referências usados foram definidos no XSD ou são primitivos.
+    fn conferir_tipos(&self) -> Result<(), String> {
+        let mut declaracoes: Vec<&Declaracao> = self.elementos.values().collect();
+        declaracoes.extend(self.complexos.values().flat_map(|tipo| &tipo.particulas));
+        let mut simples: Vec<&TipoSimples> = self.simples.values().collect();
+        simples.extend(self.complexos.values().flat_map(|tipo| tipo.atributos.iter().map(|atributo| &atributo.tipo).chain(&tipo.texto)));
+
+        while let Some(declaracao) = declaracoes.pop() {
+            match &declaracao.tipo {
+                Tipo::Nomeado(nome) if !self.complexos.contains_key(nome) && !self.simples.contains_key(nome) && primitivo(nome).is_none() => {
+                    return Err(tr!("O tipo '{}' do elemento <{}> não está definido no XSD.", nome, declaracao.nome));
+                }
+                Tipo::Referencia(nome) if !self.elementos.contains_key(nome) => {
+                    return Err(tr!("O elemento <{}> referenciado no XSD não está declarado.", nome));
+                }
+                Tipo::Complexo(tipo) => {
+                    declaracoes.extend(&tipo.particulas);
+                    simples.extend(tipo.atributos.iter().map(|atributo| &atributo.tipo).chain(&tipo.texto));
+                }
+                Tipo::Simples(tipo) => simples.push(tipo),
+                _ => {}
+            }
+        }
+        for tipo in simples {
+            if !self.simples.contains_key(&tipo.base) && primitivo(&tipo.base).is_none() {
+                return Err(tr!("O tipo '{}' não está definido no XSD.", tipo.base));
+            }
+        }
+        Ok(())
+    }
+
+    /// Valida o XML de `reader`, retornando todas as violações encontradas; uma lista vazia
+    /// indica um XML válido. Um XML mal formado resulta em uma última violação na posição do
+    /// erro.
+    ///
+    /// O documento é carregado na memória, para que as posições sejam informadas em linha e
+    /// coluna.
+    pub fn validar<R: BufRead>(&self, reader: R) -> Result<Vec<Violacao>, ErroConversor> {
+        let mut conteudo = String::new();
+        Decodificado::new(reader).read_to_string(&mut conteudo)?;
+        let mut validacao = Validacao { esquema: self, conteudo: &conteudo, pilha: Vec::new(), violacoes: Vec::new() };
+        validacao.executar();
+        Ok(validacao.violacoes)
+    }
+}
+
+/// Elemento aberto durante a validação.
+struct Aberto<'a> {
+    nome: String,
+    posicao: usize,
+    /// `None` quando o conteúdo não é verificado.
+    tipo: Option<Resolvido<'a>>,
+    contagens: Vec<usize>,
+    /// Próxima partícula esperada em um `sequence`.
+    indice: usize,
+    texto: String,
+}
+
+#[derive(Clone, Copy)]
+enum Resolvido<'a> {
+    Complexo(&'a TipoComplexo),
+    Simples(&'a TipoSimples),
+    Primitivo(&'static str),
+}
+
+struct Validacao<'a> {
+    esquema: &'a Esquema,
+    conteudo: &'a str,
+    pilha: Vec<Aberto<'a>>,
+    violacoes: Vec<Violacao>,
+}
+
+impl<'a> Validacao<'a> {
+    fn violacao(&mut self, posicao: usize, mensagem: String) {
+        let anterior = &self.conteudo[..posicao.min(self.conteudo.len())];
+        let linha = anterior.matches('\n').count() + 1;
+        let coluna = anterior.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+        self.violacoes.push(Violacao { linha, coluna, mensagem });
+    }
+
+    fn executar(&mut self) {
+        let mut reader = Reader::from_str(self.conteudo);
+        loop {
+            let antes = reader.buffer_position() as usize;
+            let posicao = antes + self.conteudo[antes..].find('<').unwrap_or(0);
+            match reader.read_event() {
+                Ok(Event::Start(inicio)) => self.abrir(&inicio, posicao),
+                Ok(Event::Empty(inicio)) => {
+                    self.abrir(&inicio, posicao);
+                    self.fechar();
+                }
+                Ok(Event::End(_)) => self.fechar(),
+                Ok(Event::Text(conteudo)) => self.acrescentar(&conteudo.xml10_content()),
+                Ok(Event::CData(conteudo)) => self.acrescentar(&conteudo.xml10_content()),
+                Ok(Event::GeneralRef(referencia)) => {
+                    let texto = match referencia.resolve_char_ref() {
+                        Ok(Some(caractere)) => caractere.to_string(),
+                        _ => resolve_predefined_entity(&referencia).unwrap_or_default().to_string(),
+                    };
+                    self.acrescentar(&texto);
+                }
+                Ok(Event::Eof) => {
+                    if let Some(aberto) = self.pilha.last() {
+                        let mensagem = tr!("o XML terminou antes do fechamento de <{}>.", aberto.nome);
+                        self.violacao(self.conteudo.len(), mensagem);
+                    }
+                    return;
+                }
+                Ok(_) => {}
+                Err(erro) => {
+                    let posicao = reader.error_position() as usize;
+                    self.violacao(posicao, tr!("XML mal formado: {}", erro));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn acrescentar(&mut self, texto: &str) {
+        if let Some(aberto) = self.pilha.last_mut() {
+            aberto.texto.push_str(texto);
+        }
+    }
+
+    /// Resolve o tipo de uma declaração, seguindo `ref` e os tipos nomeados.
+    fn resolver(&self, tipo: &'a Tipo) -> Option<Resolvido<'a>> {
+        match tipo {
+            Tipo::Qualquer => None,
+            Tipo::Referencia(nome) => self.esquema.elementos.get(nome).and_then(|declaracao| self.resolver(&declaracao.tipo)),
+            Tipo::Nomeado(nome) => {
+                if let Some(complexo) = self.esquema.complexos.get(nome) {
+                    Some(Resolvido::Complexo(complexo))
+                } else if let Some(simples) = self.esquema.simples.get(nome) {
+                    Some(Resolvido::Simples(simples))
+                } else {
+                    primitivo(nome).map(Resolvido::Primitivo)
+                }
+            }
+            Tipo::Complexo(complexo) => Some(Resolvido::Complexo(complexo)),
+            Tipo::Simples(simples) => Some(Resolvido::Simples(simples)),
+        }
+    }
+
+    fn abrir(&mut self, inicio: &BytesStart, posicao: usize) {
+        let nome = inicio.local_name().as_ref().to_string();
+        let tipo = match self.pilha.last() {
+            None => match self.esquema.elementos.get(&nome) {
+                Some(declaracao) => self.resolver(&declaracao.tipo),
+                None => {
+                    let mut declarados: Vec<&str> = self.esquema.elementos.keys().map(String::as_str).collect();
+                    declarados.sort();
+                    self.violacao(posicao, tr!("o elemento raiz <{}> não está declarado no XSD (esperado: {}).", nome, declarados.join(", ")));
+                    None
+                }
+            },
+            Some(_) => self.filho(&nome, posicao),
+        };
+
+        self.conferir_atributos(inicio, &nome, tipo, posicao);
+        let particulas = match tipo {
+            Some(Resolvido::Complexo(complexo)) => complexo.particulas.len(),
+            _ => 0,
+        };
+        self.pilha.push(Aberto { nome, posicao, tipo, contagens: vec![0; particulas], indice: 0, texto: String::new() });
+    }
+
+    /// Confere o elemento filho contra o modelo do elemento aberto e retorna o tipo dele.
+    fn filho(&mut self, nome: &str, posicao: usize) -> Option<Resolvido<'a>> {
+        let pai = self.pilha.last_mut().expect("elemento pai aberto");
+        let complexo = match pai.tipo {
+            None => return None,
+            Some(Resolvido::Complexo(complexo)) => complexo,
+            Some(_) => {
+                let mensagem = tr!("<{}> não pode conter elementos, mas contém <{}>.", pai.nome, nome);
+                pai.tipo = None;
+                self.violacao(posicao, mensagem);
+                return None;
+            }
+        };
+
+        let declarada = complexo.particulas.iter().position(|particula| particula.nome == nome);
+        let mut mensagens = Vec::new();
+        let Some(indice) = declarada else {
+            if complexo.qualquer_elemento {
+                return None;
+            }
+            let mensagem = if complexo.particulas.is_empty() {
+                tr!("<{}> não pode conter elementos, mas contém <{}>.", pai.nome, nome)
+            } else {
+                tr!("<{}> não é permitido em <{}> (permitidos: {}).", nome, pai.nome, lista_particulas(complexo))
+            };
+            self.violacao(posicao, mensagem);
+            return None;
+        };
+
+        if complexo.modelo == Modelo::Sequencia {
+            match (pai.indice..complexo.particulas.len()).find(|seguinte| complexo.particulas[*seguinte].nome == nome) {
+                Some(seguinte) => {
+                    let faltantes: Vec<String> = (pai.indice..seguinte)
+                        .filter(|anterior| pai.contagens[*anterior] < complexo.particulas[*anterior].minimo)
+                        .map(|anterior| format!("<{}>", complexo.particulas[anterior].nome))
+                        .collect();
+                    if !faltantes.is_empty() {
+                        mensagens.push(tr!("falta {} antes de <{}> em <{}>.", faltantes.join(", "), nome, pai.nome));
+                    }
+                    pai.indice = seguinte;
+                }
+                None => mensagens.push(tr!("<{}> está fora de ordem em <{}>; ele deve vir antes de <{}>.", nome, pai.nome, complexo.particulas[pai.indice].nome)),
+            }
+        }
+
+        pai.contagens[indice] += 1;
+        let particula = &complexo.particulas[indice];
+        if let Some(maximo) = particula.maximo.filter(|maximo| pai.contagens[indice] > *maximo) {
+            mensagens.push(tr!("<{}> aparece mais de {} vez(es) em <{}>.", nome, maximo, pai.nome));
+        }
+        if complexo.modelo == Modelo::Escolha && pai.contagens.iter().enumerate().any(|(outro, quantidade)| outro != indice && *quantidade > 0) {
+            mensagens.push(tr!("<{}> permite apenas um dos elementos {}, mas contém mais de um.", pai.nome, lista_particulas(complexo)));
+        }
+        for mensagem in mensagens {
+            self.violacao(posicao, mensagem);
+        }
+        self.resolver(&particula.tipo)
+    }
+
+    fn conferir_atributos(&mut self, inicio: &BytesStart, nome: &str, tipo: Option<Resolvido<'a>>, posicao: usize) {
+        let Some(tipo) = tipo else {
+            return;
+        };
+        let mut presentes = Vec::new();
+        for item in inicio.attributes().flatten() {
+            let chave = item.key.as_ref();
+            // Declarações de namespace e atributos xsi:* não fazem parte do conteúdo.
+            if chave.starts_with("xmlns") || chave.starts_with("xsi:") {
+                continue;
+            }
+            let atributo = item.key.local_name().as_ref().to_string();
+            let valor = item.normalized_value(XmlVersion::Implicit1_0).map(|valor| valor.into_owned()).unwrap_or_default();
+            presentes.push(atributo.clone());
+
+            let declarado = match tipo {
+                Resolvido::Complexo(complexo) => complexo.atributos.iter().find(|declarado| declarado.nome == atributo),
+                _ => None,
+            };
+            match (declarado, tipo) {
+                (Some(declarado), _) => {
+                    if let Some(problema) = self.conferir_valor(&declarado.tipo, &valor) {
+                        self.violacao(posicao, tr!("atributo {} de <{}>: {}", atributo, nome, problema));
+                    }
+                }
+                (None, Resolvido::Complexo(complexo)) if complexo.qualquer_atributo => {}
+                _ => self.violacao(posicao, tr!("o atributo {} não é permitido em <{}>.", atributo, nome)),
+            }
+        }
+
+        if let Resolvido::Complexo(complexo) = tipo {
+            for obrigatorio in complexo.atributos.iter().filter(|atributo| atributo.obrigatorio && !presentes.contains(&atributo.nome)) {
+                self.violacao(posicao, tr!("<{}> não possui o atributo obrigatório {}.", nome, obrigatorio.nome));
+            }
+        }
+    }
+
+    fn fechar(&mut self) {
+        let Some(aberto) = self.pilha.pop() else {
+            return;
+        };
+        let texto = aberto.texto.trim();
+        let problema = match aberto.tipo {
+            None => None,
+            Some(Resolvido::Primitivo(primitivo)) => conferir_primitivo(primitivo, texto),
+            Some(Resolvido::Simples(simples)) => self.conferir_valor(simples, texto),
+            Some(Resolvido::Complexo(complexo)) => {
+                if let Some(simples) = &complexo.texto {
+                    self.conferir_valor(simples, texto)
+                } else if !texto.is_empty() && !complexo.misto {
+                    Some(tr!("não pode conter texto ('{}').", texto))
+                } else {
+                    let faltantes: Vec<String> = match complexo.modelo {
+                        Modelo::Escolha if !complexo.particulas.iter().any(|particula| particula.minimo == 0) && aberto.contagens.iter().all(|quantidade| *quantidade == 0) => {
+                            vec![tr!("um dos elementos {}", lista_particulas(complexo))]
+                        }
+                        // Na sequência, as anteriores à posição atual já foram conferidas.
+                        Modelo::Sequencia | Modelo::Todos => complexo
+                            .particulas
+                            .iter()
+                            .zip(&aberto.contagens)
+                            .skip(if complexo.modelo == Modelo::Sequencia { aberto.indice } else { 0 })
+                            .filter(|(particula, quantidade)| **quantidade < particula.minimo)
+                            .map(|(particula, _)| format!("<{}>", particula.nome))
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    (!faltantes.is_empty()).then(|| tr!("falta {}.", faltantes.join(", ")))
+                }
+            }
+        };
+        if let Some(problema) = problema {
+            self.violacao(aberto.posicao, format!("<{}>: {}", aberto.nome, problema));
+        }
+    }
+
+    /// Descrição do problema do valor, ou `None` quando ele é válido para o tipo.
+    fn conferir_valor(&self, tipo: &TipoSimples, valor: &str) -> Option<String> {
+        let base = match self.esquema.simples.get(&tipo.base) {
+            // Restrição de outro simpleType: o valor também deve valer para ele.
+            Some(anterior) if !std::ptr::eq(anterior, tipo) => self.conferir_valor(anterior, valor),
+            _ => conferir_primitivo(primitivo(&tipo.base).unwrap_or("string"), valor),
+        };
+        if base.is_some() {
+            return base;
+        }
+
+        let tamanho = valor.chars().count();
+        if !tipo.enumeracao.is_empty() && !tipo.enumeracao.iter().any(|permitido| permitido == valor) {
+            return Some(tr!("o valor '{}' não está entre os permitidos ({}).", valor, tipo.enumeracao.join(", ")));
+        }
+        if tipo.tamanho_minimo.is_some_and(|minimo| tamanho < minimo) {
+            return Some(tr!("o valor '{}' tem menos de {} caractere(s).", valor, tipo.tamanho_minimo.unwrap_or_default()));
+        }
+        if tipo.tamanho_maximo.is_some_and(|maximo| tamanho > maximo) {
+            return Some(tr!("o valor '{}' tem mais de {} caractere(s).", valor, tipo.tamanho_maximo.unwrap_or_default()));
+        }
+        let numero = valor.parse::<f64>().ok();
+        if let (Some(minimo), Some(numero)) = (tipo.valor_minimo, numero) {
+            if numero < minimo {
+                return Some(tr!("o valor {} é menor que o mínimo {}.", valor, minimo));
+            }
+        }
+        if let (Some(maximo), Some(numero)) = (tipo.valor_maximo, numero) {
+            if numero > maximo {
+                return Some(tr!("o valor {} é maior que o máximo {}.", valor, maximo));
+            }
+        }
+        None
+    }
+}
+
+fn lista_particulas(complexo: &TipoComplexo) -> String {
+    complexo.particulas.iter().map(|particula| format!("<{}>", particula.nome)).collect::<Vec<_>>().join(", ")
+}
+
+fn conferir_primitivo(primitivo: &str, valor: &str) -> Option<String> {
+    let valido = match primitivo {
+        "decimal" => valor.parse::<f64>().is_ok() && !valor.contains(['e', 'E', 'i', 'n', 'N']),
+        "integer" => {
+            let digitos = valor.strip_prefix(['-', '+']).unwrap_or(valor);
+            !digitos.is_empty() && digitos.chars().all(|caractere| caractere.is_ascii_digit())
+        }
+        "date" => NaiveDate::parse_from_str(valor, "%Y-%m-%d").is_ok(),
+        "boolean" => matches!(valor, "true" | "false" | "1" | "0"),
+        _ => true,
+    };
+    (!valido).then(|| tr!("o valor '{}' não é do tipo {}.", valor, primitivo))
+}
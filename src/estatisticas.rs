@@ -0,0 +1,149 @@
+use colored::*;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use converterxmlcsv::competencia::ano_mes;
+use converterxmlcsv::valor::{Decimal, Quantia};
+use converterxmlcsv::{Destino, ErroConversor};
+
+use crate::compactado;
+use crate::{abrir_entrada, converter_entrada, Convertido, ErroSaida, OpcoesCli, SAIDA_FALHA_PARCIAL, SAIDA_SEM_ARQUIVOS};
+
+/// Destino de `stats`: não grava nada, apenas guarda o `Valor` e o `MesAno` de cada linha.
+/// Os valores chegam com ponto decimal, pois `stats` usa `decimal_saida` [`Decimal::Ponto`].
+#[derive(Default)]
+struct Valores {
+    colunas: [Option<usize>; 2],
+    valores: Vec<(String, Quantia)>,
+    /// Linhas cujo `Valor` não é número, fora dos cálculos.
+    nao_numericos: usize,
+}
+
+impl Destino for Valores {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.colunas = ["MesAno", "Valor"].map(|nome| cabecalho.iter().position(|coluna| *coluna == nome));
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        let campo = |coluna: Option<usize>| coluna.and_then(|indice| registro.get(indice)).copied().unwrap_or_default();
+        match Quantia::ler(campo(self.colunas[1]), Some(Decimal::Ponto)) {
+            Some(valor) => self.valores.push((campo(self.colunas[0]).to_string(), valor)),
+            None => self.nao_numericos += 1,
+        }
+        Ok(())
+    }
+}
+
+/// Tipo e quantidade de funcionários de um arquivo lido.
+struct Arquivo {
+    caminho: PathBuf,
+    tipo: String,
+    funcionarios: usize,
+}
+
+/// `stats`: lê os XML sem gravar nada e exibe um resumo do lote (arquivos por tipo,
+/// funcionários por arquivo, menor, maior e média do `Valor` e o total por competência),
+/// para conferir os arquivos antes do fechamento do mês.
+///
+/// Os valores são os do XML, sem `--cambio`. Os arquivos que não podem ser lidos são
+/// listados com o erro e não entram nas estatísticas.
+pub fn exibir(arquivos: &[PathBuf], opcoes: &OpcoesCli) -> Result<(), Box<dyn Error>> {
+    if arquivos.is_empty() {
+        return Err(ErroSaida::criar(SAIDA_SEM_ARQUIVOS, tr!("Não foram encontrados arquivos XML na pasta.")));
+    }
+
+    let mut lidos = Vec::new();
+    let mut falhas = Vec::new();
+    let mut valores = Valores::default();
+    for arquivo in arquivos {
+        let mut do_arquivo = Valores::default();
+        match ler(arquivo, opcoes, &mut do_arquivo) {
+            Ok((tipo, funcionarios)) => {
+                lidos.push(Arquivo { caminho: arquivo.clone(), tipo, funcionarios });
+                valores.valores.append(&mut do_arquivo.valores);
+                valores.nao_numericos += do_arquivo.nao_numericos;
+            }
+            Err(erro) => falhas.push((arquivo, erro.to_string())),
+        }
+    }
+
+    let dinheiro = |valor: Quantia| format!("R$ {:.2}", valor);
+    let mut por_tipo: BTreeMap<&str, usize> = BTreeMap::new();
+    for arquivo in &lidos {
+        *por_tipo.entry(arquivo.tipo.as_str()).or_default() += 1;
+    }
+    println!("{}", tr!("Arquivos por tipo").bold());
+    for (tipo, quantidade) in &por_tipo {
+        println!("  {}: {}", tipo, quantidade);
+    }
+
+    println!("{}", tr!("Funcionários por arquivo").bold());
+    for arquivo in &lidos {
+        println!("  {} ({}): {}", arquivo.caminho.display(), arquivo.tipo, arquivo.funcionarios);
+    }
+    for (arquivo, erro) in &falhas {
+        println!("  {} {}", arquivo.display(), tr!("erro: {}", erro).bright_red());
+    }
+
+    println!("{}", tr!("Valor").bold());
+    let quantidade = valores.valores.len();
+    let minimo = valores.valores.iter().map(|(_, valor)| *valor).min();
+    let maximo = valores.valores.iter().map(|(_, valor)| *valor).max();
+    match (minimo, maximo) {
+        (Some(minimo), Some(maximo)) => {
+            let total: Quantia = valores.valores.iter().map(|(_, valor)| *valor).sum();
+            println!("  {}", tr!("Mínimo: {}", dinheiro(minimo)));
+            println!("  {}", tr!("Máximo: {}", dinheiro(maximo)));
+            println!("  {}", tr!("Média: {} ({} funcionário(s))", dinheiro(total.dividir(quantidade)), quantidade));
+            println!("  {}", tr!("Total: {}", dinheiro(total)));
+        }
+        _ => println!("  {}", tr!("Nenhum valor numérico.")),
+    }
+    if valores.nao_numericos > 0 {
+        println!("  {}", tr!("{} valor(es) não numérico(s) fora dos cálculos.", valores.nao_numericos).bright_yellow());
+    }
+
+    // Competências em ordem cronológica; as não reconhecidas, pelo texto, ao final.
+    let mut por_competencia: BTreeMap<((u32, u32), &str), Quantia> = BTreeMap::new();
+    for (mes_ano, valor) in &valores.valores {
+        let chave = (ano_mes(mes_ano).unwrap_or((u32::MAX, 0)), mes_ano.as_str());
+        *por_competencia.entry(chave).or_default() += *valor;
+    }
+    println!("{}", tr!("Total por mês/ano").bold());
+    for ((_, mes_ano), total) in &por_competencia {
+        let mes_ano = if mes_ano.is_empty() { tr!("(sem MesAno)") } else { mes_ano };
+        println!("  {}: {}", mes_ano, dinheiro(*total));
+    }
+
+    if !falhas.is_empty() {
+        return Err(ErroSaida::criar(SAIDA_FALHA_PARCIAL, tr!("{} de {} arquivo(s) não puderam ser lidos.", falhas.len(), arquivos.len())));
+    }
+    Ok(())
+}
+
+/// Tipo e funcionários de um arquivo; os XML de um `.zip` são somados.
+fn ler(arquivo: &Path, opcoes: &OpcoesCli, valores: &mut Valores) -> Result<(String, usize), Box<dyn Error>> {
+    let mut tipo = String::new();
+    let mut funcionarios = 0;
+    for fonte in compactado::fontes(arquivo)? {
+        let abrir = || -> Result<_, Box<dyn Error>> { Ok(fonte.abrir(None)?) };
+        let entrada = abrir_entrada(abrir, Some(&fonte.caminho()), opcoes)?;
+        match converter_entrada(entrada, opcoes, valores)? {
+            Convertido::Tipado(resumo) => {
+                tipo = resumo.tipo.nome().to_string();
+                funcionarios += resumo.funcionarios;
+            }
+            Convertido::Generico(linhas) => {
+                tipo = "generico".to_string();
+                funcionarios += linhas;
+            }
+            Convertido::Mapeado(layout, linhas) => {
+                tipo = layout;
+                funcionarios += linhas;
+            }
+        }
+    }
+    Ok((tipo, funcionarios))
+}
@@ -0,0 +1,232 @@
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::codificacao::Decodificado;
+use crate::erro::ErroConversor;
+use crate::leitura::{mensagem_xml, ErroLeitura};
+use crate::saida::Destino;
+
+/// Separador das ocorrências de uma mesma coluna dentro de uma linha.
+pub(crate) const SEPARADOR_REPETIDOS: &str = " | ";
+
+/// Elemento do XML com atributos, texto e filhos, para a conversão genérica.
+pub(crate) struct No {
+    pub(crate) nome: String,
+    pub(crate) atributos: Vec<(String, String)>,
+    texto: String,
+    pub(crate) filhos: Vec<No>,
+}
+
+/// Converte um XML de qualquer estrutura em linhas e colunas, para os arquivos cujo tipo
+/// não é reconhecido (`--generic`).
+///
+/// O elemento que mais se repete (ex.: `<Funcionario>`) vira uma linha por ocorrência; os
+/// caminhos dos elementos abaixo do raiz viram colunas (ex.: `Empresa/Funcionario/CPF`, e
+/// `Empresa/Funcionario/Valor/@moeda` para atributos). Os elementos fora das linhas, como os
+/// dados da `<Empresa>`, são repetidos em todas as linhas que eles contêm. Sem elementos
+/// repetidos, o XML inteiro vira uma única linha.
+///
+/// Diferente da conversão dos tipos conhecidos, o documento é carregado na memória.
+/// Retorna a quantidade de linhas gravadas.
+pub fn converter_generico<R: BufRead>(reader: R, destino: &mut dyn Destino) -> Result<usize, ErroConversor> {
+    let raiz = ler_arvore(reader)?;
+
+    let mut ocorrencias = HashMap::new();
+    contar(&raiz, &mut Vec::new(), &mut ocorrencias);
+    // Em um empate, o caminho mais curto: o `<Funcionario>` e não o `<CPF>` dentro dele.
+    let caminho_linha = ocorrencias
+        .into_iter()
+        .filter(|(_, quantidade)| *quantidade > 1)
+        .max_by(|(caminho_a, quantidade_a), (caminho_b, quantidade_b)| quantidade_a.cmp(quantidade_b).then(caminho_b.len().cmp(&caminho_a.len())).then(caminho_b.cmp(caminho_a)))
+        .map(|(caminho, _)| caminho)
+        .unwrap_or_else(|| vec![raiz.nome.clone()]);
+
+    let mut linhas = Vec::new();
+    montar_linhas(&raiz, &mut vec![raiz.nome.clone()], &caminho_linha, &Vec::new(), &mut linhas);
+
+    let mut colunas: Vec<String> = Vec::new();
+    for linha in &linhas {
+        for (coluna, _) in linha {
+            if !colunas.contains(coluna) {
+                colunas.push(coluna.clone());
+            }
+        }
+    }
+    if linhas.is_empty() || colunas.is_empty() {
+        return Ok(0);
+    }
+
+    destino.iniciar(&colunas.iter().map(String::as_str).collect::<Vec<_>>())?;
+    for linha in &linhas {
+        let registro: Vec<String> = colunas
+            .iter()
+            .map(|coluna| {
+                linha
+                    .iter()
+                    .filter(|(nome, _)| nome == coluna)
+                    .map(|(_, valor)| valor.as_str())
+                    .collect::<Vec<_>>()
+                    .join(SEPARADOR_REPETIDOS)
+            })
+            .collect();
+        destino.gravar(&registro.iter().map(String::as_str).collect::<Vec<_>>())?;
+    }
+    Ok(linhas.len())
+}
+
+/// Quantidade de ocorrências de cada caminho de elemento, a partir do raiz.
+fn contar(no: &No, caminho: &mut Vec<String>, ocorrencias: &mut HashMap<Vec<String>, usize>) {
+    caminho.push(no.nome.clone());
+    *ocorrencias.entry(caminho.clone()).or_insert(0) += 1;
+    for filho in &no.filhos {
+        contar(filho, caminho, ocorrencias);
+    }
+    caminho.pop();
+}
+
+/// Percorre a árvore até os elementos de `caminho_linha`, acumulando em `contexto` as
+/// colunas dos elementos do caminho que não levam a nenhuma linha.
+pub(crate) fn montar_linhas(no: &No, caminho: &mut Vec<String>, caminho_linha: &[String], contexto: &[(String, String)], linhas: &mut Vec<Vec<(String, String)>>) {
+    if caminho.as_slice() == caminho_linha {
+        let mut linha = contexto.to_vec();
+        achatar(no, caminho, &mut linha);
+        linhas.push(linha);
+        return;
+    }
+
+    let mut contexto = contexto.to_vec();
+    for (atributo, valor) in &no.atributos {
+        contexto.push((coluna(caminho, Some(atributo)), valor.clone()));
+    }
+    let profundidade = caminho.len();
+    let (com_linhas, sem_linhas): (Vec<&No>, Vec<&No>) = no
+        .filhos
+        .iter()
+        .partition(|filho| caminho_linha.len() > profundidade && caminho_linha[..profundidade] == caminho[..] && caminho_linha[profundidade] == filho.nome);
+    for filho in sem_linhas {
+        caminho.push(filho.nome.clone());
+        achatar(filho, caminho, &mut contexto);
+        caminho.pop();
+    }
+    if no.filhos.is_empty() && !no.texto.is_empty() {
+        contexto.push((coluna(caminho, None), no.texto.clone()));
+    }
+
+    for filho in com_linhas {
+        caminho.push(filho.nome.clone());
+        montar_linhas(filho, caminho, caminho_linha, &contexto, linhas);
+        caminho.pop();
+    }
+}
+
+/// Colunas de um elemento e de todos os elementos dentro dele.
+fn achatar(no: &No, caminho: &mut Vec<String>, colunas: &mut Vec<(String, String)>) {
+    for (atributo, valor) in &no.atributos {
+        colunas.push((coluna(caminho, Some(atributo)), valor.clone()));
+    }
+    if no.filhos.is_empty() {
+        colunas.push((coluna(caminho, None), no.texto.clone()));
+        return;
+    }
+    for filho in &no.filhos {
+        caminho.push(filho.nome.clone());
+        achatar(filho, caminho, colunas);
+        caminho.pop();
+    }
+}
+
+/// Nome da coluna: o caminho sem o elemento raiz, que é o mesmo em todas as linhas.
+fn coluna(caminho: &[String], atributo: Option<&str>) -> String {
+    let mut partes: Vec<&str> = caminho.iter().skip(1).map(String::as_str).collect();
+    let atributo = atributo.map(|nome| format!("@{}", nome));
+    if let Some(atributo) = &atributo {
+        partes.push(atributo);
+    }
+    if partes.is_empty() {
+        // O próprio elemento raiz, quando ele contém apenas texto.
+        return caminho.first().cloned().unwrap_or_default();
+    }
+    partes.join("/")
+}
+
+fn novo_no(inicio: &BytesStart) -> Result<No, String> {
+    let mut atributos = Vec::new();
+    for atributo in inicio.attributes() {
+        let atributo = atributo.map_err(|erro| tr!("XML mal formado: {}", erro))?;
+        // Declarações de namespace não são dados.
+        if atributo.key.as_ref().starts_with("xmlns") {
+            continue;
+        }
+        let nome = atributo.key.local_name().as_ref().to_string();
+        atributos.push((nome, atributo.normalized_value(XmlVersion::Implicit1_0).map_err(mensagem_xml)?.into_owned()));
+    }
+    Ok(No {
+        nome: inicio.local_name().as_ref().to_string(),
+        atributos,
+        texto: String::new(),
+        filhos: Vec::new(),
+    })
+}
+
+/// Lê o documento inteiro em uma árvore de [`No`], com os textos sem os espaços das pontas.
+pub(crate) fn ler_arvore<R: BufRead>(reader: R) -> Result<No, ErroConversor> {
+    let mut reader = Reader::from_reader(Decodificado::new(reader));
+    montar_arvore(&mut reader).map_err(|mensagem| ErroLeitura::erro(reader.get_ref().linha(), None, mensagem))
+}
+
+fn montar_arvore<R: BufRead>(reader: &mut Reader<Decodificado<R>>) -> Result<No, String> {
+    let mut buf = Vec::new();
+    let mut pilha: Vec<No> = Vec::new();
+
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf).map_err(mensagem_xml)? {
+            Event::Start(inicio) => pilha.push(novo_no(&inicio)?),
+            Event::Empty(inicio) => {
+                let no = novo_no(&inicio)?;
+                match pilha.last_mut() {
+                    Some(pai) => pai.filhos.push(no),
+                    None => return Ok(no),
+                }
+            }
+            Event::End(_) => {
+                let mut no = pilha.pop().ok_or(tr!("Fechamento de elemento sem abertura no XML."))?;
+                no.texto = no.texto.trim().to_string();
+                match pilha.last_mut() {
+                    Some(pai) => pai.filhos.push(no),
+                    None => return Ok(no),
+                }
+            }
+            Event::Text(conteudo) => {
+                if let Some(no) = pilha.last_mut() {
+                    no.texto.push_str(&conteudo.xml10_content());
+                }
+            }
+            Event::CData(conteudo) => {
+                if let Some(no) = pilha.last_mut() {
+                    no.texto.push_str(&conteudo.xml10_content());
+                }
+            }
+            Event::GeneralRef(referencia) => {
+                if let Some(no) = pilha.last_mut() {
+                    match referencia.resolve_char_ref().map_err(mensagem_xml)? {
+                        Some(caractere) => no.texto.push(caractere),
+                        None => no.texto.push_str(
+                            resolve_predefined_entity(&referencia).ok_or_else(|| tr!("Entidade XML desconhecida: &{};", &*referencia))?,
+                        ),
+                    }
+                }
+            }
+            Event::Eof => {
+                return Err(match pilha.last() {
+                    Some(no) => tr!("O XML terminou antes do fechamento de <{}>.", no.nome),
+                    None => tr!("O arquivo não contém um elemento XML.").to_string(),
+                })
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::io::Write;
+
+use crate::erro::ErroConversor;
+use crate::saida::Destino;
+use crate::valor::{FormatoMonetario, Quantia};
+
+/// Colunas alinhadas à direita, exibidas como `1.234,56` e somadas nos totais.
+const COLUNAS_NUMERICAS: [&str; 2] = ["Valor", "MetaPremio"];
+
+/// Colunas com os dados da empresa, exibidos no título de cada tabela em vez de repetidos
+/// em todas as linhas.
+const COLUNAS_EMPRESA: [&str; 3] = ["Fantasia", "Razao", "CNPJ"];
+
+const ESTILO: &str = "body{font-family:system-ui,-apple-system,'Segoe UI',Roboto,sans-serif;margin:2rem;color:#1f2933}\
+h1{font-size:1.5rem;margin-bottom:.25rem}\
+h2{font-size:1.15rem;margin:2rem 0 .25rem}\
+.gerado,.empresa{color:#616e7c;margin:0 0 .75rem}\
+table{border-collapse:collapse;width:100%;font-size:.9rem}\
+th,td{border:1px solid #cbd2d9;padding:.35rem .6rem;text-align:left}\
+thead th{background:#243b53;color:#fff}\
+tbody tr:nth-child(even){background:#f0f4f8}\
+tfoot td{font-weight:bold;background:#d9e2ec}\
+.numero{text-align:right;white-space:nowrap}\
+@media print{body{margin:0}thead th{background:#fff;color:#000}}";
+
+/// Grava as linhas como um relatório HTML autônomo, com o estilo embutido, para ser aberto
+/// no navegador ou impresso.
+///
+/// Cada empresa tem o seu título (razão social, fantasia e CNPJ) e uma tabela com os
+/// funcionários, terminada pelo total de `Valor` e `MetaPremio`; com mais de uma empresa,
+/// o relatório termina com o total geral. As linhas ficam na memória e o documento é
+/// gravado em [`SaidaHtml::finalizar`].
+pub struct SaidaHtml<W: Write> {
+    writer: W,
+    titulo: String,
+    gerado_em: String,
+    cabecalho: Vec<String>,
+    /// Posições das colunas de [`COLUNAS_EMPRESA`] presentes no cabeçalho.
+    colunas_empresa: Vec<usize>,
+    empresas: Vec<Empresa>,
+    iniciado: bool,
+}
+
+/// Funcionários seguidos de uma mesma empresa.
+struct Empresa {
+    /// Valores das colunas de [`COLUNAS_EMPRESA`], na ordem de `colunas_empresa`.
+    dados: Vec<String>,
+    linhas: Vec<Vec<String>>,
+}
+
+impl<W: Write> SaidaHtml<W> {
+    /// `titulo` é o título do relatório, normalmente o nome do arquivo de origem, e
+    /// `gerado_em` a data exibida abaixo dele.
+    pub fn new(writer: W, titulo: &str, gerado_em: &str) -> SaidaHtml<W> {
+        SaidaHtml {
+            writer,
+            titulo: titulo.to_string(),
+            gerado_em: gerado_em.to_string(),
+            cabecalho: Vec::new(),
+            colunas_empresa: Vec::new(),
+            empresas: Vec::new(),
+            iniciado: false,
+        }
+    }
+
+    /// Grava o documento e devolve o escritor. Sem [`Destino::iniciar`] nada é gravado.
+    pub fn finalizar(mut self) -> Result<W, ErroConversor> {
+        Destino::finalizar(&mut self)?;
+        Ok(self.writer)
+    }
+
+    /// Razão social (ou fantasia) como título, seguida dos demais dados da empresa.
+    fn titulo_empresa(&self, html: &mut String, empresa: &Empresa) {
+        let dado = |nome: &str| {
+            self.colunas_empresa
+                .iter()
+                .position(|&indice| self.cabecalho[indice] == nome)
+                .map(|posicao| empresa.dados[posicao].as_str())
+                .filter(|valor| !valor.trim().is_empty())
+        };
+        let Some(titulo) = dado("Razao").or(dado("Fantasia")) else {
+            return;
+        };
+        html.push_str(&format!("<h2>{}</h2>\n", escapar(titulo)));
+        let detalhes: Vec<String> = [("Fantasia", tr!("Nome fantasia")), ("CNPJ", "CNPJ")]
+            .iter()
+            .filter_map(|(coluna, rotulo)| Some(format!("{}: {}", rotulo, escapar(dado(coluna)?))))
+            .collect();
+        if !detalhes.is_empty() {
+            html.push_str(&format!("<p class=\"empresa\">{}</p>\n", detalhes.join(" &middot; ")));
+        }
+    }
+
+    fn linha_cabecalho(&self, html: &mut String, colunas: &[usize]) {
+        html.push_str("<thead><tr>");
+        for &indice in colunas {
+            html.push_str(&format!("<th{}>{}</th>", self.classe(indice), escapar(&self.cabecalho[indice])));
+        }
+        html.push_str("</tr></thead>\n");
+    }
+
+    /// Rodapé com a quantidade de funcionários e a soma das colunas numéricas de `linhas`.
+    fn linha_total(&self, html: &mut String, colunas: &[usize], rotulo: &str, linhas: &[Vec<String>]) {
+        html.push_str("<tfoot><tr>");
+        for (posicao, &indice) in colunas.iter().enumerate() {
+            let texto = if self.numerica(indice) {
+                let soma: Quantia = linhas.iter().filter_map(|linha| linha.get(indice).and_then(|valor| Quantia::ler(valor, None))).sum();
+                FormatoMonetario::PtBr.formatar(soma)
+            } else if posicao == 0 {
+                tr!("{} ({} funcionário(s))", rotulo, linhas.len())
+            } else {
+                String::new()
+            };
+            html.push_str(&format!("<td{}>{}</td>", self.classe(indice), escapar(&texto)));
+        }
+        html.push_str("</tr></tfoot>\n");
+    }
+
+    fn numerica(&self, indice: usize) -> bool {
+        COLUNAS_NUMERICAS.contains(&self.cabecalho[indice].as_str())
+    }
+
+    fn classe(&self, indice: usize) -> &'static str {
+        if self.numerica(indice) {
+            " class=\"numero\""
+        } else {
+            ""
+        }
+    }
+
+    /// Texto da célula: os números das colunas numéricas como `1.234,56`, os demais como vieram.
+    fn texto(&self, indice: usize, valor: &str) -> String {
+        match Quantia::ler(valor, None).filter(|_| self.numerica(indice)) {
+            Some(numero) => FormatoMonetario::PtBr.formatar(numero),
+            None => valor.to_string(),
+        }
+    }
+}
+
+impl<W: Write> Destino for SaidaHtml<W> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.cabecalho = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        self.colunas_empresa = (0..cabecalho.len()).filter(|&indice| COLUNAS_EMPRESA.contains(&cabecalho[indice])).collect();
+        self.iniciado = true;
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        let dados: Vec<String> = self.colunas_empresa.iter().map(|&indice| registro.get(indice).copied().unwrap_or_default().to_string()).collect();
+        if self.empresas.last().is_none_or(|empresa| empresa.dados != dados) {
+            self.empresas.push(Empresa { dados, linhas: Vec::new() });
+        }
+        if let Some(empresa) = self.empresas.last_mut() {
+            empresa.linhas.push(registro.iter().map(|valor| valor.to_string()).collect());
+        }
+        Ok(())
+    }
+
+    fn finalizar(&mut self) -> Result<(), ErroConversor> {
+        if !self.iniciado {
+            return Ok(());
+        }
+
+        let mut html = String::new();
+        html.push_str(&format!("<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n", tr!("pt-BR")));
+        html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+        html.push_str(&format!("<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n", escapar(&self.titulo), ESTILO));
+        html.push_str(&format!("<h1>{}</h1>\n<p class=\"gerado\">{}</p>\n", escapar(&self.titulo), escapar(&tr!("Gerado em {}", self.gerado_em))));
+
+        let colunas: Vec<usize> = (0..self.cabecalho.len()).filter(|indice| !self.colunas_empresa.contains(indice)).collect();
+        for empresa in &self.empresas {
+            html.push_str("<section>\n");
+            self.titulo_empresa(&mut html, empresa);
+            html.push_str("<table>\n");
+            self.linha_cabecalho(&mut html, &colunas);
+            html.push_str("<tbody>\n");
+            for linha in &empresa.linhas {
+                html.push_str("<tr>");
+                for &indice in &colunas {
+                    let valor = linha.get(indice).map(String::as_str).unwrap_or_default();
+                    html.push_str(&format!("<td{}>{}</td>", self.classe(indice), escapar(&self.texto(indice, valor))));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</tbody>\n");
+            self.linha_total(&mut html, &colunas, tr!("Total"), &empresa.linhas);
+            html.push_str("</table>\n</section>\n");
+        }
+
+        if self.empresas.len() > 1 {
+            let todas: Vec<Vec<String>> = self.empresas.iter().flat_map(|empresa| empresa.linhas.iter().cloned()).collect();
+            html.push_str(&format!("<section>\n<h2>{}</h2>\n", tr!("Total geral")));
+            html.push_str(&format!("<p class=\"empresa\">{}</p>\n<table>\n", tr!("{} empresa(s)", self.empresas.len())));
+            self.linha_cabecalho(&mut html, &colunas);
+            self.linha_total(&mut html, &colunas, tr!("Total geral"), &todas);
+            html.push_str("</table>\n</section>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+
+        self.writer.write_all(html.as_bytes())?;
+        self.writer.flush()?;
+        self.iniciado = false;
+        Ok(())
+    }
+}
+
+/// Texto com `&`, `<`, `>` e aspas trocados pelas entidades do HTML.
+fn escapar(texto: &str) -> String {
+    let mut escapado = String::with_capacity(texto.len());
+    for caractere in texto.chars() {
+        match caractere {
+            '&' => escapado.push_str("&amp;"),
+            '<' => escapado.push_str("&lt;"),
+            '>' => escapado.push_str("&gt;"),
+            '"' => escapado.push_str("&quot;"),
+            '\'' => escapado.push_str("&#39;"),
+            _ => escapado.push(caractere),
+        }
+    }
+    escapado
+}
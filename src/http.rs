@@ -0,0 +1,128 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::configuracao::ConfiguracaoHttp;
+
+/// Cliente usado para baixar os XML de endereços `http://` e `https://`.
+const PROGRAMA: &str = "curl";
+
+/// Indica se a entrada é um endereço a ser baixado, em vez de um arquivo local.
+pub fn eh_url(entrada: &str) -> bool {
+    let entrada = entrada.to_ascii_lowercase();
+    entrada.starts_with("http://") || entrada.starts_with("https://")
+}
+
+/// Pasta temporária com os XML baixados, apagada quando é descartada.
+pub struct PastaTemporaria(PathBuf);
+
+impl Drop for PastaTemporaria {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Troca os endereços `http(s)://` de `entradas` pelos XML baixados numa pasta temporária,
+/// com as credenciais da seção `[http]` da configuração.
+pub fn baixar_entradas(entradas: Vec<String>, config: &ConfiguracaoHttp) -> Result<(Vec<String>, Option<PastaTemporaria>), Box<dyn Error>> {
+    if !entradas.iter().any(|entrada| eh_url(entrada)) {
+        return Ok((entradas, None));
+    }
+    let instante = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+    let pasta = PastaTemporaria(env::temp_dir().join(format!("converterxmlcsv-{}-{}", process::id(), instante)));
+    fs::create_dir(&pasta.0)?;
+
+    let mut locais = Vec::new();
+    for (indice, entrada) in entradas.into_iter().enumerate() {
+        if !eh_url(&entrada) {
+            locais.push(entrada);
+            continue;
+        }
+        // Uma subpasta por endereço, para que arquivos de mesmo nome não se sobrescrevam.
+        let destino = pasta.0.join(indice.to_string());
+        fs::create_dir(&destino)?;
+        let arquivo = destino.join(nome_arquivo(&entrada));
+        baixar(&entrada, &arquivo, config)?;
+        log::info!("{} baixado em {}", entrada, arquivo.display());
+        locais.push(arquivo.display().to_string());
+    }
+    Ok((locais, Some(pasta)))
+}
+
+/// Nome do arquivo baixado: o último trecho do endereço, sem a consulta (`?...`), ou
+/// `download.xml` quando ele não tem a extensão de um XML, `.gz` ou `.zip`.
+fn nome_arquivo(endereco: &str) -> String {
+    let caminho = endereco.split(['?', '#']).next().unwrap_or_default();
+    let nome = caminho.rsplit('/').next().unwrap_or_default();
+    let extensao = Path::new(nome).extension().and_then(|extensao| extensao.to_str()).unwrap_or_default().to_lowercase();
+    if ["xml", "gz", "zip"].contains(&extensao.as_str()) && !nome.contains(['\\', ':']) {
+        nome.to_string()
+    } else {
+        "download.xml".to_string()
+    }
+}
+
+/// Baixa `endereco` para `arquivo`. As credenciais vão para o curl pela entrada padrão, e não
+/// como argumentos, para que não apareçam na lista de processos.
+fn baixar(endereco: &str, arquivo: &Path, config: &ConfiguracaoHttp) -> Result<(), Box<dyn Error>> {
+    let mut opcoes = vec![format!("url = {}", citar(endereco)), format!("output = {}", citar(&arquivo.to_string_lossy()))];
+    if let Some(usuario) = &config.user {
+        let senha = env::var("HTTP_PASSWORD").ok().or_else(|| config.password.clone()).unwrap_or_default();
+        opcoes.push(format!("user = {}", citar(&format!("{}:{}", usuario, senha))));
+    }
+    for cabecalho in config.headers.iter().flatten() {
+        opcoes.push(format!("header = {}", citar(cabecalho)));
+    }
+
+    let mut processo = Command::new(PROGRAMA)
+        .args(["--silent", "--show-error", "--fail", "--location", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|erro| tr!("Não foi possível executar o {}, necessário para baixar {}: {}.", PROGRAMA, endereco, erro))?;
+    if let Some(mut entrada) = processo.stdin.take() {
+        entrada.write_all(opcoes.join("\n").as_bytes())?;
+    }
+    let saida = processo.wait_with_output()?;
+    if !saida.status.success() {
+        return Err(tr!("Não foi possível baixar {} ({}): {}", endereco, saida.status, String::from_utf8_lossy(&saida.stderr).trim()).into());
+    }
+    Ok(())
+}
+
+/// Texto entre aspas duplas, como nas opções do arquivo de configuração do curl.
+pub fn citar(texto: &str) -> String {
+    format!("\"{}\"", texto.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enderecos() {
+        assert!(eh_url("https://erp.empresa.com.br/comissao.xml"));
+        assert!(eh_url("HTTP://erp/comissao.xml"));
+        assert!(!eh_url("comissao.xml"));
+        assert!(!eh_url("s3://balde/comissao.xml"));
+    }
+
+    #[test]
+    fn nome_do_arquivo_baixado() {
+        assert_eq!(nome_arquivo("https://erp/exportar/comissao.xml?mes=05#inicio"), "comissao.xml");
+        assert_eq!(nome_arquivo("https://erp/lote.ZIP"), "lote.ZIP");
+        assert_eq!(nome_arquivo("https://erp/exportar?formato=xml"), "download.xml");
+        assert_eq!(nome_arquivo("https://erp/"), "download.xml");
+        assert_eq!(nome_arquivo("https://erp/..%5Cc:%5Ca.xml"), "download.xml");
+    }
+
+    #[test]
+    fn texto_citado() {
+        assert_eq!(citar("ana:s\"e\\nha\n"), r#""ana:s\"e\\nha\n""#);
+    }
+}
@@ -0,0 +1,327 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::erro::ErroConversor;
+use crate::saida::Destino;
+use crate::valor::Quantia;
+
+/// Variáveis disponíveis fora da repetição, além das colunas da primeira linha.
+const VARIAVEIS: [&str; 4] = ["arquivo", "data", "quantidade", "total"];
+
+/// Filtros aceitos depois de `|`, como no Jinja.
+const FILTROS: [&str; 9] = ["upper", "lower", "trim", "digits", "cents", "add", "truncate", "ljust", "rjust"];
+
+/// Modelo de texto de `--template`, com a sintaxe do Jinja: `{{ expressão }}`,
+/// `{% for f in funcionarios %}...{% endfor %}` e comentários `{# ... #}`.
+///
+/// Fora da repetição valem as `VARIAVEIS` e as colunas da primeira linha (ex.:
+/// `{{ CNPJ }}`); dentro dela, as colunas do funcionário (`{{ f.CPF }}`), `loop.index` (a
+/// partir de 1) e `loop.index0`. A quebra de linha logo após `{% ... %}` ou `{# ... #}` é
+/// descartada, para que as linhas das tags não apareçam no arquivo gerado.
+///
+/// Os filtros permitem montar leiautes de largura fixa, como os de remessa bancária:
+/// `{{ f.Valor | cents | rjust(13, "0") }}` grava o valor em centavos com 13 dígitos.
+#[derive(Debug, Clone)]
+pub struct Leiaute {
+    partes: Vec<Parte>,
+}
+
+#[derive(Debug, Clone)]
+enum Parte {
+    Texto(String),
+    Valor(Expressao),
+    /// `{% for variavel in funcionarios %}`, com as partes repetidas para cada linha.
+    Repeticao(String, Vec<Parte>),
+}
+
+/// Nome de uma variável (ex.: `f.Valor`) seguido dos filtros aplicados ao valor dela.
+#[derive(Debug, Clone)]
+struct Expressao {
+    nome: String,
+    filtros: Vec<Filtro>,
+}
+
+#[derive(Debug, Clone)]
+enum Filtro {
+    Maiusculas,
+    Minusculas,
+    Aparar,
+    /// Só os dígitos, ex.: `11.222.333/0001-81` → `11222333000181`.
+    Digitos,
+    /// Valor em centavos, sem separadores; vazio quando não é número.
+    Centavos,
+    /// Soma um número inteiro, ex.: `loop.index | add(1)` depois de uma linha de cabeçalho.
+    Somar(i64),
+    Cortar(usize),
+    /// Completa à direita (`ljust`) ou à esquerda (`rjust`) até a largura, cortando os textos
+    /// maiores, para que cada campo ocupe sempre a mesma largura.
+    Alinhar { largura: usize, preenchimento: char, a_esquerda: bool },
+}
+
+impl Leiaute {
+    /// Lê o modelo do arquivo de `--template`.
+    pub fn carregar(caminho: &Path) -> Result<Leiaute, ErroConversor> {
+        let conteudo = fs::read_to_string(caminho)
+            .map_err(|erro| ErroConversor::EntradaInvalida(tr!("Não foi possível ler o modelo '{}': {}", caminho.display(), erro)))?;
+        Leiaute::from_texto(&conteudo).map_err(|erro| ErroConversor::EntradaInvalida(tr!("Modelo '{}' inválido: {}", caminho.display(), erro)))
+    }
+
+    pub fn from_texto(texto: &str) -> Result<Leiaute, String> {
+        let mut pilha: Vec<(String, Vec<Parte>)> = Vec::new();
+        let mut partes = Vec::new();
+        let mut resto = texto;
+        while let Some(inicio) = resto.find('{') {
+            let abertura = &resto[inicio..];
+            let fechamento = if abertura.starts_with("{{") {
+                "}}"
+            } else if abertura.starts_with("{%") {
+                "%}"
+            } else if abertura.starts_with("{#") {
+                "#}"
+            } else {
+                partes.push(Parte::Texto(resto[..inicio + 1].to_string()));
+                resto = &resto[inicio + 1..];
+                continue;
+            };
+            if inicio > 0 {
+                partes.push(Parte::Texto(resto[..inicio].to_string()));
+            }
+            let fim = abertura[2..].find(fechamento).ok_or_else(|| tr!("falta fechar '{}' com '{}'.", &abertura[..2], fechamento))? + 2;
+            let conteudo = abertura[2..fim].trim();
+            resto = &abertura[fim + 2..];
+
+            match fechamento {
+                "}}" => partes.push(Parte::Valor(Expressao::from_texto(conteudo)?)),
+                "%}" => match conteudo.split_whitespace().collect::<Vec<_>>().as_slice() {
+                    ["for", variavel, "in", "funcionarios"] => {
+                        if !pilha.is_empty() {
+                            return Err(tr!("um {% for %} não pode ficar dentro de outro.").to_string());
+                        }
+                        pilha.push((variavel.to_string(), std::mem::take(&mut partes)));
+                    }
+                    ["endfor"] => {
+                        let (variavel, anteriores) = pilha.pop().ok_or(tr!("{% endfor %} sem o {% for %} correspondente."))?;
+                        let repetidas = std::mem::replace(&mut partes, anteriores);
+                        partes.push(Parte::Repeticao(variavel, repetidas));
+                    }
+                    _ => return Err(tr!("tag desconhecida: {% {} %}. Use {% for f in funcionarios %} e {% endfor %}.", conteudo)),
+                },
+                _ => {}
+            }
+            if fechamento != "}}" {
+                resto = resto.strip_prefix("\r\n").or_else(|| resto.strip_prefix('\n')).unwrap_or(resto);
+            }
+        }
+        if !resto.is_empty() {
+            partes.push(Parte::Texto(resto.to_string()));
+        }
+        if !pilha.is_empty() {
+            return Err(tr!("falta fechar o {% for %} com {% endfor %}.").to_string());
+        }
+        Ok(Leiaute { partes })
+    }
+
+    /// Confere se todas as variáveis do modelo existem para as colunas do arquivo gerado.
+    fn validar(&self, cabecalho: &[String]) -> Result<(), ErroConversor> {
+        fn validar_partes(partes: &[Parte], repeticao: Option<&str>, cabecalho: &[String]) -> Result<(), String> {
+            for parte in partes {
+                match parte {
+                    Parte::Texto(_) => {}
+                    Parte::Valor(expressao) => {
+                        let nome = expressao.nome.as_str();
+                        let valido = match (repeticao, nome.split_once('.')) {
+                            (Some(variavel), Some((prefixo, campo))) if prefixo == variavel => cabecalho.iter().any(|coluna| coluna == campo),
+                            (Some(_), Some(("loop", campo))) => campo == "index" || campo == "index0",
+                            (_, Some(_)) => false,
+                            (_, None) => VARIAVEIS.contains(&nome) || cabecalho.iter().any(|coluna| coluna == nome),
+                        };
+                        if !valido {
+                            return Err(tr!("Variável desconhecida em --template: '{}'. Use {} ou uma das colunas: {}.", nome, VARIAVEIS.join(", "), cabecalho.join(", ")));
+                        }
+                    }
+                    Parte::Repeticao(variavel, partes) => validar_partes(partes, Some(variavel), cabecalho)?,
+                }
+            }
+            Ok(())
+        }
+        validar_partes(&self.partes, None, cabecalho).map_err(ErroConversor::EntradaInvalida)
+    }
+}
+
+impl Expressao {
+    fn from_texto(texto: &str) -> Result<Expressao, String> {
+        let mut trechos = texto.split('|').map(str::trim);
+        let nome = trechos.next().unwrap_or_default();
+        if nome.is_empty() || !nome.chars().all(|caractere| caractere.is_alphanumeric() || caractere == '_' || caractere == '.') {
+            return Err(tr!("expressão inválida: {{ {} }}.", texto));
+        }
+        let filtros = trechos.map(Filtro::from_texto).collect::<Result<_, _>>()?;
+        Ok(Expressao { nome: nome.to_string(), filtros })
+    }
+}
+
+impl Filtro {
+    fn from_texto(texto: &str) -> Result<Filtro, String> {
+        let (nome, argumentos) = match texto.split_once('(') {
+            Some((nome, argumentos)) => {
+                let argumentos = argumentos.strip_suffix(')').ok_or_else(|| tr!("falta fechar o parêntese em '{}'.", texto))?;
+                (nome.trim(), argumentos.split(',').map(str::trim).filter(|argumento| !argumento.is_empty()).collect())
+            }
+            None => (texto, Vec::new()),
+        };
+        let numero = |posicao: usize| -> Result<i64, String> {
+            argumentos.get(posicao).and_then(|argumento: &&str| argumento.parse().ok()).ok_or_else(|| tr!("o filtro '{}' espera um número, ex.: {}(10).", nome, nome))
+        };
+        let largura = || numero(0).and_then(|largura| usize::try_from(largura).map_err(|_| tr!("largura inválida em '{}'.", texto)));
+        let preenchimento = || -> Result<char, String> {
+            match argumentos.get(1) {
+                None => Ok(' '),
+                Some(argumento) => {
+                    let mut caracteres = argumento.trim_matches(['"', '\'']).chars();
+                    match (caracteres.next(), caracteres.next()) {
+                        (Some(caractere), None) => Ok(caractere),
+                        _ => Err(tr!("o preenchimento de '{}' deve ser um único caractere, ex.: \"0\".", texto)),
+                    }
+                }
+            }
+        };
+        match nome {
+            "upper" => Ok(Filtro::Maiusculas),
+            "lower" => Ok(Filtro::Minusculas),
+            "trim" => Ok(Filtro::Aparar),
+            "digits" => Ok(Filtro::Digitos),
+            "cents" => Ok(Filtro::Centavos),
+            "add" => Ok(Filtro::Somar(numero(0)?)),
+            "truncate" => Ok(Filtro::Cortar(largura()?)),
+            "ljust" => Ok(Filtro::Alinhar { largura: largura()?, preenchimento: preenchimento()?, a_esquerda: true }),
+            "rjust" => Ok(Filtro::Alinhar { largura: largura()?, preenchimento: preenchimento()?, a_esquerda: false }),
+            _ => Err(tr!("filtro desconhecido: '{}'. Use um de: {}.", nome, FILTROS.join(", "))),
+        }
+    }
+
+    fn aplicar(&self, valor: String) -> String {
+        match self {
+            Filtro::Maiusculas => valor.to_uppercase(),
+            Filtro::Minusculas => valor.to_lowercase(),
+            Filtro::Aparar => valor.trim().to_string(),
+            Filtro::Digitos => valor.chars().filter(char::is_ascii_digit).collect(),
+            Filtro::Centavos => Quantia::ler(&valor, None).map(|numero| numero.centavos().to_string()).unwrap_or_default(),
+            Filtro::Somar(parcela) => valor.trim().parse::<i64>().map(|numero| (numero + parcela).to_string()).unwrap_or(valor),
+            Filtro::Cortar(largura) => valor.chars().take(*largura).collect(),
+            Filtro::Alinhar { largura, preenchimento, a_esquerda } => {
+                let texto: String = valor.chars().take(*largura).collect();
+                let complemento: String = std::iter::repeat_n(*preenchimento, largura - texto.chars().count()).collect();
+                if *a_esquerda {
+                    texto + &complemento
+                } else {
+                    complemento + &texto
+                }
+            }
+        }
+    }
+}
+
+/// Grava as linhas pelo [`Leiaute`] de `--template`, num arquivo de texto. As linhas ficam
+/// na memória e o texto é gravado em [`SaidaLeiaute::finalizar`].
+pub struct SaidaLeiaute<W: Write> {
+    writer: W,
+    leiaute: Leiaute,
+    /// Valores de `arquivo` e `data`.
+    arquivo: String,
+    data: String,
+    cabecalho: Vec<String>,
+    linhas: Vec<Vec<String>>,
+    iniciado: bool,
+}
+
+impl<W: Write> SaidaLeiaute<W> {
+    /// `arquivo` é o nome do arquivo de origem e `data` a data da geração, como devem
+    /// aparecer em `{{ arquivo }}` e `{{ data }}`.
+    pub fn new(writer: W, leiaute: Leiaute, arquivo: &str, data: &str) -> SaidaLeiaute<W> {
+        SaidaLeiaute {
+            writer,
+            leiaute,
+            arquivo: arquivo.to_string(),
+            data: data.to_string(),
+            cabecalho: Vec::new(),
+            linhas: Vec::new(),
+            iniciado: false,
+        }
+    }
+
+    /// Grava o texto e devolve o escritor. Sem [`Destino::iniciar`] nada é gravado.
+    pub fn finalizar(mut self) -> Result<W, ErroConversor> {
+        Destino::finalizar(&mut self)?;
+        Ok(self.writer)
+    }
+
+    fn coluna<'a>(&self, linha: Option<&'a Vec<String>>, nome: &str) -> &'a str {
+        let indice = self.cabecalho.iter().position(|coluna| coluna == nome);
+        linha.zip(indice).and_then(|(linha, indice)| linha.get(indice)).map(String::as_str).unwrap_or_default()
+    }
+
+    fn valor(&self, nome: &str, repeticao: Option<(&str, usize)>) -> String {
+        if let Some((variavel, indice)) = repeticao {
+            match nome.split_once('.') {
+                Some(("loop", "index")) => return (indice + 1).to_string(),
+                Some(("loop", "index0")) => return indice.to_string(),
+                Some((prefixo, campo)) if prefixo == variavel => return self.coluna(self.linhas.get(indice), campo).to_string(),
+                _ => {}
+            }
+        }
+        match nome {
+            "arquivo" => self.arquivo.clone(),
+            "data" => self.data.clone(),
+            "quantidade" => self.linhas.len().to_string(),
+            "total" => {
+                let total: Quantia = self.linhas.iter().filter_map(|linha| Quantia::ler(self.coluna(Some(linha), "Valor"), None)).sum();
+                format!("{:.2}", total)
+            }
+            coluna => self.coluna(self.linhas.first(), coluna).to_string(),
+        }
+    }
+
+    fn montar(&self, texto: &mut String, partes: &[Parte], repeticao: Option<(&str, usize)>) {
+        for parte in partes {
+            match parte {
+                Parte::Texto(trecho) => texto.push_str(trecho),
+                Parte::Valor(expressao) => {
+                    let valor = expressao.filtros.iter().fold(self.valor(&expressao.nome, repeticao), |valor, filtro| filtro.aplicar(valor));
+                    texto.push_str(&valor);
+                }
+                Parte::Repeticao(variavel, partes) => {
+                    for indice in 0..self.linhas.len() {
+                        self.montar(texto, partes, Some((variavel, indice)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<W: Write> Destino for SaidaLeiaute<W> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.cabecalho = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        self.leiaute.validar(&self.cabecalho)?;
+        self.iniciado = true;
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        self.linhas.push(registro.iter().map(|valor| valor.to_string()).collect());
+        Ok(())
+    }
+
+    fn finalizar(&mut self) -> Result<(), ErroConversor> {
+        if !self.iniciado {
+            return Ok(());
+        }
+        let mut texto = String::new();
+        self.montar(&mut texto, &self.leiaute.partes, None);
+        self.writer.write_all(texto.as_bytes())?;
+        self.writer.flush()?;
+        self.iniciado = false;
+        Ok(())
+    }
+}
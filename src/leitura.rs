@@ -0,0 +1,408 @@
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::codificacao::Decodificado;
+use crate::competencia::normalizar_mes_ano;
+use crate::erro::ErroConversor;
+use crate::modelo::{Empresa, Funcionario, Tipo, Valor};
+use crate::tipos;
+
+/// XML aberto para leitura em fluxo: os dados da empresa já foram lidos e os funcionários
+/// são lidos um a um, sem carregar o documento inteiro na memória.
+///
+/// Exportações consolidadas trazem várias `<Empresa>` no mesmo arquivo; elas são lidas em
+/// sequência com [`ArquivoXml::proxima_empresa`].
+pub struct ArquivoXml<R: BufRead> {
+    pub tipo: Tipo,
+    /// Dados da empresa atual; `funcionarios` fica vazio, pois eles vêm de [`ArquivoXml::funcionarios`].
+    pub empresa: Empresa,
+    pub funcionarios: Funcionarios<R>,
+}
+
+impl<R: BufRead> ArquivoXml<R> {
+    /// Passa para a próxima `<Empresa>` do XML, pulando os funcionários ainda não lidos da
+    /// atual. Retorna `false` quando não há outra empresa.
+    pub fn proxima_empresa(&mut self) -> Result<bool, ErroConversor> {
+        match self.funcionarios.proxima_empresa()? {
+            Some(empresa) => {
+                self.empresa = empresa;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Iterador sobre os `<Funcionario>` da empresa atual, na ordem do XML.
+pub struct Funcionarios<R: BufRead> {
+    reader: Reader<Decodificado<R>>,
+    buf: Vec<u8>,
+    /// Funcionários lidos antes de todos os dados da empresa aparecerem no XML.
+    pendentes: VecDeque<Funcionario>,
+    /// Verdadeiro depois de `</Empresa>` ou de um erro.
+    concluido: bool,
+    /// Linha da `<Empresa>` atual, para os erros nos seus dados.
+    linha_empresa: usize,
+    /// Quantidade de `<Funcionario>` já encontrados no XML, em todas as empresas.
+    lidos: usize,
+    /// Posição no XML do `<Funcionario>` sendo lido, a partir de 1.
+    atual: Option<usize>,
+}
+
+/// Abre o XML para leitura em fluxo, com o tipo informado ou, se `tipo` for `None`, detectado.
+///
+/// A detecção segue a mesma ordem de [`crate::ler_arquivo`]: atributo `tipo` de
+/// `<Arquivo>`, elemento raiz e, por último, o prefixo do nome do arquivo em `caminho`.
+///
+/// Os dados da empresa (`Fantasia`, `Razao`, `CNPJ` e `MesAno`) normalmente vêm antes dos
+/// funcionários; quando algum aparece depois, os funcionários anteriores a ele ficam na
+/// memória até que a empresa esteja completa.
+///
+/// XML declarados como ISO-8859-1 ou Windows-1252 são convertidos para UTF-8 na leitura.
+pub fn abrir_xml<R: BufRead>(reader: R, caminho: Option<&Path>, tipo: Option<Tipo>) -> Result<ArquivoXml<R>, ErroConversor> {
+    abrir(Decodificado::new(reader), caminho, tipo)
+}
+
+/// Como [`abrir_xml`], para um texto já em UTF-8, ignorando a codificação declarada.
+pub(crate) fn abrir_xml_utf8<'a>(conteudo: &'a str, caminho: Option<&Path>, tipo: Option<Tipo>) -> Result<ArquivoXml<&'a [u8]>, ErroConversor> {
+    abrir(Decodificado::utf8(conteudo.as_bytes()), caminho, tipo)
+}
+
+fn abrir<R: BufRead>(reader: Decodificado<R>, caminho: Option<&Path>, tipo: Option<Tipo>) -> Result<ArquivoXml<R>, ErroConversor> {
+    let mut funcionarios = Funcionarios {
+        reader: Reader::from_reader(reader),
+        buf: Vec::new(),
+        pendentes: VecDeque::new(),
+        concluido: false,
+        linha_empresa: 1,
+        lidos: 0,
+        atual: None,
+    };
+
+    let Some(raiz) = funcionarios.proximo_elemento()? else {
+        return Err(funcionarios.erro(tr!("O arquivo não contém um elemento XML.")));
+    };
+    let tipo = funcionarios.resolver_tipo(&raiz, caminho, tipo)?;
+    if !funcionarios.procurar_empresa()? {
+        return Err(funcionarios.erro(tr!("O XML não possui o elemento <Empresa>.")));
+    }
+    let empresa = funcionarios.ler_empresa()?;
+
+    Ok(ArquivoXml { tipo, empresa, funcionarios })
+}
+
+/// Erro no conteúdo do XML, com a linha em que foi encontrado e, quando ocorre dentro de
+/// um `<Funcionario>`, a posição dele no arquivo.
+///
+/// As linhas são contadas até onde a leitura chegou, que costuma ser o fim da tag com
+/// problema; em tags que ocupam várias linhas, o número é aproximado.
+#[derive(Debug)]
+pub struct ErroLeitura {
+    pub linha: usize,
+    /// Posição do `<Funcionario>` no XML, a partir de 1, contando todas as empresas.
+    pub funcionario: Option<usize>,
+    pub mensagem: String,
+}
+
+impl ErroLeitura {
+    pub(crate) fn erro(linha: usize, funcionario: Option<usize>, mensagem: impl fmt::Display) -> ErroConversor {
+        ErroConversor::Xml(ErroLeitura { linha, funcionario, mensagem: mensagem.to_string() })
+    }
+}
+
+impl fmt::Display for ErroLeitura {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.funcionario {
+            Some(posicao) => write!(f, "{}", tr!("Linha {}, <Funcionario> #{}: {}", self.linha, posicao, self.mensagem)),
+            None => write!(f, "{}", tr!("Linha {}: {}", self.linha, self.mensagem)),
+        }
+    }
+}
+
+impl Error for ErroLeitura {}
+
+/// Elemento aberto com os dados que a leitura usa.
+struct Elemento {
+    nome: String,
+    /// Atributos `moeda` e `tipo`, os únicos usados pelo conversor.
+    atributo: Option<String>,
+    vazio: bool,
+    /// Linha em que a tag de abertura termina.
+    linha: usize,
+}
+
+impl Elemento {
+    fn new(inicio: &BytesStart, vazio: bool, atributo: &str, linha: usize) -> Result<Elemento, String> {
+        let nome = inicio.local_name().as_ref().to_string();
+        let invalido = |erro: &dyn fmt::Display| tr!("Atributo inválido em <{}>: {}", nome, erro);
+        let mut valor = None;
+        for item in inicio.attributes() {
+            let item = item.map_err(|erro| invalido(&erro))?;
+            if item.key.local_name().as_ref() == atributo {
+                valor = Some(item.normalized_value(XmlVersion::Implicit1_0).map_err(|erro| invalido(&erro))?.into_owned());
+            }
+        }
+        Ok(Elemento { nome, atributo: valor, vazio, linha })
+    }
+}
+
+impl<R: BufRead> Funcionarios<R> {
+    /// Próximo elemento aberto no nível atual, pulando os demais eventos; `None` ao fechar
+    /// o elemento pai ou ao fim do arquivo.
+    fn proximo_elemento(&mut self) -> Result<Option<Elemento>, ErroConversor> {
+        loop {
+            self.buf.clear();
+            let evento = self.reader.read_event_into(&mut self.buf).map_err(|erro| mal_formado(&self.reader, self.atual, erro))?;
+            let linha = self.reader.get_ref().linha();
+            let elemento = match evento {
+                Event::Start(inicio) => Elemento::new(&inicio, false, atributo_de(&inicio), linha),
+                Event::Empty(inicio) => Elemento::new(&inicio, true, atributo_de(&inicio), linha),
+                Event::End(_) | Event::Eof => return Ok(None),
+                _ => continue,
+            };
+            return elemento.map(Some).map_err(|mensagem| ErroLeitura::erro(linha, self.atual, mensagem));
+        }
+    }
+
+    /// Erro na linha em que a leitura está.
+    fn erro(&self, mensagem: impl fmt::Display) -> ErroConversor {
+        ErroLeitura::erro(self.reader.get_ref().linha(), self.atual, mensagem)
+    }
+
+    /// Pula o conteúdo do elemento aberto até o seu fechamento.
+    fn pular(&mut self, elemento: &Elemento) -> Result<(), ErroConversor> {
+        if !elemento.vazio {
+            self.ler_texto(elemento)?;
+        }
+        Ok(())
+    }
+
+    /// Texto do elemento aberto, sem os espaços das pontas; elementos internos são ignorados.
+    fn ler_texto(&mut self, elemento: &Elemento) -> Result<String, ErroConversor> {
+        let mut texto = String::new();
+        if elemento.vazio {
+            return Ok(texto);
+        }
+
+        let mut profundidade = 1;
+        loop {
+            self.buf.clear();
+            let evento = self.reader.read_event_into(&mut self.buf).map_err(|erro| mal_formado(&self.reader, self.atual, erro))?;
+            match evento {
+                Event::Start(_) => profundidade += 1,
+                Event::End(_) => {
+                    profundidade -= 1;
+                    if profundidade == 0 {
+                        return Ok(texto.trim().to_string());
+                    }
+                }
+                Event::Text(conteudo) if profundidade == 1 => texto.push_str(&conteudo.xml10_content()),
+                Event::CData(conteudo) if profundidade == 1 => texto.push_str(&conteudo.xml10_content()),
+                Event::GeneralRef(referencia) if profundidade == 1 => {
+                    let linha = self.reader.get_ref().linha();
+                    match referencia.resolve_char_ref().map_err(|erro| ErroLeitura::erro(linha, self.atual, tr!("Referência de caractere inválida em <{}>: {}", elemento.nome, erro)))? {
+                        Some(caractere) => texto.push(caractere),
+                        None => texto.push_str(resolve_predefined_entity(&referencia).ok_or_else(|| {
+                            ErroLeitura::erro(linha, self.atual, tr!("Entidade XML desconhecida em <{}>: &{};", elemento.nome, &*referencia))
+                        })?),
+                    }
+                }
+                Event::Eof => return Err(self.erro(tr!("O XML terminou antes do fechamento de <{}>.", elemento.nome))),
+                _ => {}
+            }
+        }
+    }
+
+    fn resolver_tipo(&mut self, raiz: &Elemento, caminho: Option<&Path>, tipo: Option<Tipo>) -> Result<Tipo, ErroConversor> {
+        let nao_detectado = || {
+            let arquivo = caminho.map_or(tr!("do XML").to_string(), |caminho| tr!("de {}", caminho.display()));
+            ErroConversor::TipoNaoReconhecido(tr!("Tipo de arquivo não suportado: não foi possível identificar o tipo {}. Informe-o com --type ou use --generic.", arquivo))
+        };
+        let pelo_nome = || caminho.and_then(tipos::por_caminho).map(|leitor| leitor.tipo());
+
+        if let Some(tipo) = tipo {
+            return Ok(tipo);
+        }
+        if raiz.nome == "Arquivo" {
+            return match &raiz.atributo {
+                Some(nome) => Tipo::from_nome(nome).ok_or_else(|| ErroConversor::TipoNaoReconhecido(tr!("Tipo de arquivo não suportado: '{}'. Use --generic para convertê-lo mesmo assim.", nome))),
+                None => pelo_nome().ok_or_else(nao_detectado),
+            };
+        }
+        tipos::por_raiz(&raiz.nome).map(|leitor| leitor.tipo()).or_else(pelo_nome).ok_or_else(nao_detectado)
+    }
+
+    /// Avança até a abertura da próxima `<Empresa>`, filha do elemento raiz; `false` quando
+    /// o elemento raiz termina antes.
+    fn procurar_empresa(&mut self) -> Result<bool, ErroConversor> {
+        while let Some(elemento) = self.proximo_elemento()? {
+            if elemento.nome == "Empresa" {
+                self.concluido = elemento.vazio;
+                self.linha_empresa = elemento.linha;
+                return Ok(true);
+            }
+            self.pular(&elemento)?;
+        }
+        Ok(false)
+    }
+
+    /// Pula o que resta da empresa atual e lê os dados da próxima, se houver.
+    fn proxima_empresa(&mut self) -> Result<Option<Empresa>, ErroConversor> {
+        self.pendentes.clear();
+        while !self.concluido {
+            if self.ler_proximo()?.is_none() {
+                self.concluido = true;
+            }
+        }
+        if !self.procurar_empresa()? {
+            return Ok(None);
+        }
+        self.ler_empresa().map(Some)
+    }
+
+    /// Lê os dados da empresa, guardando em `pendentes` os funcionários que vierem antes deles.
+    fn ler_empresa(&mut self) -> Result<Empresa, ErroConversor> {
+        let mut fantasia = None;
+        let mut razao = None;
+        let mut cnpj = None;
+        let mut mes_ano = None;
+
+        while !self.concluido && [&fantasia, &razao, &cnpj, &mes_ano].iter().any(|campo| campo.is_none()) {
+            let Some(elemento) = self.proximo_elemento()? else {
+                self.concluido = true;
+                break;
+            };
+            match elemento.nome.as_str() {
+                "Fantasia" => fantasia = Some(self.ler_texto(&elemento)?),
+                "Razao" => razao = Some(self.ler_texto(&elemento)?),
+                "CNPJ" => cnpj = Some(self.ler_texto(&elemento)?),
+                "MesAno" => mes_ano = Some(normalizar_mes_ano(&self.ler_texto(&elemento)?)),
+                "Funcionario" => {
+                    let funcionario = self.ler_funcionario(&elemento)?;
+                    self.pendentes.push_back(funcionario);
+                }
+                _ => self.pular(&elemento)?,
+            }
+        }
+
+        let linha = self.linha_empresa;
+        let ausente = |campo: &str| ErroLeitura::erro(linha, None, tr!("O elemento <Empresa> não possui o campo <{}>.", campo));
+        Ok(Empresa {
+            fantasia: fantasia.ok_or_else(|| ausente("Fantasia"))?,
+            razao: razao.ok_or_else(|| ausente("Razao"))?,
+            cnpj: cnpj.ok_or_else(|| ausente("CNPJ"))?,
+            mes_ano: mes_ano.ok_or_else(|| ausente("MesAno"))?,
+            funcionarios: None,
+        })
+    }
+
+    fn ler_funcionario(&mut self, funcionario: &Elemento) -> Result<Funcionario, ErroConversor> {
+        self.lidos += 1;
+        self.atual = Some(self.lidos);
+        let lido = self.ler_campos(funcionario);
+        self.atual = None;
+        lido
+    }
+
+    fn ler_campos(&mut self, funcionario: &Elemento) -> Result<Funcionario, ErroConversor> {
+        let mut cpf = None;
+        let mut valor = None;
+        let mut meta_premio = None;
+        let mut mes_ano = None;
+        let mut data_inicio = None;
+        let mut data_fim = None;
+        let mut parcela = None;
+
+        if !funcionario.vazio {
+            while let Some(elemento) = self.proximo_elemento()? {
+                match elemento.nome.as_str() {
+                    "CPF" => cpf = Some(self.ler_texto(&elemento)?),
+                    "Valor" => {
+                        let moeda = elemento.atributo.clone();
+                        valor = Some(Valor { moeda, valor: self.ler_texto(&elemento)? });
+                    }
+                    "MetaPremio" => meta_premio = Some(self.ler_texto(&elemento)?),
+                    "MesAno" => mes_ano = Some(normalizar_mes_ano(&self.ler_texto(&elemento)?)),
+                    "DataInicio" => data_inicio = Some(self.ler_texto(&elemento)?),
+                    "DataFim" => data_fim = Some(self.ler_texto(&elemento)?),
+                    "Parcela" => parcela = Some(self.ler_texto(&elemento)?),
+                    _ => self.pular(&elemento)?,
+                }
+            }
+        }
+
+        let posicao = self.atual;
+        let ausente = |campo: &str| {
+            let cpf = cpf.as_ref().map(|cpf| format!(" (CPF {})", cpf)).unwrap_or_default();
+            ErroLeitura::erro(funcionario.linha, posicao, tr!("Falta o campo <{}>{}.", campo, cpf))
+        };
+        Ok(Funcionario {
+            valor: valor.ok_or_else(|| ausente("Valor"))?,
+            cpf: cpf.clone().ok_or_else(|| ausente("CPF"))?,
+            meta_premio,
+            mes_ano,
+            data_inicio,
+            data_fim,
+            parcela,
+        })
+    }
+
+    /// Lê o próximo funcionário do XML, pulando os demais elementos da empresa.
+    fn ler_proximo(&mut self) -> Result<Option<Funcionario>, ErroConversor> {
+        while let Some(elemento) = self.proximo_elemento()? {
+            if elemento.nome == "Funcionario" {
+                return self.ler_funcionario(&elemento).map(Some);
+            }
+            self.pular(&elemento)?;
+        }
+        Ok(None)
+    }
+}
+
+impl<R: BufRead> Iterator for Funcionarios<R> {
+    type Item = Result<Funcionario, ErroConversor>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(funcionario) = self.pendentes.pop_front() {
+            return Some(Ok(funcionario));
+        }
+        if self.concluido {
+            return None;
+        }
+
+        let proximo = self.ler_proximo();
+        if !matches!(proximo, Ok(Some(_))) {
+            self.concluido = true;
+        }
+        proximo.transpose()
+    }
+}
+
+/// Erro de XML mal formado na linha em que a leitura parou.
+fn mal_formado<R: BufRead>(reader: &Reader<Decodificado<R>>, atual: Option<usize>, erro: quick_xml::Error) -> ErroConversor {
+    ErroLeitura::erro(reader.get_ref().linha(), atual, mensagem_xml(erro))
+}
+
+/// Mensagem de um erro do leitor de XML. Erros de leitura do arquivo, como uma codificação
+/// não suportada, mantêm a mensagem original.
+pub(crate) fn mensagem_xml(erro: quick_xml::Error) -> String {
+    match erro {
+        quick_xml::Error::Io(erro) => erro.to_string(),
+        erro => tr!("XML mal formado: {}", erro),
+    }
+}
+
+/// Atributo de interesse do elemento: `moeda` em `<Valor>` e `tipo` nos demais.
+fn atributo_de(inicio: &BytesStart) -> &'static str {
+    if inicio.local_name().as_ref() == "Valor" {
+        "moeda"
+    } else {
+        "tipo"
+    }
+}
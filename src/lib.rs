@@ -0,0 +1,94 @@
+//! Conversão de arquivos XML de comissões, vales, férias e décimo terceiro para CSV, JSON, XLSX ou HTML.
+//!
+//! O binário `converterxmlcsv` é apenas a interface de linha de comando sobre estas funções;
+//! outros programas podem usá-las diretamente, lendo e gravando em qualquer `Read`/`Write`.
+
+use std::io::{BufReader, Read, Write};
+
+#[macro_use]
+pub mod idioma;
+pub mod anonimizacao;
+pub mod avisos;
+pub mod calculo;
+pub mod codificacao;
+pub mod competencia;
+pub mod conversao;
+pub mod documento;
+pub mod duplicados;
+pub mod erro;
+pub mod esquema;
+pub mod generico;
+pub mod html;
+pub mod leiaute;
+pub mod leitura;
+pub mod mapeamento;
+pub mod modelo;
+pub mod opcoes;
+pub mod ordenacao;
+pub mod planilha;
+pub mod posicional;
+pub mod reverso;
+pub mod saida;
+pub mod sha256;
+pub mod sql;
+pub mod tipos;
+pub mod valor;
+
+pub use anonimizacao::{Anonimizacao, Anonimizador};
+pub use avisos::{Aviso, Avisos};
+pub use calculo::ColunaCalculada;
+pub use codificacao::Codificacao;
+pub use conversao::{converter_empresa, converter_empresas, converter_funcionarios, converter_xml, Resumo};
+pub use documento::{cnpj_valido, cpf_valido, DocumentoInvalido, FormatoDocumento};
+pub use duplicados::Deduplicacao;
+pub use erro::ErroConversor;
+pub use esquema::{Esquema, Violacao};
+pub use generico::converter_generico;
+pub use html::SaidaHtml;
+pub use leiaute::{Leiaute, SaidaLeiaute};
+pub use leitura::{abrir_xml, ArquivoXml, ErroLeitura, Funcionarios};
+pub use mapeamento::{ColunaMapeada, Mapeamento};
+pub use modelo::{ler_arquivo, Tipo, TipoArquivo};
+pub use opcoes::{Base, Filtro, Opcoes};
+pub use ordenacao::{CampoOrdenacao, Ordenacao};
+pub use planilha::SaidaXlsx;
+pub use posicional::{LeiautePosicional, SaidaPosicional};
+pub use reverso::csv_para_xml;
+pub use sql::SaidaSql;
+pub use tipos::LeitorTipo;
+pub use saida::{Aspas, Cabecalho, Destino, EscritorCsv, Formato, FormatoCsv, SaidaCsv, SaidaJson};
+pub use valor::{parse_valor, parse_valor_decimal, Decimal, FormatoMonetario, Quantia, ValorNaoNumerico};
+
+/// Converte um XML de comissões (`<Comissao>`) lido de `reader` em CSV gravado em `writer`.
+pub fn converter_comissao<R: Read, W: Write>(reader: R, writer: W, opcoes: &Opcoes) -> Result<Resumo, ErroConversor> {
+    converter_tipo(reader, writer, Some(Tipo::Comissao), opcoes)
+}
+
+/// Converte um XML de vales (`<Vales>`) lido de `reader` em CSV gravado em `writer`.
+pub fn converter_vales<R: Read, W: Write>(reader: R, writer: W, opcoes: &Opcoes) -> Result<Resumo, ErroConversor> {
+    converter_tipo(reader, writer, Some(Tipo::Vales), opcoes)
+}
+
+/// Converte um XML de férias (`<Ferias>`) lido de `reader` em CSV gravado em `writer`.
+pub fn converter_ferias<R: Read, W: Write>(reader: R, writer: W, opcoes: &Opcoes) -> Result<Resumo, ErroConversor> {
+    converter_tipo(reader, writer, Some(Tipo::Ferias), opcoes)
+}
+
+/// Converte um XML de décimo terceiro (`<DecimoTerceiro>`) lido de `reader` em CSV gravado em `writer`.
+pub fn converter_decimo_terceiro<R: Read, W: Write>(reader: R, writer: W, opcoes: &Opcoes) -> Result<Resumo, ErroConversor> {
+    converter_tipo(reader, writer, Some(Tipo::DecimoTerceiro), opcoes)
+}
+
+/// Converte um XML de qualquer tipo suportado, usando `opcoes.tipo` ou a detecção automática.
+pub fn converter<R: Read, W: Write>(reader: R, writer: W, opcoes: &Opcoes) -> Result<Resumo, ErroConversor> {
+    converter_tipo(reader, writer, opcoes.tipo, opcoes)
+}
+
+/// O XML é lido em fluxo e cada linha é gravada assim que o funcionário é lido.
+fn converter_tipo<R: Read, W: Write>(reader: R, writer: W, tipo: Option<Tipo>, opcoes: &Opcoes) -> Result<Resumo, ErroConversor> {
+    let arquivo = abrir_xml(BufReader::new(reader), None, tipo)?;
+    let mut writer = saida::novo_writer(writer, &opcoes.formato);
+    let resumo = converter_empresas(arquivo, opcoes, &mut writer)?;
+    writer.finalizar()?;
+    Ok(resumo)
+}
@@ -0,0 +1,119 @@
+use serde::Deserialize;
+use std::fs;
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::erro::ErroConversor;
+use crate::generico::{ler_arvore, montar_linhas, SEPARADOR_REPETIDOS};
+use crate::saida::Destino;
+
+/// Layout de XML descrito pelo usuário em um arquivo TOML (`--mapping`), para converter
+/// tipos novos sem recompilar o programa. Exemplo:
+///
+/// ```toml
+/// name = "ferias"
+/// root = "Ferias"
+/// row = "Empresa/Funcionario"
+///
+/// [[columns]]
+/// header = "CNPJ"
+/// path = "Empresa/CNPJ"
+///
+/// [[columns]]
+/// header = "CPF"
+/// path = "Empresa/Funcionario/CPF"
+///
+/// [[columns]]
+/// header = "Moeda"
+/// path = "Empresa/Funcionario/Valor/@moeda"
+/// ```
+///
+/// Os caminhos partem do elemento raiz, sem incluí-lo, e usam `@` para atributos: são os
+/// mesmos nomes das colunas geradas por `--generic`. Um caminho dentro de `row` é lido de
+/// cada linha; um caminho fora dela, como os dados da empresa, é repetido em todas as
+/// linhas que ele contém.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Mapeamento {
+    /// Nome do layout, usado no lugar do tipo no nome dos arquivos gerados; sem ele, o
+    /// nome do arquivo de mapeamento.
+    #[serde(default)]
+    pub name: String,
+    /// Elemento raiz esperado no XML.
+    pub root: String,
+    /// Caminho do elemento repetido que vira uma linha; vazio para uma única linha com o
+    /// documento inteiro.
+    #[serde(default)]
+    pub row: String,
+    pub columns: Vec<ColunaMapeada>,
+}
+
+/// Coluna do arquivo gerado e o caminho do valor no XML.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ColunaMapeada {
+    pub header: String,
+    pub path: String,
+}
+
+impl Mapeamento {
+    /// Lê e valida o arquivo de mapeamento.
+    pub fn carregar(caminho: &Path) -> Result<Mapeamento, ErroConversor> {
+        let conteudo = fs::read_to_string(caminho)
+            .map_err(|erro| ErroConversor::EntradaInvalida(tr!("Não foi possível ler o arquivo de mapeamento '{}': {}", caminho.display(), erro)))?;
+        let mut mapeamento: Mapeamento = toml::from_str(&conteudo)
+            .map_err(|erro| ErroConversor::EntradaInvalida(tr!("Arquivo de mapeamento '{}' inválido: {}", caminho.display(), erro)))?;
+
+        if mapeamento.columns.is_empty() {
+            return Err(ErroConversor::EntradaInvalida(tr!("O arquivo de mapeamento '{}' não define nenhuma coluna.", caminho.display())));
+        }
+        if mapeamento.name.is_empty() {
+            mapeamento.name = caminho.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        }
+        Ok(mapeamento)
+    }
+
+    /// Converte o XML de `reader` pelas colunas do mapeamento, gravando as linhas em `destino`.
+    ///
+    /// Como na conversão genérica, o documento é carregado na memória. Retorna a quantidade
+    /// de linhas gravadas; sem nenhuma ocorrência de `row`, nada é gravado.
+    pub fn converter<R: BufRead>(&self, reader: R, destino: &mut dyn Destino) -> Result<usize, ErroConversor> {
+        let raiz = ler_arvore(reader)?;
+        if raiz.nome != self.root {
+            return Err(ErroConversor::EntradaInvalida(tr!("O elemento raiz do XML é <{}>, mas o mapeamento '{}' espera <{}>.", raiz.nome, self.name, self.root)));
+        }
+
+        let caminho_linha: Vec<String> = std::iter::once(self.root.as_str())
+            .chain(partes(&self.row))
+            .map(str::to_string)
+            .collect();
+        let mut linhas = Vec::new();
+        montar_linhas(&raiz, &mut vec![raiz.nome.clone()], &caminho_linha, &[], &mut linhas);
+        if linhas.is_empty() {
+            return Ok(0);
+        }
+
+        let caminhos: Vec<String> = self.columns.iter().map(|coluna| partes(&coluna.path).collect::<Vec<_>>().join("/")).collect();
+        destino.iniciar(&self.columns.iter().map(|coluna| coluna.header.as_str()).collect::<Vec<_>>())?;
+        for linha in &linhas {
+            let registro: Vec<String> = caminhos
+                .iter()
+                .map(|caminho| {
+                    linha
+                        .iter()
+                        .filter(|(nome, _)| nome == caminho)
+                        .map(|(_, valor)| valor.as_str())
+                        .collect::<Vec<_>>()
+                        .join(SEPARADOR_REPETIDOS)
+                })
+                .collect();
+            destino.gravar(&registro.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+        Ok(linhas.len())
+    }
+}
+
+/// Elementos de um caminho, ignorando barras nas pontas ou repetidas.
+fn partes(caminho: &str) -> impl Iterator<Item = &str> {
+    caminho.split('/').map(str::trim).filter(|parte| !parte.is_empty())
+}
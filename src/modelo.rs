@@ -0,0 +1,280 @@
+use serde::de::{self, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+
+use crate::competencia::normalizar_mes_ano;
+use crate::erro::ErroConversor;
+use crate::leitura::abrir_xml_utf8;
+use crate::tipos;
+
+/// Moeda assumida quando o elemento `<Valor>` não traz o atributo `moeda`.
+pub const MOEDA_PADRAO: &str = "BRL";
+
+#[derive(Debug, Deserialize)]
+pub struct Valor {
+    #[serde(rename = "moeda", default)]
+    pub moeda: Option<String>,
+    #[serde(rename = "$value", default)]
+    pub valor: String,
+}
+
+impl Valor {
+    pub fn moeda(&self) -> &str {
+        self.moeda.as_deref().unwrap_or(MOEDA_PADRAO)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Funcionario {
+    #[serde(rename = "CPF")]
+    pub cpf: String,
+    #[serde(rename = "Valor")]
+    pub valor: Valor,
+    #[serde(rename = "MetaPremio", default)]
+    pub meta_premio: Option<String>,
+    /// Competência própria do funcionário (ex.: pagamento retroativo).
+    #[serde(rename = "MesAno", default)]
+    pub mes_ano: Option<String>,
+    /// Início do período de férias (apenas férias).
+    #[serde(rename = "DataInicio", default)]
+    pub data_inicio: Option<String>,
+    /// Fim do período de férias (apenas férias).
+    #[serde(rename = "DataFim", default)]
+    pub data_fim: Option<String>,
+    /// Parcela do décimo terceiro, ex.: `1ª` ou `2ª` (apenas décimo terceiro).
+    #[serde(rename = "Parcela", default)]
+    pub parcela: Option<String>,
+}
+
+/// Todas as colunas que a conversão pode gerar, aceitas por `--columns`: as de cada tipo e
+/// as extras de `--currency-column`, `--split-mesano`, `--keep-original`, `--keep-raw-valor`
+/// e `--delta-against`.
+pub const COLUNAS: [&str; 17] = [
+    "Fantasia",
+    "Razao",
+    "CNPJ",
+    "MesAno",
+    "Ano",
+    "Mes",
+    "CPF",
+    "DataInicio",
+    "DataFim",
+    "Parcela",
+    "Valor",
+    "MetaPremio",
+    "Moeda",
+    "FantasiaOriginal",
+    "RazaoOriginal",
+    "ValorOriginal",
+    "Alteracao",
+];
+
+/// Campos do funcionário que podem ser exigidos com `--require-fields`.
+pub const CAMPOS_FUNCIONARIO: [&str; 7] = ["CPF", "Valor", "MetaPremio", "MesAno", "DataInicio", "DataFim", "Parcela"];
+
+impl Funcionario {
+    /// Competência do funcionário, ou a da empresa quando ele não informa a sua.
+    pub fn mes_ano<'a>(&'a self, empresa: &'a Empresa) -> &'a str {
+        self.mes_ano.as_deref().unwrap_or(&empresa.mes_ano)
+    }
+
+    /// Valor de um dos [`CAMPOS_FUNCIONARIO`], ou `None` quando ausente.
+    pub fn campo<'a>(&'a self, nome: &str, empresa: &'a Empresa) -> Option<&'a str> {
+        match nome {
+            "CPF" => Some(&self.cpf),
+            "Valor" => Some(&self.valor.valor),
+            "MetaPremio" => self.meta_premio.as_deref(),
+            "MesAno" => Some(self.mes_ano(empresa)),
+            "DataInicio" => self.data_inicio.as_deref(),
+            "DataFim" => self.data_fim.as_deref(),
+            "Parcela" => self.parcela.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Empresa {
+    pub fantasia: String,
+    pub razao: String,
+    pub cnpj: String,
+    pub mes_ano: String,
+    pub funcionarios: Option<Vec<Funcionario>>,
+}
+
+/// Desserialização manual de `<Empresa>`.
+///
+/// O serde-xml-rs só agrupa em um `Vec` os elementos `<Funcionario>` consecutivos; quando
+/// outro elemento aparece entre eles, o derive acusa campo duplicado. Aqui cada ocorrência
+/// é acrescentada à lista, seja um único funcionário, vários ou vários intercalados.
+impl<'de> Deserialize<'de> for Empresa {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Empresa, D::Error> {
+        struct EmpresaVisitor;
+
+        impl<'de> Visitor<'de> for EmpresaVisitor {
+            type Value = Empresa;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("o elemento <Empresa>")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Empresa, A::Error> {
+                let mut fantasia = None;
+                let mut razao = None;
+                let mut cnpj = None;
+                let mut mes_ano = None;
+                let mut funcionarios: Option<Vec<Funcionario>> = None;
+
+                while let Some(campo) = map.next_key::<String>()? {
+                    match campo.as_str() {
+                        "Fantasia" => fantasia = Some(map.next_value()?),
+                        "Razao" => razao = Some(map.next_value()?),
+                        "CNPJ" => cnpj = Some(map.next_value()?),
+                        "MesAno" => mes_ano = Some(map.next_value()?),
+                        "Funcionario" => {
+                            let lote: Vec<Funcionario> = map.next_value()?;
+                            funcionarios.get_or_insert_with(Vec::new).extend(lote);
+                        }
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                Ok(Empresa {
+                    fantasia: fantasia.ok_or_else(|| de::Error::missing_field("Fantasia"))?,
+                    razao: razao.ok_or_else(|| de::Error::missing_field("Razao"))?,
+                    cnpj: cnpj.ok_or_else(|| de::Error::missing_field("CNPJ"))?,
+                    mes_ano: mes_ano.map(|mes_ano: String| normalizar_mes_ano(&mes_ano)).ok_or_else(|| de::Error::missing_field("MesAno"))?,
+                    funcionarios,
+                })
+            }
+        }
+
+        deserializer.deserialize_struct(
+            "Empresa",
+            &["Fantasia", "Razao", "CNPJ", "MesAno", "Funcionario"],
+            EmpresaVisitor,
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub enum TipoArquivo {
+    Comissao(Comissao),
+    Vales(Vales),
+    Ferias(Ferias),
+    DecimoTerceiro(DecimoTerceiro),
+}
+
+/// As exportações consolidadas trazem várias `<Empresa>`; as demais, uma só.
+#[derive(Debug, Deserialize)]
+pub struct Comissao {
+    #[serde(rename = "Empresa")]
+    pub empresas: Vec<Empresa>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Vales {
+    #[serde(rename = "Empresa")]
+    pub empresas: Vec<Empresa>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Ferias {
+    #[serde(rename = "Empresa")]
+    pub empresas: Vec<Empresa>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DecimoTerceiro {
+    #[serde(rename = "Empresa")]
+    pub empresas: Vec<Empresa>,
+}
+
+/// Tipo de arquivo escolhido por `--type` ou pela detecção automática.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tipo {
+    Comissao,
+    Vales,
+    Ferias,
+    DecimoTerceiro,
+}
+
+impl Tipo {
+    /// Nomes aceitos por `--type`, além de `auto`, na ordem dos leitores registrados.
+    pub fn nomes() -> Vec<&'static str> {
+        tipos::LEITORES.iter().map(|leitor| leitor.nomes()[0]).collect()
+    }
+
+    pub fn from_nome(nome: &str) -> Option<Tipo> {
+        tipos::por_nome(nome).map(|leitor| leitor.tipo())
+    }
+
+    /// Tipo indicado pelo prefixo do nome do arquivo, ex.: `comissao_0124.xml`.
+    pub fn from_caminho(caminho: &Path) -> Option<Tipo> {
+        tipos::por_caminho(caminho).map(|leitor| leitor.tipo())
+    }
+
+    /// Nome usado no manifest e nas mensagens.
+    pub fn nome(self) -> &'static str {
+        tipos::leitor(self).nomes()[0]
+    }
+
+    fn com_empresas(self, empresas: Vec<Empresa>) -> TipoArquivo {
+        match self {
+            Tipo::Comissao => TipoArquivo::Comissao(Comissao { empresas }),
+            Tipo::Vales => TipoArquivo::Vales(Vales { empresas }),
+            Tipo::Ferias => TipoArquivo::Ferias(Ferias { empresas }),
+            Tipo::DecimoTerceiro => TipoArquivo::DecimoTerceiro(DecimoTerceiro { empresas }),
+        }
+    }
+}
+
+/// Desserializa o XML no tipo informado ou, se `tipo` for `None`, no tipo detectado.
+///
+/// A detecção automática segue a ordem: atributo `tipo` de `<Arquivo>` (layout em que a
+/// `<Empresa>` vem dentro de `<Arquivo tipo="comissao">`), elemento raiz (`<Comissao>`,
+/// `<Vales>`, `<Ferias>` ou `<DecimoTerceiro>`, sem diferenciar maiúsculas) e, só quando o XML não identifica o tipo, o
+/// prefixo do nome do arquivo. Assim arquivos renomeados pelo ERP continuam sendo
+/// reconhecidos.
+///
+/// Todos os funcionários ficam na memória; para arquivos grandes use [`crate::abrir_xml`].
+pub fn ler_arquivo(conteudo: &str, caminho: Option<&Path>, tipo: Option<Tipo>) -> Result<TipoArquivo, ErroConversor> {
+    let mut arquivo = abrir_xml_utf8(conteudo, caminho, tipo)?;
+    let mut empresas = Vec::new();
+    loop {
+        let funcionarios = arquivo.funcionarios.by_ref().collect::<Result<Vec<_>, _>>()?;
+        let empresa = &arquivo.empresa;
+        empresas.push(Empresa {
+            fantasia: empresa.fantasia.clone(),
+            razao: empresa.razao.clone(),
+            cnpj: empresa.cnpj.clone(),
+            mes_ano: empresa.mes_ano.clone(),
+            funcionarios: Some(funcionarios),
+        });
+        if !arquivo.proxima_empresa()? {
+            break;
+        }
+    }
+    Ok(arquivo.tipo.com_empresas(empresas))
+}
+
+/// Nome local do elemento raiz do XML, ignorando a declaração, comentários, DOCTYPE e o
+/// prefixo de namespace (`<ns:Comissao>` resulta em `Comissao`).
+pub fn elemento_raiz(xml: &str) -> Option<&str> {
+    let mut resto = xml;
+    loop {
+        let inicio = resto.find('<')?;
+        resto = &resto[inicio + 1..];
+        if resto.starts_with('?') || resto.starts_with('!') {
+            continue;
+        }
+        let fim = resto
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(resto.len());
+        let nome = &resto[..fim];
+        return Some(nome.rsplit(':').next().unwrap_or(nome));
+    }
+}
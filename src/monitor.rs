@@ -0,0 +1,106 @@
+use colored::*;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::compactado::eh_entrada;
+use crate::convertidos::Convertidos;
+use crate::{arquivar, converter_arquivo, gravar_manifest, registrar_convertido, registrar_gerado, travar_saida, OpcoesCli};
+
+/// Tempo sem novos eventos de um arquivo antes de convertê-lo, para não ler um XML que
+/// ainda está sendo copiado para a pasta.
+const ESPERA_ESTAVEL: Duration = Duration::from_secs(2);
+
+/// Intervalo entre as verificações dos arquivos que aguardam conversão.
+const INTERVALO: Duration = Duration::from_millis(500);
+
+/// Monitora `pasta` e converte cada XML criado ou alterado nela, até o processo ser interrompido.
+///
+/// Com `--archive` ou `--done-dir`, o XML convertido com sucesso vai para essa pasta; os
+/// XML com erro ficam onde estão, para serem corrigidos e gravados de novo. Um XML já
+/// convertido que volta à pasta sem alteração é ignorado, a menos que `--reconvert` seja usado.
+pub fn monitorar(pasta: &Path, opcoes: &OpcoesCli) -> Result<(), Box<dyn Error>> {
+    if !pasta.is_dir() {
+        return Err(tr!("{} não é uma pasta.", pasta.display()).into());
+    }
+    // Os eventos trazem caminhos absolutos; a pasta de concluídos é comparada da mesma forma.
+    let concluidos = opcoes.arquivamento.as_ref().map(|arquivamento| arquivamento.preparar()).transpose()?;
+    let mut convertidos = opcoes.convertidos.as_deref().map(Convertidos::carregar).transpose()?;
+    let destino = opcoes.destino_convertidos();
+
+    let (envio, recebimento) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(envio)?;
+    let modo = if opcoes.recursivo { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+    watcher.watch(&fs::canonicalize(pasta)?, modo)?;
+
+    println!("{}", tr!("Monitorando {} (Ctrl+C para sair)...", pasta.display()).bright_cyan());
+
+    let mut aguardando: HashMap<PathBuf, Instant> = HashMap::new();
+    let mut gerados = Vec::new();
+    loop {
+        match recebimento.recv_timeout(INTERVALO) {
+            Ok(Ok(evento)) => {
+                if matches!(evento.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    for caminho in evento.paths {
+                        let fora_dos_concluidos = concluidos.as_ref().is_none_or(|concluidos| !caminho.starts_with(concluidos));
+                        if eh_entrada(&caminho) && fora_dos_concluidos {
+                            aguardando.insert(caminho, Instant::now());
+                        }
+                    }
+                }
+            }
+            Ok(Err(erro)) => eprintln!("{}", tr!("Erro ao monitorar {}: {}", pasta.display(), erro).bright_red()),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Err(tr!("O monitoramento da pasta foi encerrado.").into()),
+        }
+
+        let prontos: Vec<PathBuf> = aguardando
+            .iter()
+            .filter(|(_, ultimo_evento)| ultimo_evento.elapsed() >= ESPERA_ESTAVEL)
+            .map(|(caminho, _)| caminho.clone())
+            .collect();
+        for caminho in prontos {
+            aguardando.remove(&caminho);
+            // O arquivo pode ter sido removido ou renomeado depois do evento.
+            if !caminho.is_file() {
+                continue;
+            }
+            // Com a trava, a lista de convertidos é lida de novo, com o que outra execução
+            // na mesma pasta já converteu.
+            let _trava = travar_saida(std::slice::from_ref(&caminho), opcoes, &mut io::stdout())?;
+            if let Some(arquivo) = &opcoes.convertidos {
+                convertidos = Some(Convertidos::carregar(arquivo)?);
+            }
+            if !opcoes.reconverter && convertidos.as_ref().is_some_and(|convertidos| convertidos.contem(&caminho, &destino).unwrap_or(false)) {
+                println!("{}", tr!("{} já foi convertido e não foi alterado; ignorado.", caminho.display()).bright_yellow());
+                continue;
+            }
+
+            println!("{}", tr!("Convertendo {}...", caminho.display()).bright_cyan());
+            let resultado = converter_arquivo(&caminho, opcoes, None, &mut io::stdout()).and_then(|(_, gerado)| {
+                let caminhos = gerado.caminhos(opcoes);
+                registrar_gerado(gerado, opcoes, &mut gerados, &mut io::stdout())?;
+                Ok(caminhos)
+            });
+            match resultado {
+                Ok(caminhos) => {
+                    if let Some(manifest) = &opcoes.manifest {
+                        gravar_manifest(manifest, &gerados, &mut io::stdout())?;
+                    }
+                    if let Some(convertidos) = &mut convertidos {
+                        registrar_convertido(convertidos, &caminho, &destino, &caminhos, &mut io::stdout());
+                    }
+                    if let Some(arquivamento) = &opcoes.arquivamento {
+                        arquivar(&caminho, arquivamento, &mut io::stdout());
+                    }
+                }
+                Err(erro) => eprintln!("{}", tr!("Erro ao converter {}: {}", caminho.display(), erro).bright_red()),
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use converterxmlcsv::modelo::Empresa;
+use converterxmlcsv::{Formato, Tipo};
+
+/// Marcadores aceitos por `--name-template`.
+pub const MARCADORES: [&str; 6] = ["arquivo", "tipo", "cnpj", "mesano", "fantasia", "razao"];
+
+/// Modelo padrão: o nome do XML de origem, trocando apenas a extensão.
+pub const MODELO_PADRAO: &str = "{arquivo}";
+
+/// Caracteres que não podem aparecer em nomes de arquivo no Windows ou no Linux.
+const CARACTERES_PROIBIDOS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Modelo do nome dos arquivos gerados, ex.: `{tipo}_{cnpj}_{mesano}`.
+#[derive(Debug)]
+pub struct ModeloNome {
+    modelo: String,
+}
+
+impl Default for ModeloNome {
+    fn default() -> ModeloNome {
+        ModeloNome { modelo: MODELO_PADRAO.to_string() }
+    }
+}
+
+impl ModeloNome {
+    /// Valida os marcadores do modelo. A extensão, se informada (ex.: `.csv`), é descartada:
+    /// os arquivos sempre recebem a extensão do formato escolhido em `--format`.
+    pub fn new(modelo: &str) -> Result<ModeloNome, String> {
+        let mut resto = modelo;
+        while let Some(inicio) = resto.find('{') {
+            let fim = resto[inicio..]
+                .find('}')
+                .ok_or_else(|| tr!("Modelo de nome inválido '{}': falta fechar o marcador com }.", modelo))?;
+            let marcador = &resto[inicio + 1..inicio + fim];
+            if !MARCADORES.contains(&marcador) {
+                return Err(tr!("Marcador desconhecido em --name-template: {{}}. Use um de: {}.", marcador, MARCADORES.map(|nome| format!("{{{}}}", nome)).join(", ")));
+            }
+            resto = &resto[inicio + fim + 1..];
+        }
+        if modelo.contains(CARACTERES_PROIBIDOS) {
+            return Err(tr!("Modelo de nome inválido '{}': use --out-dir para escolher a pasta.", modelo));
+        }
+
+        let sem_extensao = Formato::NOMES
+            .iter()
+            .filter_map(|nome| Formato::from_nome(nome))
+            .find_map(|formato| modelo.strip_suffix(&format!(".{}", formato.extensao())))
+            .unwrap_or(modelo);
+        Ok(ModeloNome { modelo: sem_extensao.to_string() })
+    }
+
+    /// Caminho do arquivo gerado a partir de `origem`, dentro de `pasta` ou, sem ela, na
+    /// mesma pasta do XML.
+    pub fn caminho(&self, pasta: Option<&Path>, origem: &Path, tipo: Tipo, empresa: &Empresa, formato: Formato) -> PathBuf {
+        self.montar(pasta, origem, tipo.nome(), Some(empresa), formato)
+    }
+
+    /// Caminho do arquivo gerado sem os dados da empresa, nas conversões por `--generic`
+    /// ou `--mapping`: `{tipo}` vira `tipo` e os marcadores da empresa ficam vazios.
+    pub fn caminho_sem_empresa(&self, pasta: Option<&Path>, origem: &Path, tipo: &str, formato: Formato) -> PathBuf {
+        self.montar(pasta, origem, tipo, None, formato)
+    }
+
+    fn montar(&self, pasta: Option<&Path>, origem: &Path, tipo: &str, empresa: Option<&Empresa>, formato: Formato) -> PathBuf {
+        let arquivo = origem.file_stem().unwrap_or_default().to_string_lossy();
+        let dado = |campo: fn(&Empresa) -> &str| empresa.map(campo).unwrap_or("");
+        let mut nome = self.modelo.clone();
+        for (marcador, valor) in [
+            ("{arquivo}", arquivo.as_ref()),
+            ("{tipo}", tipo),
+            ("{cnpj}", dado(|empresa| &empresa.cnpj)),
+            ("{mesano}", dado(|empresa| &empresa.mes_ano)),
+            ("{fantasia}", dado(|empresa| &empresa.fantasia)),
+            ("{razao}", dado(|empresa| &empresa.razao)),
+        ] {
+            nome = nome.replace(marcador, &limpar(valor));
+        }
+
+        let pasta = pasta.or_else(|| origem.parent()).unwrap_or(Path::new(""));
+        pasta.join(format!("{}.{}", nome, formato.extensao()))
+    }
+}
+
+/// Troca por `_` os caracteres que não podem aparecer em nomes de arquivo, como a `/` do CNPJ formatado.
+fn limpar(valor: &str) -> String {
+    valor
+        .trim()
+        .chars()
+        .map(|c| if CARACTERES_PROIBIDOS.contains(&c) || c.is_control() { '_' } else { c })
+        .collect()
+}
@@ -0,0 +1,464 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::anonimizacao::Anonimizador;
+use crate::avisos::Avisos;
+use crate::calculo::ColunaCalculada;
+use crate::competencia::ano_mes;
+use crate::documento::{DocumentoInvalido, FormatoDocumento};
+use crate::duplicados::Deduplicacao;
+use crate::erro::ErroConversor;
+use crate::modelo::{Empresa, Funcionario, Tipo, MOEDA_PADRAO};
+use crate::ordenacao::Ordenacao;
+use crate::saida::FormatoCsv;
+use crate::valor::{formatar_valor, normalizar_valor, parse_valor_decimal, Decimal, FormatoMonetario, Quantia, ValorNaoNumerico};
+
+/// Opções que influenciam a conversão.
+#[derive(Debug, Default)]
+pub struct Opcoes {
+    /// Taxas de câmbio para reais, indexadas pelo código da moeda (ex.: `USD`).
+    pub cambio: HashMap<String, f64>,
+    /// Quantidade máxima de caracteres da coluna `Fantasia`.
+    pub truncar_fantasia: Option<usize>,
+    /// Quantidade máxima de caracteres da coluna `Razao`.
+    pub truncar_razao: Option<usize>,
+    /// Indica o corte com reticências, que contam dentro do limite.
+    pub reticencias: bool,
+    /// Remove os espaços do início e do fim de `Fantasia`, `Razao` e `CPF`.
+    pub aparar: bool,
+    /// Troca as sequências de espaços de `Fantasia`, `Razao` e `CPF` por um só espaço, além
+    /// de removê-los do início e do fim.
+    pub juntar_espacos: bool,
+    /// Escrita de `CPF` e `CNPJ`; `None` mantém a do XML.
+    pub formato_documento: Option<FormatoDocumento>,
+    /// Mantém o texto completo em colunas `*Original` quando houver truncamento configurado.
+    pub manter_original: bool,
+    /// Adiciona a coluna `ValorOriginal` com o texto do `<Valor>` como veio no XML.
+    pub manter_valor_original: bool,
+    /// Adiciona a coluna `Moeda`, depois das colunas próprias do tipo.
+    pub coluna_moeda: bool,
+    /// Quantidade máxima de funcionários por CSV; acima disso a saída é dividida em partes.
+    pub max_linhas: Option<usize>,
+    /// Tipo forçado por `--type`; `None` quando a detecção é automática.
+    pub tipo: Option<Tipo>,
+    /// CSV exportado anteriormente; apenas as linhas novas ou alteradas em relação a ele são gravadas.
+    pub delta: Option<Base>,
+    /// Campos que todo funcionário deve informar, além dos obrigatórios do XML.
+    pub campos_exigidos: Vec<String>,
+    /// Com campos exigidos ausentes, apenas avisa em vez de interromper a conversão.
+    pub campos_exigidos_aviso: bool,
+    /// Delimitador, aspas e terminador de linha dos CSVs gerados.
+    pub formato: FormatoCsv,
+    /// Interrompe a conversão quando algum CPF ou CNPJ tem dígitos verificadores inválidos.
+    pub documentos_estrito: bool,
+    /// Interrompe a conversão quando algum `Valor` ou `MetaPremio` não é número, em vez de
+    /// somá-lo como zero.
+    pub valores_estrito: bool,
+    /// Separador decimal de `Valor` e `MetaPremio` no XML; `None` deduz pelo texto.
+    pub decimal_entrada: Option<Decimal>,
+    /// Separador decimal de `Valor` e `MetaPremio` no arquivo gerado; `None` mantém o texto
+    /// numérico do XML e grava com ponto apenas os valores formatados (ex.: `R$ 1.234,56`).
+    pub decimal_saida: Option<Decimal>,
+    /// Escrita de `Valor` e `MetaPremio` de `--money-format`; substitui `decimal_saida`.
+    pub formato_monetario: Option<FormatoMonetario>,
+    /// Acrescenta as colunas `Ano` e `Mes`, separadas do `MesAno`.
+    pub separar_mes_ano: bool,
+    /// Esconde os CPFs (e, no modo fictício, CNPJ e nomes) dos arquivos gerados.
+    pub anonimizar: Option<Anonimizador>,
+    /// Colunas gravadas, na ordem desejada; vazio grava todas as colunas do tipo.
+    pub colunas: Vec<String>,
+    /// Junta os funcionários de mesmo CPF de cada empresa com a estratégia escolhida.
+    pub deduplicar: Option<Deduplicacao>,
+    /// Grava apenas os funcionários que atendem a estes critérios.
+    pub filtro: Filtro,
+    /// Critérios de `--sort`; vazio mantém a ordem do XML.
+    pub ordenar: Vec<Ordenacao>,
+    /// Colunas de `--computed`, gravadas depois das demais colunas do tipo.
+    pub calculadas: Vec<ColunaCalculada>,
+}
+
+/// Critérios de `--filter-cpf`, `--min-valor`, `--max-valor` e `--cnpj`; os vazios não filtram.
+#[derive(Debug, Default)]
+pub struct Filtro {
+    /// CPFs aceitos, comparados só pelos dígitos.
+    pub cpfs: Vec<String>,
+    /// CNPJs das empresas aceitas, comparados só pelos dígitos.
+    pub cnpjs: Vec<String>,
+    /// Menor `Valor` aceito, na moeda do XML (sem câmbio).
+    pub valor_minimo: Option<f64>,
+    /// Maior `Valor` aceito, na moeda do XML (sem câmbio).
+    pub valor_maximo: Option<f64>,
+}
+
+impl Filtro {
+    pub fn is_empty(&self) -> bool {
+        self.cpfs.is_empty() && self.cnpjs.is_empty() && self.valor_minimo.is_none() && self.valor_maximo.is_none()
+    }
+
+    /// Verdadeiro quando o funcionário da empresa atende a todos os critérios. Um `Valor`
+    /// que não é número só passa quando não há limite de valor.
+    pub(crate) fn aceita(&self, empresa: &Empresa, funcionario: &Funcionario, opcoes: &Opcoes) -> bool {
+        let digitos = |texto: &str| texto.chars().filter(char::is_ascii_digit).collect::<String>();
+        let na_lista = |lista: &[String], documento: &str| lista.is_empty() || lista.iter().any(|item| digitos(item) == digitos(documento));
+        if !na_lista(&self.cnpjs, &empresa.cnpj) || !na_lista(&self.cpfs, &funcionario.cpf) {
+            return false;
+        }
+        if self.valor_minimo.is_none() && self.valor_maximo.is_none() {
+            return true;
+        }
+        opcoes
+            .ler_numero(&funcionario.valor.valor)
+            .is_some_and(|valor| self.valor_minimo.is_none_or(|minimo| valor >= minimo) && self.valor_maximo.is_none_or(|maximo| valor <= maximo))
+    }
+}
+
+/// Coluna que recebe `novo` ou `alterado` nas saídas de `--delta-against`.
+const COLUNA_ALTERACAO: &str = "Alteracao";
+
+/// CSV de referência para `--delta-against`, com as linhas indexadas pelo CPF.
+#[derive(Debug)]
+pub struct Base {
+    cabecalho: Vec<String>,
+    linhas: HashMap<String, Vec<String>>,
+}
+
+impl Base {
+    /// Lê o CSV base, separado por `delimitador` (o mesmo usado na exportação).
+    pub fn ler(caminho: &Path, delimitador: u8) -> Result<Base, ErroConversor> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimitador)
+            .from_path(caminho)
+            .map_err(|erro| ErroConversor::EntradaInvalida(tr!("Não foi possível ler o CSV base '{}': {}", caminho.display(), erro)))?;
+
+        let cabecalho: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+        let coluna_cpf = cabecalho
+            .iter()
+            .position(|coluna| coluna == "CPF")
+            .ok_or_else(|| ErroConversor::EntradaInvalida(tr!("O CSV base '{}' não possui a coluna CPF.", caminho.display())))?;
+
+        let mut linhas = HashMap::new();
+        for registro in reader.records() {
+            let registro: Vec<String> = registro?.iter().map(str::to_string).collect();
+            if let Some(cpf) = registro.get(coluna_cpf) {
+                linhas.insert(cpf.clone(), registro);
+            }
+        }
+
+        Ok(Base { cabecalho, linhas })
+    }
+
+    /// Compara o registro com a linha de mesmo CPF na base, coluna a coluna pelo nome.
+    ///
+    /// Retorna `None` quando nada mudou; colunas ausentes em um dos lados são ignoradas.
+    fn comparar(&self, cabecalho: &[&str], registro: &[&str]) -> Option<&'static str> {
+        let valor = |coluna: &str| {
+            cabecalho
+                .iter()
+                .position(|nome| *nome == coluna)
+                .and_then(|indice| registro.get(indice).copied())
+        };
+
+        let Some(anterior) = valor("CPF").and_then(|cpf| self.linhas.get(cpf)) else {
+            return Some("novo");
+        };
+
+        let alterado = self
+            .cabecalho
+            .iter()
+            .zip(anterior)
+            .filter(|(coluna, _)| *coluna != COLUNA_ALTERACAO)
+            .any(|(coluna, antigo)| valor(coluna).is_some_and(|atual| atual != antigo));
+
+        alterado.then_some("alterado")
+    }
+}
+
+impl Opcoes {
+    /// Campos de `campos_exigidos` ausentes ou vazios no funcionário da posição `indice`.
+    pub(crate) fn pendencia_campos_exigidos(&self, indice: usize, empresa: &Empresa, funcionario: &Funcionario) -> Option<String> {
+        let ausentes: Vec<&str> = self
+            .campos_exigidos
+            .iter()
+            .filter(|campo| funcionario.campo(campo, empresa).is_none_or(|valor| valor.trim().is_empty()))
+            .map(String::as_str)
+            .collect();
+
+        if ausentes.is_empty() {
+            return None;
+        }
+        Some(tr!("funcionário {} (CPF {}) sem {}", indice + 1, funcionario.cpf, ausentes.join(", ")))
+    }
+
+    /// Conclui a verificação de `campos_exigidos` com as pendências de todos os funcionários.
+    ///
+    /// Os funcionários com campos ausentes ou vazios são listados de uma vez; a conversão
+    /// falha, a menos que `campos_exigidos_aviso` esteja ligado.
+    pub(crate) fn verificar_campos_exigidos(&self, pendencias: Vec<String>, avisos: &mut Avisos) -> Result<(), ErroConversor> {
+        if pendencias.is_empty() {
+            return Ok(());
+        }
+        if self.campos_exigidos_aviso {
+            for pendencia in pendencias {
+                avisos.registrar("funcionários sem campos exigidos", format!("{}.", pendencia));
+            }
+            return Ok(());
+        }
+        Err(ErroConversor::ValorInvalido(tr!("Campos exigidos por --require-fields ausentes:\n  {}", pendencias.join("\n  "))))
+    }
+
+    /// Com `documentos_estrito`, falha listando os CPFs e CNPJ inválidos do arquivo.
+    pub(crate) fn verificar_documentos(&self, invalidos: &[DocumentoInvalido]) -> Result<(), ErroConversor> {
+        if !self.documentos_estrito || invalidos.is_empty() {
+            return Ok(());
+        }
+        let lista: Vec<String> = invalidos.iter().map(DocumentoInvalido::to_string).collect();
+        Err(ErroConversor::ValorInvalido(tr!("Documentos com dígitos verificadores inválidos (--strict):\n  {}", lista.join("\n  "))))
+    }
+
+    /// `Valor` e `MetaPremio` preenchida do funcionário da posição `indice` (a partir de 0)
+    /// que não são números.
+    pub(crate) fn valores_nao_numericos(&self, indice: usize, funcionario: &Funcionario) -> Vec<ValorNaoNumerico> {
+        let meta_premio = funcionario.meta_premio.as_deref().filter(|meta| !meta.trim().is_empty());
+        [("Valor", Some(funcionario.valor.valor.as_str())), ("MetaPremio", meta_premio)]
+            .into_iter()
+            .filter_map(|(campo, texto)| Some((campo, texto?)))
+            .filter(|(_, texto)| self.ler_numero(texto).is_none())
+            .map(|(campo, texto)| ValorNaoNumerico { funcionario: indice + 1, cpf: funcionario.cpf.clone(), campo, valor: texto.to_string() })
+            .collect()
+    }
+
+    /// Com `valores_estrito`, falha listando os valores do arquivo que não são números.
+    pub(crate) fn verificar_valores(&self, invalidos: &[ValorNaoNumerico]) -> Result<(), ErroConversor> {
+        if !self.valores_estrito || invalidos.is_empty() {
+            return Ok(());
+        }
+        let lista: Vec<String> = invalidos.iter().map(ValorNaoNumerico::to_string).collect();
+        Err(ErroConversor::ValorInvalido(tr!("Valores não numéricos (--strict-values):\n  {}", lista.join("\n  "))))
+    }
+
+    /// Coluna extra que indica o tipo de alteração quando `delta` é usado.
+    pub(crate) fn colunas_delta(&self) -> Vec<&'static str> {
+        match self.delta {
+            Some(_) => vec![COLUNA_ALTERACAO],
+            None => Vec::new(),
+        }
+    }
+
+    /// Aplica `delta` ao registro: retorna `false` quando ele é igual ao da base e,
+    /// caso contrário, acrescenta o valor da coluna `Alteracao`.
+    pub(crate) fn aplicar_delta(&self, cabecalho: &[&str], registro: &mut Vec<&str>) -> bool {
+        let Some(base) = &self.delta else {
+            return true;
+        };
+
+        match base.comparar(cabecalho, registro) {
+            Some(alteracao) => {
+                registro.push(alteracao);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// `Fantasia`, `Razao` ou `CPF` com os espaços tratados conforme `aparar` e
+    /// `juntar_espacos`. O ERP completa alguns campos com espaços, que atrapalham a busca
+    /// desses valores em outras planilhas.
+    pub(crate) fn espacos<'a>(&self, valor: &'a str) -> Cow<'a, str> {
+        if self.juntar_espacos {
+            Cow::Owned(valor.split_whitespace().collect::<Vec<_>>().join(" "))
+        } else if self.aparar {
+            Cow::Borrowed(valor.trim())
+        } else {
+            Cow::Borrowed(valor)
+        }
+    }
+
+    /// `CPF` do arquivo gerado, com [`Opcoes::espacos`] e `formato_documento`.
+    pub(crate) fn cpf<'a>(&self, cpf: &'a str) -> Cow<'a, str> {
+        let cpf = self.espacos(cpf);
+        match self.formato_documento {
+            Some(formato) => Cow::Owned(formato.formatar_cpf(&cpf)),
+            None => cpf,
+        }
+    }
+
+    /// `CNPJ` do arquivo gerado, no `formato_documento`.
+    pub(crate) fn cnpj<'a>(&self, cnpj: &'a str) -> Cow<'a, str> {
+        match self.formato_documento {
+            Some(formato) => Cow::Owned(formato.formatar_cnpj(cnpj)),
+            None => Cow::Borrowed(cnpj),
+        }
+    }
+
+    /// Corta `valor` em `limite` caracteres, avisando quando o texto foi truncado.
+    pub(crate) fn truncar(&self, coluna: &str, valor: &str, limite: Option<usize>, avisos: &mut Avisos) -> String {
+        let limite = match limite {
+            Some(limite) if valor.chars().count() > limite => limite,
+            _ => return valor.to_string(),
+        };
+
+        let truncado: String = if self.reticencias && limite > 0 {
+            valor.chars().take(limite - 1).chain(std::iter::once('…')).collect()
+        } else {
+            valor.chars().take(limite).collect()
+        };
+
+        avisos.registrar("textos truncados", tr!("a coluna {} foi truncada para {} caracteres: '{}' -> '{}'.", coluna, limite, valor, truncado));
+        truncado
+    }
+
+    /// Colunas `Ano` e `Mes` de `separar_mes_ano`.
+    pub(crate) fn colunas_moeda(&self) -> Vec<&'static str> {
+        if self.coluna_moeda {
+            vec!["Moeda"]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Valor da coluna de [`Opcoes::colunas_moeda`]: o atributo `moeda` do `<Valor>`, ou
+    /// [`MOEDA_PADRAO`] quando ausente.
+    pub(crate) fn valores_moeda<'a>(&self, funcionario: &'a Funcionario) -> Vec<&'a str> {
+        if self.coluna_moeda {
+            vec![funcionario.valor.moeda()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub(crate) fn colunas_competencia(&self) -> Vec<&'static str> {
+        if self.separar_mes_ano {
+            vec!["Ano", "Mes"]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Valores das colunas de [`Opcoes::colunas_competencia`], vazios quando o `MesAno` não é
+    /// uma competência reconhecida.
+    pub(crate) fn valores_competencia<'a>(&self, mes_ano: &'a str) -> Vec<&'a str> {
+        if !self.separar_mes_ano {
+            return Vec::new();
+        }
+        // O `MesAno` reconhecido já foi normalizado para `MM/AAAA` na leitura.
+        match (ano_mes(mes_ano), mes_ano.split_once('/')) {
+            (Some(_), Some((mes, ano))) => vec![ano, mes],
+            _ => vec!["", ""],
+        }
+    }
+
+    /// Colunas extras com o texto original das colunas truncadas.
+    pub(crate) fn colunas_originais(&self) -> Vec<&'static str> {
+        let mut colunas = Vec::new();
+        if self.manter_original {
+            if self.truncar_fantasia.is_some() {
+                colunas.push("FantasiaOriginal");
+            }
+            if self.truncar_razao.is_some() {
+                colunas.push("RazaoOriginal");
+            }
+        }
+        if self.manter_valor_original {
+            colunas.push("ValorOriginal");
+        }
+        colunas
+    }
+
+    /// Valores das colunas retornadas por [`Opcoes::colunas_originais`].
+    pub(crate) fn valores_originais<'a>(&self, empresa: &'a Empresa, funcionario: &'a Funcionario) -> Vec<&'a str> {
+        let mut valores = Vec::new();
+        if self.manter_original {
+            if self.truncar_fantasia.is_some() {
+                valores.push(empresa.fantasia.as_str());
+            }
+            if self.truncar_razao.is_some() {
+                valores.push(empresa.razao.as_str());
+            }
+        }
+        if self.manter_valor_original {
+            valores.push(funcionario.valor.valor.as_str());
+        }
+        valores
+    }
+
+    /// Nomes das colunas de `calculadas`.
+    pub(crate) fn colunas_calculadas(&self) -> Vec<&str> {
+        self.calculadas.iter().map(|coluna| coluna.nome.as_str()).collect()
+    }
+
+    /// Valores das colunas de [`Opcoes::colunas_calculadas`], com duas casas e o separador
+    /// decimal da saída; vazios quando o cálculo não é possível.
+    ///
+    /// `Valor` e `MetaPremio` entram como números do XML (sem câmbio) e as demais colunas
+    /// pelo texto já gravado em `registro`.
+    pub(crate) fn valores_calculados(&self, funcionario: &Funcionario, cabecalho: &[&str], registro: &[&str]) -> Vec<String> {
+        if self.calculadas.is_empty() {
+            return Vec::new();
+        }
+        let virgula = match self.formato_monetario {
+            Some(formato) => matches!(formato, FormatoMonetario::PtBr | FormatoMonetario::Real),
+            None => self.decimal_saida == Some(Decimal::Virgula),
+        };
+        let coluna = |nome: &str| match nome {
+            "Valor" => self.ler_quantia(&funcionario.valor.valor),
+            "MetaPremio" => funcionario.meta_premio.as_deref().and_then(|meta| self.ler_quantia(meta)),
+            _ => cabecalho.iter().position(|coluna| *coluna == nome).and_then(|indice| self.ler_quantia(registro.get(indice)?)),
+        };
+        self.calculadas
+            .iter()
+            .map(|calculada| match calculada.calcular(&coluna) {
+                Some(resultado) if virgula => format!("{:.2}", resultado).replace('.', ","),
+                Some(resultado) => format!("{:.2}", resultado),
+                None => String::new(),
+            })
+            .collect()
+    }
+
+    /// Lê `Valor` ou `MetaPremio` com o separador decimal de `decimal_entrada`.
+    pub(crate) fn ler_numero(&self, texto: &str) -> Option<f64> {
+        parse_valor_decimal(texto, self.decimal_entrada)
+    }
+
+    /// Como [`Opcoes::ler_numero`], em decimal exato para os totais e as colunas calculadas.
+    pub(crate) fn ler_quantia(&self, texto: &str) -> Option<Quantia> {
+        Quantia::ler(texto, self.decimal_entrada)
+    }
+
+    /// Texto gravado para `Valor` ou `MetaPremio`, conforme `formato_monetario` ou, sem ele,
+    /// `decimal_entrada` e `decimal_saida`.
+    ///
+    /// Textos que não são números são gravados como vieram.
+    pub(crate) fn texto_numero(&self, texto: &str) -> String {
+        match self.formato_monetario {
+            Some(FormatoMonetario::Original) => return texto.to_string(),
+            Some(formato) => return self.ler_quantia(texto).map_or_else(|| texto.to_string(), |valor| formato.formatar(valor)),
+            None => {}
+        }
+        if self.decimal_entrada.is_none() && self.decimal_saida.is_none() {
+            return normalizar_valor(texto);
+        }
+        match self.ler_numero(texto) {
+            Some(valor) => formatar_valor(valor, self.decimal_saida.unwrap_or(Decimal::Ponto)),
+            None => texto.to_string(),
+        }
+    }
+
+    /// Converte o valor para reais, avisando quando a moeda não tem taxa configurada.
+    pub(crate) fn valor_em_reais(&self, funcionario: &Funcionario, avisos: &mut Avisos) -> Quantia {
+        let valor = self.ler_quantia(&funcionario.valor.valor).unwrap_or_default();
+        let moeda = funcionario.valor.moeda().to_uppercase();
+
+        if moeda == MOEDA_PADRAO {
+            return valor;
+        }
+
+        match self.cambio.get(&moeda) {
+            Some(taxa) => valor.multiplicar(*taxa),
+            None => {
+                avisos.registrar("valores sem taxa de câmbio", tr!("o CPF {} possui valor em {} sem taxa de câmbio informada (use --cambio {}=TAXA). O valor foi somado sem conversão.", funcionario.cpf, moeda, moeda));
+                valor
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::cmp::Ordering;
+
+use crate::modelo::Funcionario;
+use crate::opcoes::Opcoes;
+
+/// Campo pelo qual `--sort` ordena as linhas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CampoOrdenacao {
+    Cpf,
+    Valor,
+    MetaPremio,
+    MesAno,
+    DataInicio,
+    DataFim,
+    Parcela,
+}
+
+/// Um critério de `--sort`, ex.: `valor:desc`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ordenacao {
+    pub campo: CampoOrdenacao,
+    pub decrescente: bool,
+}
+
+impl Ordenacao {
+    /// Nomes de campo aceitos por `--sort`.
+    pub const CAMPOS: [&'static str; 7] = ["cpf", "valor", "metapremio", "mesano", "datainicio", "datafim", "parcela"];
+
+    /// Lê um critério no formato `campo` ou `campo:asc`/`campo:desc`.
+    pub fn from_texto(texto: &str) -> Result<Ordenacao, String> {
+        let (nome, direcao) = texto.split_once(':').unwrap_or((texto, "asc"));
+        let campo = match nome.trim().to_lowercase().as_str() {
+            "cpf" => CampoOrdenacao::Cpf,
+            "valor" => CampoOrdenacao::Valor,
+            "metapremio" => CampoOrdenacao::MetaPremio,
+            "mesano" => CampoOrdenacao::MesAno,
+            "datainicio" => CampoOrdenacao::DataInicio,
+            "datafim" => CampoOrdenacao::DataFim,
+            "parcela" => CampoOrdenacao::Parcela,
+            _ => return Err(tr!("Campo inválido para sort: '{}'. Use um de: {}.", nome.trim(), Ordenacao::CAMPOS.join(", "))),
+        };
+        let decrescente = match direcao.trim().to_lowercase().as_str() {
+            "asc" => false,
+            "desc" => true,
+            _ => return Err(tr!("Direção inválida para sort: '{}'. Use asc ou desc.", direcao.trim())),
+        };
+        Ok(Ordenacao { campo, decrescente })
+    }
+}
+
+/// Ordena os funcionários pelos critérios, na ordem em que foram informados; empates
+/// mantêm a ordem do XML.
+///
+/// `Valor` e `MetaPremio` são comparados como números, na moeda do XML (sem câmbio), e as
+/// datas (`dd/mm/aaaa` ou `mm/aaaa`) pela data. Campos vazios ou que não são números ficam
+/// por último, em qualquer direção.
+pub(crate) fn ordenar(funcionarios: &mut [Funcionario], criterios: &[Ordenacao], opcoes: &Opcoes) {
+    funcionarios.sort_by(|a, b| {
+        criterios
+            .iter()
+            .map(|criterio| comparar(a, b, *criterio, opcoes))
+            .find(|ordem| ordem.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+fn comparar(a: &Funcionario, b: &Funcionario, criterio: Ordenacao, opcoes: &Opcoes) -> Ordering {
+    let numero = |texto: Option<&str>| texto.and_then(|texto| opcoes.ler_numero(texto));
+    let decrescente = criterio.decrescente;
+    match criterio.campo {
+        CampoOrdenacao::Cpf => ausentes_por_ultimo(texto(Some(&digitos(&a.cpf))), texto(Some(&digitos(&b.cpf))), decrescente, Ord::cmp),
+        CampoOrdenacao::Valor => ausentes_por_ultimo(numero(Some(&a.valor.valor)), numero(Some(&b.valor.valor)), decrescente, f64::total_cmp),
+        CampoOrdenacao::MetaPremio => ausentes_por_ultimo(numero(a.meta_premio.as_deref()), numero(b.meta_premio.as_deref()), decrescente, f64::total_cmp),
+        CampoOrdenacao::MesAno => ausentes_por_ultimo(chave_data(a.mes_ano.as_deref()), chave_data(b.mes_ano.as_deref()), decrescente, Ord::cmp),
+        CampoOrdenacao::DataInicio => ausentes_por_ultimo(chave_data(a.data_inicio.as_deref()), chave_data(b.data_inicio.as_deref()), decrescente, Ord::cmp),
+        CampoOrdenacao::DataFim => ausentes_por_ultimo(chave_data(a.data_fim.as_deref()), chave_data(b.data_fim.as_deref()), decrescente, Ord::cmp),
+        CampoOrdenacao::Parcela => ausentes_por_ultimo(texto(a.parcela.as_deref()), texto(b.parcela.as_deref()), decrescente, Ord::cmp),
+    }
+}
+
+/// Compara na direção pedida, mantendo os ausentes (`None`) por último.
+fn ausentes_por_ultimo<T>(a: Option<T>, b: Option<T>, decrescente: bool, comparar: impl Fn(&T, &T) -> Ordering) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if decrescente => comparar(&b, &a),
+        (Some(a), Some(b)) => comparar(&a, &b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn digitos(texto: &str) -> String {
+    texto.chars().filter(char::is_ascii_digit).collect()
+}
+
+fn texto(valor: Option<&str>) -> Option<String> {
+    valor.map(str::trim).filter(|texto| !texto.is_empty()).map(str::to_lowercase)
+}
+
+/// Partes da data da maior para a menor: `dd/mm/aaaa` e `mm/aaaa` são invertidas, `aaaa-mm-dd`
+/// é mantida. Datas que não são números ficam como ausentes.
+fn chave_data(valor: Option<&str>) -> Option<Vec<u32>> {
+    let texto = texto(valor)?;
+    let mut partes = texto.split(['/', '-']).map(|parte| parte.trim().parse().ok()).collect::<Option<Vec<u32>>>()?;
+    if texto.split(['/', '-']).next().is_some_and(|primeira| primeira.trim().len() < 4) {
+        partes.reverse();
+    }
+    Some(partes)
+}
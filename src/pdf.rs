@@ -0,0 +1,178 @@
+use colored::*;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use converterxmlcsv::{FormatoMonetario, Quantia, Tipo};
+
+use crate::relatorio::{total_geral, LinhaRelatorio};
+
+/// Página A4, em pontos.
+const LARGURA: f32 = 595.0;
+const ALTURA: f32 = 842.0;
+const MARGEM: f32 = 56.0;
+
+/// Uma linha de texto do resumo.
+struct Texto {
+    negrito: bool,
+    tamanho: f32,
+    conteudo: String,
+}
+
+impl Texto {
+    fn normal(conteudo: String) -> Texto {
+        Texto { negrito: false, tamanho: 10.0, conteudo }
+    }
+
+    fn negrito(conteudo: String, tamanho: f32) -> Texto {
+        Texto { negrito: true, tamanho, conteudo }
+    }
+
+    /// Altura ocupada pela linha, com o espaço até a próxima.
+    fn altura(&self) -> f32 {
+        self.tamanho * 1.5
+    }
+}
+
+/// Grava em `caminho` o resumo do lote em PDF (`--pdf`): para cada XML convertido, a
+/// empresa, a competência, a quantidade de funcionários e os totais, seguidos dos totais
+/// gerais, para ser anexado à aprovação do pagamento.
+pub fn gravar_resumo(caminho: &Path, linhas: &[LinhaRelatorio], gerado_em: &str, console: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    let mut textos = vec![Texto::negrito(tr!("Resumo do lote").to_string(), 16.0), Texto::normal(tr!("Gerado em {}", gerado_em)), Texto::normal(String::new())];
+    if linhas.is_empty() {
+        textos.push(Texto::normal(tr!("Nenhum XML foi convertido.").to_string()));
+    }
+    for linha in linhas {
+        textos.push(Texto::negrito(linha.arquivo.display().to_string(), 11.0));
+        textos.push(Texto::normal(tr!("Empresa: {}", linha.empresa)));
+        textos.push(Texto::normal(tr!("Mês/ano: {}", if linha.mes_ano.is_empty() { tr!("não informado") } else { &linha.mes_ano })));
+        textos.push(Texto::normal(tr!("Funcionários: {}", linha.funcionarios)));
+        textos.extend(totais(linha));
+        textos.push(Texto::normal(String::new()));
+    }
+    let total = total_geral(linhas);
+    textos.push(Texto::negrito(tr!("Total geral").to_string(), 11.0));
+    textos.push(Texto::normal(tr!("Arquivos: {}", linhas.len())));
+    textos.push(Texto::normal(tr!("Funcionários: {}", total.funcionarios)));
+    textos.extend(totais(&total));
+
+    fs::write(caminho, documento(&textos))?;
+    writeln!(console, "{}", tr!("Resumo em PDF gravado em {}.", caminho.display()).bright_cyan())?;
+    Ok(())
+}
+
+/// Totais da linha: o do tipo do XML e, na comissão, o de meta; na linha de totais gerais,
+/// os que não são zero.
+fn totais(linha: &LinhaRelatorio) -> Vec<Texto> {
+    let todos = [
+        (Some(Tipo::Comissao), tr!("Total de comissão"), linha.total_comissao),
+        (Some(Tipo::Comissao), tr!("Total por meta"), linha.total_meta),
+        (Some(Tipo::Vales), tr!("Total de vales"), linha.total_vales),
+        (Some(Tipo::Ferias), tr!("Total de férias"), linha.total_ferias),
+        (Some(Tipo::DecimoTerceiro), tr!("Total de décimo terceiro"), linha.total_decimo_terceiro),
+    ];
+    todos
+        .into_iter()
+        .filter(|(tipo, _, valor)| match linha.tipo {
+            Some(_) => *tipo == linha.tipo,
+            None => *valor != Quantia::ZERO,
+        })
+        .map(|(_, rotulo, valor)| Texto::normal(format!("{}: {}", rotulo, FormatoMonetario::Real.formatar(valor))))
+        .collect()
+}
+
+/// Documento PDF com os textos em Helvetica, quebrando as páginas quando necessário.
+fn documento(textos: &[Texto]) -> Vec<u8> {
+    let mut paginas: Vec<String> = Vec::new();
+    let mut conteudo = String::new();
+    let mut altura = ALTURA - MARGEM;
+    for texto in textos {
+        if altura - texto.altura() < MARGEM && !conteudo.is_empty() {
+            paginas.push(std::mem::take(&mut conteudo));
+            altura = ALTURA - MARGEM;
+        }
+        altura -= texto.altura();
+        if !texto.conteudo.is_empty() {
+            let fonte = if texto.negrito { "F2" } else { "F1" };
+            conteudo.push_str(&format!("BT /{} {} Tf {} {:.1} Td ({}) Tj ET\n", fonte, texto.tamanho, MARGEM, altura, literal(&texto.conteudo)));
+        }
+    }
+    paginas.push(conteudo);
+
+    // Objetos: 1 catálogo, 2 páginas, 3 e 4 fontes e, para cada página, ela e o seu conteúdo.
+    let filhos: Vec<String> = (0..paginas.len()).map(|indice| format!("{} 0 R", 5 + indice * 2)).collect();
+    let mut objetos = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", filhos.join(" "), paginas.len()),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+    for (indice, pagina) in paginas.iter().enumerate() {
+        objetos.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            LARGURA,
+            ALTURA,
+            6 + indice * 2
+        ));
+        objetos.push(format!("<< /Length {} >>\nstream\n{}endstream", pagina.chars().count(), pagina));
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut posicoes = Vec::new();
+    for (indice, objeto) in objetos.iter().enumerate() {
+        posicoes.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", indice + 1).bytes());
+        pdf.extend(win_ansi(objeto));
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objetos.len() + 1).bytes());
+    for posicao in posicoes {
+        pdf.extend(format!("{:010} 00000 n \n", posicao).bytes());
+    }
+    pdf.extend(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objetos.len() + 1, xref).bytes());
+    pdf
+}
+
+/// Texto entre parênteses do PDF, com `(`, `)` e `\` escapados.
+fn literal(texto: &str) -> String {
+    texto.replace('\\', "\\\\").replace('(', "\\(").replace(')', "\\)")
+}
+
+/// Bytes do texto na codificação das fontes: os caracteres do Latin-1 são os mesmos no
+/// WinAnsi; os demais viram `?`.
+///
+/// O tamanho de cada stream é contado em caracteres, que aqui são sempre um byte.
+fn win_ansi(texto: &str) -> Vec<u8> {
+    texto.chars().map(|caractere| if (caractere as u32) < 0x80 || (0xA0..=0xFF).contains(&(caractere as u32)) { caractere as u8 } else { b'?' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn referencias_apontam_para_os_objetos() {
+        let textos: Vec<Texto> = (0..60).map(|indice| Texto::normal(format!("Linha {}", indice))).collect();
+        let pdf = documento(&textos);
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+
+        let texto = String::from_utf8(pdf).unwrap();
+        // Cabem 48 linhas de 15 pontos em uma página A4.
+        assert!(texto.contains("/Count 2 >>"), "{}", texto);
+        let xref: usize = texto.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        assert!(texto[xref..].starts_with("xref\n0 9\n"));
+        for (indice, linha) in texto[xref..].lines().skip(3).take(8).enumerate() {
+            let posicao: usize = linha[..10].parse().unwrap();
+            assert!(texto[posicao..].starts_with(&format!("{} 0 obj\n", indice + 1)), "objeto {}", indice + 1);
+        }
+    }
+
+    #[test]
+    fn textos_escapados_em_win_ansi() {
+        assert_eq!(literal(r"Total (R$) \ 1"), r"Total \(R$\) \\ 1");
+        assert_eq!(win_ansi("Férias €"), b"F\xe9rias ?");
+    }
+}
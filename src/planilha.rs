@@ -0,0 +1,121 @@
+use rust_xlsxwriter::{Format, Workbook, Worksheet};
+use std::io::Write;
+
+use crate::erro::ErroConversor;
+use crate::saida::Destino;
+use crate::valor::Quantia;
+
+/// Colunas gravadas como número na planilha e somadas na linha de totais.
+const COLUNAS_NUMERICAS: [&str; 2] = ["Valor", "MetaPremio"];
+
+/// Caracteres que o Excel não aceita no nome de uma planilha.
+const CARACTERES_PROIBIDOS: [char; 7] = ['[', ']', ':', '*', '?', '/', '\\'];
+
+/// Tamanho máximo do nome de uma planilha no Excel.
+const MAX_NOME_PLANILHA: usize = 31;
+
+/// Grava as linhas em uma pasta de trabalho XLSX com uma única planilha.
+///
+/// A primeira linha é o cabeçalho; `Valor` e `MetaPremio` são gravados como números
+/// (quando o texto é reconhecido) e somados em uma linha de totais ao final. O arquivo só
+/// é gravado no escritor em [`SaidaXlsx::finalizar`].
+pub struct SaidaXlsx<W: Write> {
+    writer: W,
+    planilha: Worksheet,
+    cabecalho: Vec<String>,
+    /// Próxima linha livre da planilha.
+    linha: u32,
+    totais: Vec<Quantia>,
+    iniciado: bool,
+}
+
+impl<W: Write> SaidaXlsx<W> {
+    /// `nome` é o nome da planilha, normalmente o do arquivo de origem; caracteres não
+    /// aceitos pelo Excel são trocados por `_` e o nome é cortado em 31 caracteres.
+    pub fn new(writer: W, nome: &str) -> Result<SaidaXlsx<W>, ErroConversor> {
+        let nome: String = nome
+            .chars()
+            .map(|c| if CARACTERES_PROIBIDOS.contains(&c) { '_' } else { c })
+            .take(MAX_NOME_PLANILHA)
+            .collect();
+
+        let mut planilha = Worksheet::new();
+        if !nome.trim().is_empty() {
+            planilha.set_name(nome)?;
+        }
+
+        Ok(SaidaXlsx {
+            writer,
+            planilha,
+            cabecalho: Vec::new(),
+            linha: 0,
+            totais: Vec::new(),
+            iniciado: false,
+        })
+    }
+
+    /// Grava a linha de totais e o arquivo XLSX e devolve o escritor.
+    ///
+    /// Sem [`Destino::iniciar`] (empresa sem funcionários) nada é gravado.
+    pub fn finalizar(mut self) -> Result<W, ErroConversor> {
+        Destino::finalizar(&mut self)?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> Destino for SaidaXlsx<W> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        let negrito = Format::new().set_bold();
+        for (indice, coluna) in cabecalho.iter().enumerate() {
+            self.planilha.write_string_with_format(0, indice as u16, *coluna, &negrito)?;
+        }
+        self.planilha.set_freeze_panes(1, 0)?;
+
+        self.cabecalho = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        self.totais = vec![Quantia::ZERO; cabecalho.len()];
+        self.linha = 1;
+        self.iniciado = true;
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        let numero = Format::new().set_num_format("#,##0.00");
+        for (indice, valor) in registro.iter().enumerate() {
+            let numerica = self.cabecalho.get(indice).is_some_and(|coluna| COLUNAS_NUMERICAS.contains(&coluna.as_str()));
+            match Quantia::ler(valor, None).filter(|_| numerica) {
+                Some(numero_lido) => {
+                    self.planilha.write_number_with_format(self.linha, indice as u16, numero_lido.to_f64(), &numero)?;
+                    self.totais[indice] += numero_lido;
+                }
+                None => {
+                    self.planilha.write_string(self.linha, indice as u16, *valor)?;
+                }
+            }
+        }
+        self.linha += 1;
+        Ok(())
+    }
+
+    fn finalizar(&mut self) -> Result<(), ErroConversor> {
+        if !self.iniciado {
+            return Ok(());
+        }
+
+        let negrito = Format::new().set_bold();
+        let moeda = Format::new().set_bold().set_num_format("#,##0.00");
+        self.planilha.write_string_with_format(self.linha, 0, "Total", &negrito)?;
+        for (indice, coluna) in self.cabecalho.iter().enumerate() {
+            if COLUNAS_NUMERICAS.contains(&coluna.as_str()) {
+                self.planilha.write_number_with_format(self.linha, indice as u16, self.totais[indice].to_f64(), &moeda)?;
+            }
+        }
+        self.planilha.autofit();
+
+        let mut pasta = Workbook::new();
+        pasta.push_worksheet(std::mem::replace(&mut self.planilha, Worksheet::new()));
+        self.writer.write_all(&pasta.save_to_buffer()?)?;
+        self.writer.flush()?;
+        self.iniciado = false;
+        Ok(())
+    }
+}
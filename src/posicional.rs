@@ -0,0 +1,306 @@
+use serde::Deserialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::erro::ErroConversor;
+use crate::saida::Destino;
+use crate::valor::Quantia;
+
+/// Valores calculados que podem ser usados em `column` além das colunas do arquivo gerado.
+const CALCULADOS: [&str; 5] = ["Sequencia", "Quantidade", "Registros", "Total", "Data"];
+
+/// Leiaute de arquivo posicional (`--layout`), em que cada campo ocupa posições fixas da
+/// linha, como nos arquivos de crédito de salário dos bancos. Exemplo:
+///
+/// ```toml
+/// crlf = true
+///
+/// [[header]]
+/// value = "0"
+/// width = 1
+///
+/// [[header]]
+/// column = "CNPJ"
+/// width = 14
+/// align = "right"
+/// pad = "0"
+/// format = "digits"
+///
+/// [[fields]]
+/// value = "1"
+/// width = 1
+///
+/// [[fields]]
+/// column = "CPF"
+/// start = 2
+/// width = 11
+///
+/// [[fields]]
+/// column = "Valor"
+/// width = 15
+/// align = "right"
+/// pad = "0"
+/// format = "cents"
+///
+/// [[trailer]]
+/// column = "Total"
+/// width = 17
+/// align = "right"
+/// pad = "0"
+/// format = "cents"
+/// ```
+///
+/// `fields` monta uma linha por funcionário; `header` e `trailer`, opcionais, a primeira e a
+/// última linha do arquivo, com os valores da primeira linha. Além das colunas, `column`
+/// aceita os valores de `CALCULADOS`: o número da linha no arquivo (`Sequencia`), a
+/// quantidade de funcionários, a de linhas do arquivo (`Registros`), a soma de `Valor` e a
+/// data da geração.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LeiautePosicional {
+    /// Termina as linhas com CRLF em vez de LF.
+    #[serde(default)]
+    pub crlf: bool,
+    #[serde(default)]
+    pub header: Vec<Campo>,
+    pub fields: Vec<Campo>,
+    #[serde(default)]
+    pub trailer: Vec<Campo>,
+}
+
+/// Campo de uma linha do arquivo posicional.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Campo {
+    /// Coluna do arquivo gerado, ou um dos `CALCULADOS`, cujo valor ocupa o campo.
+    pub column: Option<String>,
+    /// Texto fixo, usado no lugar de `column`.
+    pub value: Option<String>,
+    /// Posição do primeiro caractere, a partir de 1; sem ela, logo após o campo anterior.
+    /// As posições puladas são preenchidas com espaços.
+    pub start: Option<usize>,
+    pub width: usize,
+    #[serde(default)]
+    pub align: Alinhamento,
+    /// Caractere que completa a largura do campo.
+    #[serde(default = "preenchimento_padrao")]
+    pub pad: char,
+    pub format: Option<FormatoCampo>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub enum Alinhamento {
+    #[default]
+    #[serde(rename = "left")]
+    Esquerda,
+    #[serde(rename = "right")]
+    Direita,
+}
+
+/// Transformação do valor antes de ele ser alinhado no campo.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum FormatoCampo {
+    /// Só os dígitos, ex.: `11.222.333/0001-81` → `11222333000181`.
+    #[serde(rename = "digits")]
+    Digitos,
+    /// Valor em centavos, sem separadores (`1.234,56` → `123456`).
+    #[serde(rename = "cents")]
+    Centavos,
+    #[serde(rename = "upper")]
+    Maiusculas,
+}
+
+fn preenchimento_padrao() -> char {
+    ' '
+}
+
+impl LeiautePosicional {
+    /// Lê e valida o arquivo de leiaute.
+    pub fn carregar(caminho: &Path) -> Result<LeiautePosicional, ErroConversor> {
+        let conteudo = fs::read_to_string(caminho)
+            .map_err(|erro| ErroConversor::EntradaInvalida(tr!("Não foi possível ler o leiaute '{}': {}", caminho.display(), erro)))?;
+        let leiaute: LeiautePosicional =
+            toml::from_str(&conteudo).map_err(|erro| ErroConversor::EntradaInvalida(tr!("Leiaute '{}' inválido: {}", caminho.display(), erro)))?;
+
+        if leiaute.fields.is_empty() {
+            return Err(ErroConversor::EntradaInvalida(tr!("O leiaute '{}' não define nenhum campo em fields.", caminho.display())));
+        }
+        for (secao, campos) in [("header", &leiaute.header), ("fields", &leiaute.fields), ("trailer", &leiaute.trailer)] {
+            let mut posicao = 1;
+            for (indice, campo) in campos.iter().enumerate() {
+                let descricao = tr!("o campo {} de {} no leiaute '{}'", indice + 1, secao, caminho.display());
+                if campo.column.is_some() == campo.value.is_some() {
+                    return Err(ErroConversor::EntradaInvalida(tr!("Informe column ou value em {}.", descricao)));
+                }
+                if campo.width == 0 {
+                    return Err(ErroConversor::EntradaInvalida(tr!("A largura (width) deve ser maior que zero em {}.", descricao)));
+                }
+                let inicio = campo.start.unwrap_or(posicao);
+                if inicio < posicao {
+                    return Err(ErroConversor::EntradaInvalida(tr!("Em {}, a posição {} fica antes do fim do campo anterior ({}).", descricao, inicio, posicao)));
+                }
+                posicao = inicio + campo.width;
+            }
+        }
+        Ok(leiaute)
+    }
+
+    /// Confere se as colunas usadas existem no arquivo gerado.
+    fn validar(&self, cabecalho: &[String]) -> Result<(), ErroConversor> {
+        let colunas = self.header.iter().chain(&self.fields).chain(&self.trailer).filter_map(|campo| campo.column.as_deref());
+        for coluna in colunas {
+            if !cabecalho.iter().any(|atual| atual == coluna) && !CALCULADOS.contains(&coluna) {
+                return Err(ErroConversor::EntradaInvalida(tr!(
+                    "Coluna desconhecida em --layout: '{}'. Use uma das colunas ({}) ou {}.",
+                    coluna,
+                    cabecalho.join(", "),
+                    CALCULADOS.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Campo {
+    /// Valor formatado e alinhado na largura do campo. Um texto maior que o campo é cortado;
+    /// um número (`digits` ou `cents`) maior que ele é um erro, para não gravar um valor errado.
+    fn montar(&self, valor: &str) -> Result<String, ErroConversor> {
+        let valor = match self.format {
+            None => valor.to_string(),
+            Some(FormatoCampo::Maiusculas) => valor.to_uppercase(),
+            Some(FormatoCampo::Digitos) => valor.chars().filter(char::is_ascii_digit).collect(),
+            Some(FormatoCampo::Centavos) => match Quantia::ler(valor, None) {
+                Some(numero) => numero.centavos().to_string(),
+                None if valor.trim().is_empty() => String::new(),
+                None => return Err(ErroConversor::ValorInvalido(tr!("O valor '{}' de {} não é um número.", valor, self.nome()))),
+            },
+        };
+        let tamanho = valor.chars().count();
+        if tamanho > self.width && matches!(self.format, Some(FormatoCampo::Digitos | FormatoCampo::Centavos)) {
+            return Err(ErroConversor::ValorInvalido(tr!("O valor '{}' de {} não cabe nas {} posições do campo.", valor, self.nome(), self.width)));
+        }
+        let texto: String = valor.chars().take(self.width).collect();
+        let complemento: String = std::iter::repeat_n(self.pad, self.width - tamanho.min(self.width)).collect();
+        Ok(match self.align {
+            Alinhamento::Esquerda => texto + &complemento,
+            Alinhamento::Direita => complemento + &texto,
+        })
+    }
+
+    fn nome(&self) -> &str {
+        self.column.as_deref().unwrap_or("value")
+    }
+}
+
+/// Grava as linhas no arquivo posicional de [`LeiautePosicional`]. O `header` é gravado com
+/// a primeira linha e o `trailer`, com os totais, em [`SaidaPosicional::finalizar`].
+pub struct SaidaPosicional<W: Write> {
+    writer: W,
+    leiaute: LeiautePosicional,
+    /// Valor de `Data`.
+    data: String,
+    cabecalho: Vec<String>,
+    /// Linhas gravadas no arquivo, inclusive o `header`.
+    registros: usize,
+    quantidade: usize,
+    total: Quantia,
+    iniciado: bool,
+    cabecalho_gravado: bool,
+}
+
+impl<W: Write> SaidaPosicional<W> {
+    /// `data` é a data da geração, como deve aparecer em `Data` (ex.: `15102026`).
+    pub fn new(writer: W, leiaute: LeiautePosicional, data: &str) -> SaidaPosicional<W> {
+        SaidaPosicional {
+            writer,
+            leiaute,
+            data: data.to_string(),
+            cabecalho: Vec::new(),
+            registros: 0,
+            quantidade: 0,
+            total: Quantia::ZERO,
+            iniciado: false,
+            cabecalho_gravado: false,
+        }
+    }
+
+    /// Grava o `trailer` e devolve o escritor. Sem [`Destino::iniciar`] nada é gravado.
+    pub fn finalizar(mut self) -> Result<W, ErroConversor> {
+        Destino::finalizar(&mut self)?;
+        Ok(self.writer)
+    }
+
+    /// Grava uma linha com os campos da `secao` do leiaute, tirando os valores das colunas de
+    /// `registro`.
+    fn gravar_linha(&mut self, secao: fn(&LeiautePosicional) -> &[Campo], registro: &[&str]) -> Result<(), ErroConversor> {
+        let campos = secao(&self.leiaute);
+        if campos.is_empty() {
+            return Ok(());
+        }
+        self.registros += 1;
+        let mut linha = String::new();
+        for campo in campos {
+            let inicio = campo.start.unwrap_or(linha.chars().count() + 1);
+            linha.extend(std::iter::repeat_n(' ', (inicio - 1).saturating_sub(linha.chars().count())));
+            let valor = match (&campo.value, campo.column.as_deref()) {
+                (Some(valor), _) => valor.clone(),
+                (None, Some(coluna)) => match self.cabecalho.iter().position(|atual| atual == coluna) {
+                    Some(indice) => registro.get(indice).copied().unwrap_or_default().to_string(),
+                    None => self.calculado(coluna),
+                },
+                (None, None) => String::new(),
+            };
+            linha.push_str(&campo.montar(&valor)?);
+        }
+        linha.push_str(if self.leiaute.crlf { "\r\n" } else { "\n" });
+        self.writer.write_all(linha.as_bytes())?;
+        Ok(())
+    }
+
+    fn calculado(&self, nome: &str) -> String {
+        match nome {
+            "Sequencia" | "Registros" => self.registros.to_string(),
+            "Quantidade" => self.quantidade.to_string(),
+            "Total" => format!("{:.2}", self.total),
+            "Data" => self.data.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+impl<W: Write> Destino for SaidaPosicional<W> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.cabecalho = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        self.leiaute.validar(&self.cabecalho)?;
+        self.iniciado = true;
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        if !self.cabecalho_gravado {
+            self.gravar_linha(|leiaute| &leiaute.header, registro)?;
+            self.cabecalho_gravado = true;
+        }
+        self.quantidade += 1;
+        if let Some(indice) = self.cabecalho.iter().position(|coluna| coluna == "Valor") {
+            self.total += registro.get(indice).and_then(|valor| Quantia::ler(valor, None)).unwrap_or_default();
+        }
+        self.gravar_linha(|leiaute| &leiaute.fields, registro)
+    }
+
+    fn finalizar(&mut self) -> Result<(), ErroConversor> {
+        if !self.iniciado {
+            return Ok(());
+        }
+        if !self.cabecalho_gravado {
+            self.gravar_linha(|leiaute| &leiaute.header, &[])?;
+            self.cabecalho_gravado = true;
+        }
+        self.gravar_linha(|leiaute| &leiaute.trailer, &[])?;
+        self.writer.flush()?;
+        self.iniciado = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use colored::*;
+use std::io::{self, Write};
+
+use converterxmlcsv::{Destino, ErroConversor};
+
+/// Quantidade padrão de linhas exibidas por `--dry-run`.
+pub const LINHAS_PREVIA: usize = 10;
+
+/// Largura máxima de uma coluna da tabela; textos maiores são cortados com reticências.
+const LARGURA_MAXIMA: usize = 30;
+
+/// Destino de `--dry-run`: guarda o cabeçalho e as primeiras linhas para a tabela de
+/// prévia, contando as demais sem guardá-las.
+#[derive(Debug)]
+pub struct Previa {
+    cabecalho: Vec<String>,
+    linhas: Vec<Vec<String>>,
+    limite: usize,
+    total: usize,
+}
+
+impl Previa {
+    pub fn new(limite: usize) -> Previa {
+        Previa { cabecalho: Vec::new(), linhas: Vec::new(), limite, total: 0 }
+    }
+}
+
+impl Destino for Previa {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.cabecalho = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        self.total += 1;
+        if self.linhas.len() < self.limite {
+            self.linhas.push(registro.iter().map(|valor| valor.to_string()).collect());
+        }
+        Ok(())
+    }
+}
+
+/// Exibe em `console` a tabela com as linhas guardadas e quantas ficaram de fora.
+pub fn exibir_previa(console: &mut dyn Write, previa: &Previa) -> io::Result<()> {
+    if previa.cabecalho.is_empty() {
+        return Ok(());
+    }
+
+    let celula = |texto: &str| -> String {
+        if texto.chars().count() <= LARGURA_MAXIMA {
+            return texto.to_string();
+        }
+        let mut cortado: String = texto.chars().take(LARGURA_MAXIMA - 1).collect();
+        cortado.push('…');
+        cortado
+    };
+    let cabecalho: Vec<String> = previa.cabecalho.iter().map(|coluna| celula(coluna)).collect();
+    let linhas: Vec<Vec<String>> = previa.linhas.iter().map(|linha| linha.iter().map(|valor| celula(valor)).collect()).collect();
+    let larguras: Vec<usize> = (0..cabecalho.len())
+        .map(|indice| {
+            std::iter::once(&cabecalho)
+                .chain(&linhas)
+                .filter_map(|linha| linha.get(indice))
+                .map(|texto| texto.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let borda = |esquerda: &str, meio: &str, direita: &str| -> String {
+        let partes: Vec<String> = larguras.iter().map(|largura| "─".repeat(largura + 2)).collect();
+        format!("{}{}{}", esquerda, partes.join(meio), direita)
+    };
+    let linha = |valores: &[String]| -> String {
+        let partes: Vec<String> = larguras
+            .iter()
+            .enumerate()
+            .map(|(indice, largura)| {
+                let texto = valores.get(indice).map_or("", String::as_str);
+                format!(" {}{} ", texto, " ".repeat(largura - texto.chars().count()))
+            })
+            .collect();
+        format!("│{}│", partes.join("│"))
+    };
+
+    writeln!(console, "{}", borda("┌", "┬", "┐"))?;
+    writeln!(console, "{}", linha(&cabecalho).bold())?;
+    writeln!(console, "{}", borda("├", "┼", "┤"))?;
+    for valores in &linhas {
+        writeln!(console, "{}", linha(valores))?;
+    }
+    writeln!(console, "{}", borda("└", "┴", "┘"))?;
+    if previa.total > linhas.len() {
+        writeln!(console, "{}", tr!("Exibindo {} de {} linhas.", linhas.len(), previa.total).bright_cyan())?;
+    }
+    Ok(())
+}
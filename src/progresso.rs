@@ -0,0 +1,178 @@
+use std::fs;
+use std::io::{self, stderr, Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Tempo sem barra no início do lote, para que conversões rápidas terminem sem exibi-la.
+const ESPERA_INICIAL: Duration = Duration::from_millis(300);
+
+/// Intervalo mínimo entre dois desenhos da barra.
+const INTERVALO: Duration = Duration::from_millis(100);
+
+/// Largura, em caracteres, das barras.
+const LARGURA_BARRA: usize = 20;
+
+/// Tamanho máximo do nome do arquivo exibido ao lado da barra.
+const LARGURA_NOME: usize = 30;
+
+/// Barra de progresso do modo em lote, desenhada em uma única linha da saída de erro: o
+/// total do lote, pelos bytes já lidos de todos os XML, e, sem `--jobs`, o arquivo atual.
+///
+/// Inativa, nada é desenhado; assim o lote não precisa tratar os dois casos.
+pub struct Progresso {
+    ativo: bool,
+    /// Com `--jobs`, vários arquivos são lidos ao mesmo tempo e só o total é exibido.
+    paralelo: bool,
+    inicio: Instant,
+    estado: Mutex<Estado>,
+}
+
+struct Estado {
+    nomes: Vec<String>,
+    tamanhos: Vec<u64>,
+    lidos: Vec<u64>,
+    concluidos: usize,
+    /// Arquivo em conversão, sem `--jobs`.
+    atual: Option<usize>,
+    /// Momento do último desenho; `None` quando a linha está limpa.
+    desenhado: Option<Instant>,
+}
+
+impl Progresso {
+    pub fn new(arquivos: &[PathBuf], ativo: bool, paralelo: bool) -> Progresso {
+        let estado = Estado {
+            nomes: arquivos.iter().map(|arquivo| arquivo.file_name().unwrap_or(arquivo.as_os_str()).to_string_lossy().into_owned()).collect(),
+            tamanhos: arquivos.iter().map(|arquivo| fs::metadata(arquivo).map_or(0, |dados| dados.len())).collect(),
+            lidos: vec![0; arquivos.len()],
+            concluidos: 0,
+            atual: None,
+            desenhado: None,
+        };
+        Progresso { ativo, paralelo, inicio: Instant::now(), estado: Mutex::new(estado) }
+    }
+
+    /// Marca o início da conversão do arquivo de posição `indice` na lista do lote.
+    pub fn arquivo(&self, indice: usize) -> ProgressoArquivo<'_> {
+        self.alterar(|estado| estado.atual = Some(indice));
+        ProgressoArquivo { progresso: self, indice }
+    }
+
+    /// Marca o arquivo como concluído, com ou sem erro.
+    pub fn concluir(&self, indice: usize) {
+        self.alterar(|estado| {
+            estado.lidos[indice] = estado.tamanhos[indice];
+            estado.concluidos += 1;
+            if estado.atual == Some(indice) {
+                estado.atual = None;
+            }
+        });
+    }
+
+    /// Apaga a barra, para que as mensagens do lote não se misturem a ela; a barra volta
+    /// no próximo avanço.
+    pub fn limpar(&self) {
+        if !self.ativo {
+            return;
+        }
+        let mut estado = self.estado.lock().unwrap_or_else(|erro| erro.into_inner());
+        if estado.desenhado.take().is_some() {
+            let _ = write!(stderr(), "\r\x1b[2K");
+            let _ = stderr().flush();
+        }
+    }
+
+    fn alterar(&self, alteracao: impl FnOnce(&mut Estado)) {
+        if !self.ativo {
+            return;
+        }
+        let mut estado = self.estado.lock().unwrap_or_else(|erro| erro.into_inner());
+        alteracao(&mut estado);
+
+        let agora = Instant::now();
+        let cedo = agora.duration_since(self.inicio) < ESPERA_INICIAL;
+        let recente = estado.desenhado.is_some_and(|desenhado| agora.duration_since(desenhado) < INTERVALO);
+        if cedo || recente {
+            return;
+        }
+        let linha = self.linha(&estado);
+        let _ = write!(stderr(), "\r\x1b[2K{}", linha);
+        let _ = stderr().flush();
+        estado.desenhado = Some(agora);
+    }
+
+    fn linha(&self, estado: &Estado) -> String {
+        let total: u64 = estado.tamanhos.iter().sum();
+        let lidos: u64 = estado.lidos.iter().sum();
+        let fracao = if total > 0 {
+            lidos as f64 / total as f64
+        } else {
+            estado.concluidos as f64 / estado.nomes.len().max(1) as f64
+        };
+        let mut linha = tr!("Lote {} {}% · {}/{} arquivo(s)", barra(fracao), format!("{:>3}", (fracao * 100.0) as u32), estado.concluidos, estado.nomes.len());
+
+        if let Some(indice) = estado.atual.filter(|_| !self.paralelo) {
+            let tamanho = estado.tamanhos[indice];
+            let fracao = if tamanho > 0 { estado.lidos[indice] as f64 / tamanho as f64 } else { 0.0 };
+            linha.push_str(&tr!(
+                " · {} {} {}% ({} de {})",
+                cortar(&estado.nomes[indice]),
+                barra(fracao),
+                format!("{:>3}", (fracao * 100.0) as u32),
+                megabytes(estado.lidos[indice]),
+                megabytes(tamanho)
+            ));
+        }
+        linha
+    }
+}
+
+/// Progresso de um arquivo do lote; atualizado por [`ProgressoArquivo::ler`].
+#[derive(Clone, Copy)]
+pub struct ProgressoArquivo<'a> {
+    progresso: &'a Progresso,
+    indice: usize,
+}
+
+impl<'a> ProgressoArquivo<'a> {
+    /// Envolve a leitura do XML, avançando a barra conforme os bytes são lidos.
+    pub fn ler<R: Read>(self, interno: R) -> LeituraContada<'a, R> {
+        LeituraContada { interno, progresso: self, posicao: 0 }
+    }
+}
+
+/// Leitor que informa ao [`Progresso`] quantos bytes do XML já foram lidos.
+pub struct LeituraContada<'a, R: Read> {
+    interno: R,
+    progresso: ProgressoArquivo<'a>,
+    posicao: u64,
+}
+
+impl<R: Read> Read for LeituraContada<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let lidos = self.interno.read(buf)?;
+        self.posicao += lidos as u64;
+        let ProgressoArquivo { progresso, indice } = self.progresso;
+        let posicao = self.posicao;
+        progresso.alterar(|estado| estado.lidos[indice] = posicao.min(estado.tamanhos[indice]));
+        Ok(lidos)
+    }
+}
+
+fn barra(fracao: f64) -> String {
+    let cheios = ((fracao.clamp(0.0, 1.0) * LARGURA_BARRA as f64).round() as usize).min(LARGURA_BARRA);
+    format!("[{}{}]", "█".repeat(cheios), "░".repeat(LARGURA_BARRA - cheios))
+}
+
+fn cortar(nome: &str) -> String {
+    if nome.chars().count() <= LARGURA_NOME {
+        return nome.to_string();
+    }
+    let mut cortado: String = nome.chars().take(LARGURA_NOME - 1).collect();
+    cortado.push('…');
+    cortado
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1_000_000.0).replace('.', ",")
+}
@@ -0,0 +1,95 @@
+use chrono::Local;
+use colored::*;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{stderr, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Registro da execução: com `-v`/`-vv`/`-vvv` na saída de erro e, com `--log-file`, em um
+/// arquivo, que recebe ao menos o nível `info` (arquivos processados, linhas, tempos e
+/// avisos da conversão).
+///
+/// Só os registros do conversor são gravados; os das dependências são ignorados.
+struct Registro {
+    console: LevelFilter,
+    arquivo: Option<(Mutex<File>, LevelFilter)>,
+}
+
+impl Registro {
+    fn nivel(&self) -> LevelFilter {
+        let arquivo = self.arquivo.as_ref().map_or(LevelFilter::Off, |(_, nivel)| *nivel);
+        self.console.max(arquivo)
+    }
+}
+
+impl Log for Registro {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with(env!("CARGO_CRATE_NAME")) && metadata.level() <= self.nivel()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if record.level() <= self.console {
+            let nivel = format!("{:<5}", record.level());
+            let nivel = match record.level() {
+                Level::Error => nivel.bright_red(),
+                Level::Warn => nivel.bright_yellow(),
+                Level::Info => nivel.bright_cyan(),
+                Level::Debug | Level::Trace => nivel.dimmed(),
+            };
+            eprintln!("{} {}", nivel, record.args());
+        }
+        if let Some((arquivo, nivel)) = &self.arquivo {
+            if record.level() <= *nivel {
+                let mut arquivo = arquivo.lock().unwrap_or_else(|erro| erro.into_inner());
+                let _ = writeln!(arquivo, "{} {:<5} {}", Local::now().format("%Y-%m-%d %H:%M:%S%.3f"), record.level(), record.args());
+            }
+        }
+    }
+
+    fn flush(&self) {
+        let _ = stderr().flush();
+        if let Some((arquivo, _)) = &self.arquivo {
+            let _ = arquivo.lock().unwrap_or_else(|erro| erro.into_inner()).flush();
+        }
+    }
+}
+
+/// Nível da saída de erro para a quantidade de `-v`.
+pub fn nivel_console(verbosidade: u8) -> LevelFilter {
+    match verbosidade {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Instala o registro da execução; o arquivo de `--log-file` recebe os registros ao final,
+/// sem apagar os de execuções anteriores.
+pub fn iniciar(verbosidade: u8, arquivo: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let console = nivel_console(verbosidade);
+    let arquivo = match arquivo {
+        Some(caminho) => {
+            let arquivo = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(caminho)
+                .map_err(|erro| tr!("Não foi possível abrir o arquivo de log '{}': {}", caminho.display(), erro))?;
+            Some((Mutex::new(arquivo), console.max(LevelFilter::Info)))
+        }
+        None => None,
+    };
+
+    let registro = Registro { console, arquivo };
+    let nivel = registro.nivel();
+    // O registro só pode ser instalado uma vez por processo; as opções são lidas uma única vez.
+    if log::set_boxed_logger(Box::new(registro)).is_ok() {
+        log::set_max_level(nivel);
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use colored::*;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use converterxmlcsv::saida::novo_writer;
+use converterxmlcsv::{FormatoCsv, Quantia, Resumo, Tipo};
+
+/// Arquivo do relatório consolidado quando `--summary` é usado sem um nome.
+pub const ARQUIVO_RELATORIO: &str = "summary.csv";
+
+const CABECALHO: [&str; 10] = ["Arquivo", "Tipo", "Empresa", "Funcionarios", "TotalComissao", "TotalMeta", "TotalVales", "TotalFerias", "TotalDecimoTerceiro", "ParseWarnings"];
+
+/// Totais de um XML convertido, uma linha do relatório consolidado do lote.
+#[derive(Debug)]
+pub struct LinhaRelatorio {
+    pub arquivo: PathBuf,
+    pub tipo: Option<Tipo>,
+    pub empresa: String,
+    /// Competência do XML, usada no assunto de `--email-to` e no resumo de `--pdf`; não faz
+    /// parte do relatório.
+    pub mes_ano: String,
+    pub funcionarios: usize,
+    pub total_comissao: Quantia,
+    pub total_meta: Quantia,
+    pub total_vales: Quantia,
+    pub total_ferias: Quantia,
+    pub total_decimo_terceiro: Quantia,
+    /// Valores que não são números, somados como zero nos totais.
+    pub valores_invalidos: usize,
+}
+
+impl LinhaRelatorio {
+    pub fn new(arquivo: &Path, resumo: &Resumo) -> LinhaRelatorio {
+        let mut totais = [Quantia::ZERO; 4];
+        let indice = match resumo.tipo {
+            Tipo::Comissao => 0,
+            Tipo::Vales => 1,
+            Tipo::Ferias => 2,
+            Tipo::DecimoTerceiro => 3,
+        };
+        totais[indice] = resumo.total_valor;
+        let [total_comissao, total_vales, total_ferias, total_decimo_terceiro] = totais;
+        LinhaRelatorio {
+            arquivo: arquivo.to_path_buf(),
+            tipo: Some(resumo.tipo),
+            empresa: resumo.razao.clone(),
+            mes_ano: resumo.mes_ano.clone(),
+            funcionarios: resumo.funcionarios,
+            total_comissao,
+            total_meta: resumo.total_meta,
+            total_vales,
+            total_ferias,
+            total_decimo_terceiro,
+            valores_invalidos: resumo.valores_invalidos.len(),
+        }
+    }
+
+    fn campos(&self) -> [String; 10] {
+        [
+            self.arquivo.display().to_string(),
+            self.tipo.map(|tipo| tipo.nome().to_string()).unwrap_or_default(),
+            self.empresa.clone(),
+            self.funcionarios.to_string(),
+            format!("{:.2}", self.total_comissao),
+            format!("{:.2}", self.total_meta),
+            format!("{:.2}", self.total_vales),
+            format!("{:.2}", self.total_ferias),
+            format!("{:.2}", self.total_decimo_terceiro),
+            self.valores_invalidos.to_string(),
+        ]
+    }
+}
+
+/// Linha de totais gerais, somando todas as linhas do relatório.
+pub fn total_geral(linhas: &[LinhaRelatorio]) -> LinhaRelatorio {
+    LinhaRelatorio {
+        arquivo: PathBuf::from("Total"),
+        tipo: None,
+        empresa: String::new(),
+        mes_ano: String::new(),
+        funcionarios: linhas.iter().map(|linha| linha.funcionarios).sum(),
+        total_comissao: linhas.iter().map(|linha| linha.total_comissao).sum(),
+        total_meta: linhas.iter().map(|linha| linha.total_meta).sum(),
+        total_vales: linhas.iter().map(|linha| linha.total_vales).sum(),
+        total_ferias: linhas.iter().map(|linha| linha.total_ferias).sum(),
+        total_decimo_terceiro: linhas.iter().map(|linha| linha.total_decimo_terceiro).sum(),
+        valores_invalidos: linhas.iter().map(|linha| linha.valores_invalidos).sum(),
+    }
+}
+
+/// Exibe o relatório por arquivo, com a linha de totais gerais ao final.
+pub fn exibir_relatorio(console: &mut dyn Write, linhas: &[LinhaRelatorio]) -> io::Result<()> {
+    let total = total_geral(linhas);
+    let todas: Vec<[String; 10]> = linhas.iter().chain([&total]).map(LinhaRelatorio::campos).collect();
+
+    let larguras: Vec<usize> = (0..CABECALHO.len())
+        .map(|coluna| todas.iter().map(|campos| campos[coluna].chars().count()).chain([CABECALHO[coluna].len()]).max().unwrap_or(0))
+        .collect();
+    // Texto alinhado à esquerda e números à direita.
+    let formatar = |campos: &[&str]| -> String {
+        campos
+            .iter()
+            .zip(&larguras)
+            .enumerate()
+            .map(|(coluna, (campo, largura))| if coluna < 3 { format!("{:<largura$}", campo) } else { format!("{:>largura$}", campo) })
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    writeln!(console, "{}", tr!("==== Relatório do lote ====").bright_cyan())?;
+    writeln!(console, "{}", formatar(&CABECALHO).bold())?;
+    for (indice, campos) in todas.iter().enumerate() {
+        let linha = formatar(&campos.iter().map(String::as_str).collect::<Vec<_>>());
+        if indice == linhas.len() {
+            writeln!(console, "{}", linha.bold())?;
+        } else {
+            writeln!(console, "{}", linha)?;
+        }
+    }
+    Ok(())
+}
+
+/// Grava o relatório em CSV, com o mesmo delimitador e estilo de aspas das conversões.
+pub fn gravar_relatorio(caminho: &Path, linhas: &[LinhaRelatorio], formato: &FormatoCsv, console: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    let mut writer = novo_writer(File::create(caminho)?, formato);
+    writer.write_record(CABECALHO)?;
+    for linha in linhas.iter().chain([&total_geral(linhas)]) {
+        writer.write_record(linha.campos())?;
+    }
+    writer.flush()?;
+    writeln!(console, "{}", tr!("Relatório do lote gravado em {}.", caminho.display()).bright_cyan())?;
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use converterxmlcsv::avisos::Avisos;
+use converterxmlcsv::Quantia;
+
+use crate::relatorio::{total_geral, LinhaRelatorio};
+use crate::{ArquivoGerado, ENTRADA_PADRAO};
+
+/// Formatos aceitos por `--report`.
+pub const FORMATOS: [&str; 1] = ["json"];
+
+/// Resultado do lote em JSON (`--report json`), para ser lido por outros programas em vez
+/// das mensagens coloridas.
+#[derive(Debug, Serialize)]
+pub struct Resultado<'a> {
+    inicio: String,
+    duracao_segundos: f64,
+    convertidos: usize,
+    falhas: usize,
+    /// XML já convertidos e sem alteração, ignorados em `--all`, `watch` e `schedule`.
+    ignorados: usize,
+    entradas: &'a [EntradaLote],
+    saidas: &'a [ArquivoGerado],
+    totais: Totais,
+}
+
+/// Um XML do lote, convertido ou não.
+#[derive(Debug, Serialize)]
+pub struct EntradaLote {
+    arquivo: PathBuf,
+    sucesso: bool,
+    /// Já convertido e sem alteração, por isso não foi convertido de novo.
+    ignorado: bool,
+    /// Tipo do XML; ausente quando a conversão falhou ou foi ignorada.
+    tipo: Option<String>,
+    erro: Option<String>,
+    avisos: Vec<AvisoEntrada>,
+}
+
+#[derive(Debug, Serialize)]
+struct AvisoEntrada {
+    categoria: &'static str,
+    mensagem: String,
+}
+
+/// Totais gerais do lote, os mesmos da última linha do relatório de `--summary`.
+#[derive(Debug, Serialize)]
+struct Totais {
+    funcionarios: usize,
+    total_comissao: Quantia,
+    total_meta: Quantia,
+    total_vales: Quantia,
+    total_ferias: Quantia,
+    total_decimo_terceiro: Quantia,
+}
+
+impl EntradaLote {
+    pub fn convertida(arquivo: &Path, tipo: String, avisos: Option<&Avisos>) -> EntradaLote {
+        let avisos = avisos
+            .into_iter()
+            .flat_map(Avisos::iter)
+            .map(|aviso| AvisoEntrada { categoria: aviso.categoria, mensagem: aviso.mensagem.clone() })
+            .collect();
+        EntradaLote { arquivo: arquivo.to_path_buf(), sucesso: true, ignorado: false, tipo: Some(tipo), erro: None, avisos }
+    }
+
+    pub fn com_erro(arquivo: &Path, erro: &str) -> EntradaLote {
+        EntradaLote { arquivo: arquivo.to_path_buf(), sucesso: false, ignorado: false, tipo: None, erro: Some(erro.to_string()), avisos: Vec::new() }
+    }
+
+    pub fn ignorada(arquivo: &Path) -> EntradaLote {
+        EntradaLote { arquivo: arquivo.to_path_buf(), sucesso: true, ignorado: true, tipo: None, erro: None, avisos: Vec::new() }
+    }
+}
+
+impl<'a> Resultado<'a> {
+    pub fn new(inicio: DateTime<Local>, duracao: Duration, entradas: &'a [EntradaLote], saidas: &'a [ArquivoGerado], relatorio: &[LinhaRelatorio]) -> Resultado<'a> {
+        let total = total_geral(relatorio);
+        let falhas = entradas.iter().filter(|entrada| !entrada.sucesso).count();
+        let ignorados = entradas.iter().filter(|entrada| entrada.ignorado).count();
+        Resultado {
+            inicio: inicio.to_rfc3339(),
+            duracao_segundos: duracao.as_secs_f64(),
+            convertidos: entradas.len() - falhas - ignorados,
+            falhas,
+            ignorados,
+            entradas,
+            saidas,
+            totais: Totais {
+                funcionarios: total.funcionarios,
+                total_comissao: total.total_comissao,
+                total_meta: total.total_meta,
+                total_vales: total.total_vales,
+                total_ferias: total.total_ferias,
+                total_decimo_terceiro: total.total_decimo_terceiro,
+            },
+        }
+    }
+
+    /// Grava o resultado em `destino`; com `-`, numa única linha da saída padrão, para que
+    /// cada lote de `watch` e `schedule` seja um JSON por linha.
+    pub fn gravar(&self, destino: &Path) -> Result<(), Box<dyn Error>> {
+        if destino == Path::new(ENTRADA_PADRAO) {
+            let mut saida = io::stdout().lock();
+            serde_json::to_writer(&mut saida, self)?;
+            writeln!(saida)?;
+        } else {
+            serde_json::to_writer_pretty(File::create(destino)?, self)?;
+        }
+        Ok(())
+    }
+}
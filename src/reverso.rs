@@ -0,0 +1,147 @@
+use std::io::{Read, Write};
+
+use crate::erro::ErroConversor;
+use crate::modelo::{Tipo, MOEDA_PADRAO};
+use crate::tipos;
+
+/// Colunas que o CSV precisa ter para que o XML seja reconstruído.
+const COLUNAS_OBRIGATORIAS: [&str; 6] = ["Fantasia", "Razao", "CNPJ", "MesAno", "CPF", "Valor"];
+
+/// Uma linha do CSV, com as colunas localizadas pelo nome no cabeçalho.
+struct Linha<'a> {
+    cabecalho: &'a [String],
+    registro: &'a csv::StringRecord,
+}
+
+impl Linha<'_> {
+    fn campo(&self, coluna: &str) -> Option<&str> {
+        let indice = self.cabecalho.iter().position(|nome| nome == coluna)?;
+        self.registro.get(indice)
+    }
+
+    /// Valor de `coluna`, trocado pelo da coluna `*Original` quando o CSV a possui.
+    ///
+    /// Assim o XML recebe os textos completos, e não os truncados pelo `--truncate-*`.
+    fn campo_original(&self, coluna: &str, original: &str) -> &str {
+        self.campo(original)
+            .filter(|valor| !valor.is_empty())
+            .or_else(|| self.campo(coluna))
+            .unwrap_or("")
+    }
+}
+
+/// Converte um CSV gerado por este conversor de volta para o XML de origem.
+///
+/// As colunas são localizadas pelo nome, então a ordem pode ter sido alterada na planilha.
+/// Sem `tipo`, o CSV é tratado como comissões quando possui a coluna `MetaPremio`, como
+/// férias quando possui as colunas `DataInicio` e `DataFim` (ou uma delas), como décimo
+/// terceiro quando possui a coluna `Parcela` e, caso contrário, como vales.
+/// Todas as linhas devem ser da mesma empresa (mesmo `CNPJ`). Retorna a quantidade de
+/// funcionários gravados.
+pub fn csv_para_xml<R: Read, W: Write>(reader: R, mut writer: W, tipo: Option<Tipo>, delimitador: u8) -> Result<usize, ErroConversor> {
+    let mut reader = csv::ReaderBuilder::new().delimiter(delimitador).from_reader(reader);
+    let cabecalho: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+
+    let ausentes: Vec<&str> = COLUNAS_OBRIGATORIAS
+        .iter()
+        .filter(|coluna| !cabecalho.iter().any(|nome| nome == *coluna))
+        .copied()
+        .collect();
+    if !ausentes.is_empty() {
+        return Err(ErroConversor::EntradaInvalida(tr!("O CSV não possui as colunas {}. Verifique o delimitador (--delimiter) e o cabeçalho.", ausentes.join(", "))));
+    }
+
+    let registros = reader.records().collect::<Result<Vec<_>, _>>()?;
+    let linhas: Vec<Linha> = registros.iter().map(|registro| Linha { cabecalho: &cabecalho, registro }).collect();
+    let Some(primeira) = linhas.first() else {
+        return Err(ErroConversor::EntradaInvalida(tr!("O CSV não possui funcionários.").to_string()));
+    };
+
+    let cnpj = primeira.campo("CNPJ").unwrap_or("");
+    if let Some(outra) = linhas.iter().find(|linha| linha.campo("CNPJ") != Some(cnpj)) {
+        return Err(ErroConversor::EntradaInvalida(tr!("O CSV possui mais de uma empresa (CNPJ {} e {}); separe-as antes de gerar o XML.", cnpj, outra.campo("CNPJ").unwrap_or(""))));
+    }
+
+    let possui = |coluna: &str| cabecalho.iter().any(|nome| nome == coluna);
+    // O tipo é o do leitor com mais colunas próprias no CSV (MetaPremio, DataInicio e
+    // DataFim, Parcela), em qualquer posição, preferindo o que tem todas elas; sem nenhuma
+    // delas, vales.
+    let tipo = tipo.unwrap_or_else(|| {
+        tipos::LEITORES
+            .iter()
+            .map(|leitor| {
+                let proprias: Vec<&str> = leitor.colunas_antes().iter().chain(leitor.colunas_depois()).copied().collect();
+                let presentes = proprias.iter().filter(|coluna| possui(coluna)).count();
+                (leitor, presentes, presentes == proprias.len())
+            })
+            .filter(|(_, presentes, _)| *presentes > 0)
+            .max_by_key(|(_, presentes, todas)| (*presentes, *todas))
+            .map_or(Tipo::Vales, |(leitor, _, _)| leitor.tipo())
+    });
+    let raiz = tipos::leitor(tipo).raiz();
+    let mes_ano = primeira.campo("MesAno").unwrap_or("");
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, "<{}>", raiz)?;
+    writeln!(writer, "  <Empresa>")?;
+    writeln!(writer, "    <Fantasia>{}</Fantasia>", escapar(primeira.campo_original("Fantasia", "FantasiaOriginal")))?;
+    writeln!(writer, "    <Razao>{}</Razao>", escapar(primeira.campo_original("Razao", "RazaoOriginal")))?;
+    writeln!(writer, "    <CNPJ>{}</CNPJ>", escapar(cnpj))?;
+    writeln!(writer, "    <MesAno>{}</MesAno>", escapar(mes_ano))?;
+
+    for linha in &linhas {
+        writeln!(writer, "    <Funcionario>")?;
+        writeln!(writer, "      <CPF>{}</CPF>", escapar(linha.campo("CPF").unwrap_or("")))?;
+        if tipo == Tipo::Ferias {
+            for coluna in ["DataInicio", "DataFim"] {
+                if let Some(data) = linha.campo(coluna).filter(|data| !data.is_empty()) {
+                    writeln!(writer, "      <{}>{}</{}>", coluna, escapar(data), coluna)?;
+                }
+            }
+        }
+        if tipo == Tipo::DecimoTerceiro {
+            if let Some(parcela) = linha.campo("Parcela").filter(|parcela| !parcela.is_empty()) {
+                writeln!(writer, "      <Parcela>{}</Parcela>", escapar(parcela))?;
+            }
+        }
+
+        let valor = escapar(linha.campo_original("Valor", "ValorOriginal"));
+        match linha.campo("Moeda").filter(|moeda| !moeda.is_empty() && !moeda.eq_ignore_ascii_case(MOEDA_PADRAO)) {
+            Some(moeda) => writeln!(writer, r#"      <Valor moeda="{}">{}</Valor>"#, escapar(moeda), valor)?,
+            None => writeln!(writer, "      <Valor>{}</Valor>", valor)?,
+        }
+
+        if tipo == Tipo::Comissao {
+            if let Some(meta_premio) = linha.campo("MetaPremio").filter(|meta| !meta.is_empty()) {
+                writeln!(writer, "      <MetaPremio>{}</MetaPremio>", escapar(meta_premio))?;
+            }
+        }
+
+        // A competência só é repetida no funcionário quando difere da empresa.
+        if let Some(mes_ano_funcionario) = linha.campo("MesAno").filter(|mes| *mes != mes_ano) {
+            writeln!(writer, "      <MesAno>{}</MesAno>", escapar(mes_ano_funcionario))?;
+        }
+        writeln!(writer, "    </Funcionario>")?;
+    }
+
+    writeln!(writer, "  </Empresa>")?;
+    writeln!(writer, "</{}>", raiz)?;
+    writer.flush()?;
+    Ok(linhas.len())
+}
+
+/// Escapa os caracteres reservados do XML.
+fn escapar(texto: &str) -> String {
+    let mut escapado = String::with_capacity(texto.len());
+    for c in texto.chars() {
+        match c {
+            '&' => escapado.push_str("&amp;"),
+            '<' => escapado.push_str("&lt;"),
+            '>' => escapado.push_str("&gt;"),
+            '"' => escapado.push_str("&quot;"),
+            '\'' => escapado.push_str("&apos;"),
+            _ => escapado.push(c),
+        }
+    }
+    escapado
+}
@@ -0,0 +1,117 @@
+use glob::Pattern;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+use crate::{ErroSaida, PREFIXO_S3 as PREFIXO, SAIDA_SEM_ARQUIVOS};
+
+/// Cliente da AWS usado para listar, baixar e enviar os arquivos do S3, com as credenciais
+/// e a região já configuradas nele (`aws configure` ou as variáveis `AWS_*`).
+const PROGRAMA: &str = "aws";
+
+/// Baixa para `pasta` os XML de `endereco`: um objeto (`s3://bucket/pasta/arquivo.xml`) ou
+/// os que atendem ao padrão do último trecho (`s3://bucket/pasta/*.xml`).
+pub fn baixar(endereco: &str, pasta: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let (prefixo, nome) = endereco.rsplit_once('/').filter(|(prefixo, _)| prefixo.len() > PREFIXO.len()).ok_or_else(|| tr!("Endereço do S3 inválido: {}", endereco))?;
+    let nomes = if nome.contains(['*', '?', '[']) {
+        let padrao = Pattern::new(nome).map_err(|erro| tr!("Padrão inválido em {}: {}", endereco, erro))?;
+        let listagem = executar(&["s3", "ls", &format!("{}/", prefixo)])?;
+        let nomes: Vec<String> = listagem.lines().filter_map(nome_listado).filter(|nome| padrao.matches(nome)).map(str::to_string).collect();
+        if nomes.is_empty() {
+            return Err(ErroSaida::criar(SAIDA_SEM_ARQUIVOS, tr!("Nenhum arquivo de {} atende a {}.", prefixo, nome)));
+        }
+        nomes
+    } else {
+        vec![nome.to_string()]
+    };
+
+    let mut baixados = Vec::new();
+    for nome in nomes {
+        let local = pasta.join(&nome);
+        executar(&["s3", "cp", "--only-show-errors", &format!("{}/{}", prefixo, nome), &local.to_string_lossy()])?;
+        log::info!("{}/{} baixado em {}", prefixo, nome, local.display());
+        baixados.push(local);
+    }
+    Ok(baixados)
+}
+
+/// Troca os endereços `s3://` de `entradas` pelos XML baixados numa pasta temporária,
+/// apagada quando o [`TempDir`] devolvido é descartado.
+pub fn baixar_entradas(entradas: Vec<String>) -> Result<(Vec<String>, Option<TempDir>), Box<dyn Error>> {
+    if !entradas.iter().any(|entrada| entrada.starts_with(PREFIXO)) {
+        return Ok((entradas, None));
+    }
+    let pasta = tempfile::Builder::new().prefix("converterxmlcsv-").tempdir()?;
+    let mut locais = Vec::new();
+    for (indice, entrada) in entradas.into_iter().enumerate() {
+        if !entrada.starts_with(PREFIXO) {
+            locais.push(entrada);
+            continue;
+        }
+        // Uma subpasta por endereço, para que arquivos de mesmo nome não se sobrescrevam.
+        let destino = pasta.path().join(indice.to_string());
+        fs::create_dir_all(&destino)?;
+        locais.extend(baixar(&entrada, &destino)?.iter().map(|arquivo| arquivo.display().to_string()));
+    }
+    Ok((locais, Some(pasta)))
+}
+
+/// Pasta temporária onde os arquivos de `--out-dir s3://` são gravados antes do envio; quem
+/// a usa deve apagá-la.
+pub fn pasta_temporaria() -> io::Result<PathBuf> {
+    Ok(tempfile::Builder::new().prefix("converterxmlcsv-").tempdir()?.into_path())
+}
+
+/// Envia cada arquivo para a pasta `destino` do S3, com o mesmo nome.
+pub fn enviar(arquivos: &[&Path], destino: &str) -> Result<(), Box<dyn Error>> {
+    let destino = destino.trim_end_matches('/');
+    for arquivo in arquivos {
+        let remoto = format!("{}/{}", destino, arquivo.file_name().unwrap_or_default().to_string_lossy());
+        executar(&["s3", "cp", "--only-show-errors", &arquivo.to_string_lossy(), &remoto])?;
+        log::info!("{} enviado para {}", arquivo.display(), remoto);
+    }
+    Ok(())
+}
+
+/// Nome do objeto numa linha de `aws s3 ls` (`2024-05-01 10:00:00  1234 arquivo.xml`);
+/// `None` para as subpastas (`PRE pasta/`).
+fn nome_listado(linha: &str) -> Option<&str> {
+    let mut resto = linha.trim_start();
+    // Data, hora e tamanho vêm antes do nome, que pode ter espaços.
+    for _ in 0..3 {
+        let (campo, depois) = resto.split_once(' ')?;
+        if campo == "PRE" {
+            return None;
+        }
+        resto = depois.trim_start();
+    }
+    Some(resto).filter(|nome| !nome.is_empty())
+}
+
+fn executar(argumentos: &[&str]) -> Result<String, Box<dyn Error>> {
+    let saida = Command::new(PROGRAMA)
+        .args(argumentos)
+        .output()
+        .map_err(|erro| tr!("Não foi possível executar o {}, necessário para os endereços s3://: {}. Instale e configure o AWS CLI.", PROGRAMA, erro))?;
+    if !saida.status.success() {
+        return Err(tr!("{} {} falhou ({}): {}", PROGRAMA, argumentos.join(" "), saida.status, String::from_utf8_lossy(&saida.stderr).trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&saida.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nomes_de_aws_s3_ls() {
+        assert_eq!(nome_listado("2024-05-01 10:00:00       1234 comissao.xml"), Some("comissao.xml"));
+        assert_eq!(nome_listado("2024-05-01 10:00:00       1234 folha de maio.xml"), Some("folha de maio.xml"));
+        assert_eq!(nome_listado("                           PRE antigos/"), None);
+        assert_eq!(nome_listado("2024-05-01 10:00:00       1234 "), None);
+        assert_eq!(nome_listado(""), None);
+    }
+}
@@ -0,0 +1,732 @@
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::codificacao::{Codificacao, Codificado};
+use crate::erro::ErroConversor;
+use crate::opcoes::Opcoes;
+use crate::valor::{parse_valor, Decimal, FormatoMonetario, Quantia, COLUNAS_NUMERICAS};
+
+/// Destino das linhas produzidas pela conversão.
+///
+/// Cada formato de saída (CSV, JSON, XLSX, SQL, HTML) é uma implementação; a leitura dos
+/// XML só conhece esta interface, e outros formatos podem ser gravados implementando-a fora
+/// da biblioteca. Essas implementações retornam os seus próprios erros em
+/// [`ErroConversor::Destino`].
+pub trait Destino {
+    /// Chamado uma única vez, antes da primeira linha. Empresas sem funcionários não o chamam.
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor>;
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor>;
+
+    /// Chamado por quem criou o destino depois da conversão, para gravar o que depende de
+    /// todas as linhas (totais, o documento JSON, a planilha) e descarregar o escritor. As
+    /// funções de conversão não o chamam, pois um destino pode receber vários XML; chamá-lo
+    /// de novo não grava nada.
+    fn finalizar(&mut self) -> Result<(), ErroConversor> {
+        Ok(())
+    }
+}
+
+/// Quando os campos do CSV são colocados entre aspas.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Aspas {
+    /// Apenas quando o campo contém o delimitador, aspas ou quebra de linha.
+    #[default]
+    Necessarias,
+    Sempre,
+    /// Todos os campos que não são números.
+    NaoNumericos,
+    Nunca,
+}
+
+impl Aspas {
+    /// Nomes aceitos por `--quote-style`.
+    pub const NOMES: [&'static str; 4] = ["necessary", "always", "non-numeric", "never"];
+
+    pub fn from_nome(nome: &str) -> Option<Aspas> {
+        match nome.to_lowercase().as_str() {
+            "necessary" => Some(Aspas::Necessarias),
+            "always" => Some(Aspas::Sempre),
+            "non-numeric" => Some(Aspas::NaoNumericos),
+            "never" => Some(Aspas::Nunca),
+            _ => None,
+        }
+    }
+
+    fn quote_style(self) -> csv::QuoteStyle {
+        match self {
+            Aspas::Necessarias => csv::QuoteStyle::Necessary,
+            Aspas::Sempre => csv::QuoteStyle::Always,
+            Aspas::NaoNumericos => csv::QuoteStyle::NonNumeric,
+            Aspas::Nunca => csv::QuoteStyle::Never,
+        }
+    }
+}
+
+/// Formato dos CSVs gerados: delimitador, aspas e terminador de linha.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormatoCsv {
+    pub delimitador: u8,
+    pub aspas: Aspas,
+    /// Termina as linhas com `\r\n` em vez de `\n`.
+    pub crlf: bool,
+    pub codificacao: Codificacao,
+}
+
+impl Default for FormatoCsv {
+    fn default() -> FormatoCsv {
+        FormatoCsv {
+            delimitador: b';',
+            aspas: Aspas::default(),
+            crlf: false,
+            codificacao: Codificacao::default(),
+        }
+    }
+}
+
+impl FormatoCsv {
+    /// Interpreta o delimitador de `--delimiter`: um único caractere ASCII, ou `tab`/`\t`.
+    pub fn parse_delimitador(texto: &str) -> Result<u8, String> {
+        match texto {
+            "tab" | "\\t" | "\t" => Ok(b'\t'),
+            _ if texto.len() == 1 && texto.is_ascii() && texto != "\"" => Ok(texto.as_bytes()[0]),
+            _ => Err(tr!("Delimitador inválido '{}'. Use um único caractere ASCII, ex.: ; , | ou tab.", texto)),
+        }
+    }
+}
+
+/// Linha de cabeçalho dos CSVs gerados, escolhida por `--no-header` e `--header-names`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Cabecalho {
+    /// Os nomes das colunas da conversão.
+    #[default]
+    Colunas,
+    /// Sem linha de cabeçalho.
+    Nenhum,
+    /// Um nome para cada coluna, na ordem das colunas gravadas.
+    Nomes(Vec<String>),
+}
+
+impl Cabecalho {
+    /// Linha gravada para as `colunas`, ou `None` sem cabeçalho; com [`Cabecalho::Nomes`],
+    /// falha quando a quantidade de nomes não é a de colunas.
+    pub fn linha<'a>(&'a self, colunas: &'a [String]) -> Result<Option<Vec<&'a str>>, ErroConversor> {
+        match self {
+            Cabecalho::Colunas => Ok(Some(colunas.iter().map(String::as_str).collect())),
+            Cabecalho::Nenhum => Ok(None),
+            Cabecalho::Nomes(nomes) if nomes.len() == colunas.len() => Ok(Some(nomes.iter().map(String::as_str).collect())),
+            Cabecalho::Nomes(nomes) => Err(ErroConversor::EntradaInvalida(tr!(
+                "--header-names informa {} nome(s), mas o arquivo gerado possui {} coluna(s): {}.",
+                nomes.len(),
+                colunas.len(),
+                colunas.join(", ")
+            ))),
+        }
+    }
+}
+
+/// Grava o CSV num único escritor, como `csv::Writer`, mas com a linha de cabeçalho de
+/// [`Cabecalho`].
+pub struct EscritorCsv<W: Write> {
+    writer: csv::Writer<W>,
+    cabecalho: Cabecalho,
+}
+
+impl<W: Write> EscritorCsv<W> {
+    pub fn new(writer: csv::Writer<W>, cabecalho: Cabecalho) -> EscritorCsv<W> {
+        EscritorCsv { writer, cabecalho }
+    }
+
+    pub fn into_inner(self) -> csv::Writer<W> {
+        self.writer
+    }
+}
+
+impl<W: Write> Destino for EscritorCsv<W> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        let colunas: Vec<String> = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        if let Some(linha) = self.cabecalho.linha(&colunas)? {
+            self.writer.write_record(linha)?;
+        }
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        self.writer.gravar(registro)
+    }
+
+    fn finalizar(&mut self) -> Result<(), ErroConversor> {
+        Destino::finalizar(&mut self.writer)
+    }
+}
+
+/// Arquivo em que a saída é gravada antes de receber o nome definitivo (`arquivo.csv.tmp`),
+/// para que uma interrupção no meio da gravação nunca deixe um arquivo incompleto com o nome
+/// que outros sistemas importam.
+pub fn caminho_temporario(caminho: &Path) -> PathBuf {
+    let mut nome = caminho.as_os_str().to_owned();
+    nome.push(".tmp");
+    PathBuf::from(nome)
+}
+
+/// Arquivo que de fato recebe a gravação em `caminho`: com um link simbólico, o arquivo para
+/// o qual ele aponta, para que o link não seja substituído. `None` quando o destino existe e
+/// não é um arquivo comum (`/dev/null`, um FIFO), que deve ser gravado diretamente, sem
+/// [`caminho_temporario`].
+pub fn destino_real(caminho: &Path) -> io::Result<Option<PathBuf>> {
+    let mut atual = caminho.to_path_buf();
+    // O mesmo limite de links encadeados do Linux, contra links circulares.
+    for _ in 0..40 {
+        match fs::symlink_metadata(&atual) {
+            Ok(metadados) if metadados.file_type().is_symlink() => {
+                let alvo = fs::read_link(&atual)?;
+                atual = atual.parent().unwrap_or(Path::new("")).join(alvo);
+            }
+            Ok(metadados) => return Ok(metadados.is_file().then_some(atual)),
+            Err(erro) if erro.kind() == io::ErrorKind::NotFound => return Ok(Some(atual)),
+            Err(erro) => return Err(erro),
+        }
+    }
+    Err(io::Error::other(tr!("{}: links simbólicos demais", caminho.display())))
+}
+
+/// Grava `conteudo` em `caminho` passando por [`caminho_temporario`]; com erro, o arquivo
+/// temporário é apagado e um `caminho` anterior fica como estava. Links simbólicos e destinos
+/// que não são arquivos comuns são tratados como em [`destino_real`].
+pub fn gravar_atomico(caminho: &Path, conteudo: &[u8]) -> io::Result<()> {
+    let Some(caminho) = destino_real(caminho)? else {
+        return fs::write(caminho, conteudo);
+    };
+    let temporario = caminho_temporario(&caminho);
+    let resultado = fs::write(&temporario, conteudo).and_then(|()| fs::rename(&temporario, &caminho));
+    if resultado.is_err() {
+        let _ = fs::remove_file(&temporario);
+    }
+    resultado
+}
+
+/// Cria um escritor de CSV no formato informado, para um arquivo novo.
+pub fn novo_writer<W: Write>(writer: W, formato: &FormatoCsv) -> csv::Writer<Codificado<W>> {
+    criar_writer(Codificado::new(writer, formato.codificacao, true), formato)
+}
+
+/// Como [`novo_writer`], para acrescentar linhas a um CSV que já possui conteúdo: o BOM de
+/// `utf8-bom` não é gravado de novo.
+pub fn writer_continuacao<W: Write>(writer: W, formato: &FormatoCsv) -> csv::Writer<Codificado<W>> {
+    criar_writer(Codificado::new(writer, formato.codificacao, false), formato)
+}
+
+fn criar_writer<W: Write>(writer: Codificado<W>, formato: &FormatoCsv) -> csv::Writer<Codificado<W>> {
+    let terminador = if formato.crlf { csv::Terminator::CRLF } else { csv::Terminator::Any(b'\n') };
+    csv::WriterBuilder::new()
+        .delimiter(formato.delimitador)
+        .quote_style(formato.aspas.quote_style())
+        .terminator(terminador)
+        .from_writer(writer)
+}
+
+impl<W: Write> Destino for csv::Writer<W> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.write_record(cabecalho)?;
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        self.write_record(registro)?;
+        Ok(())
+    }
+
+    fn finalizar(&mut self) -> Result<(), ErroConversor> {
+        self.flush()?;
+        Ok(())
+    }
+}
+
+/// Grava as linhas em arquivos CSV, dividindo-as em partes de no máximo `max_linhas` linhas.
+///
+/// Cada parte é gravada em [`caminho_temporario`] e só recebe o nome final em
+/// [`Destino::finalizar`], para que uma interrupção não deixe um CSV pela metade.
+pub struct SaidaCsv {
+    caminho: PathBuf,
+    cabecalho: Vec<String>,
+    max_linhas: Option<usize>,
+    formato: FormatoCsv,
+    writer: Option<csv::Writer<Codificado<File>>>,
+    partes: Vec<(PathBuf, usize)>,
+    totais: Option<Totais>,
+    linha_cabecalho: Cabecalho,
+}
+
+/// Colunas somadas nas linhas de totais de [`SaidaCsv::com_totais`].
+const COLUNAS_SOMADAS: [&str; 2] = ["Valor", "MetaPremio"];
+
+/// Somas das linhas de totais da parte atual do CSV e da empresa atual.
+#[derive(Debug)]
+struct Totais {
+    decimal: Option<Decimal>,
+    formato_monetario: Option<FormatoMonetario>,
+    /// Posições de `CNPJ` e `CPF` no cabeçalho.
+    cnpj: Option<usize>,
+    cpf: Option<usize>,
+    parte: Soma,
+    empresa: Soma,
+    cnpj_atual: Option<String>,
+    /// Empresas já encerradas com um subtotal na parte atual.
+    subtotais: usize,
+}
+
+/// Quantidade de funcionários e soma de cada coluna de [`COLUNAS_SOMADAS`], indexada pela
+/// posição no cabeçalho; `None` nas colunas que não são somadas.
+#[derive(Debug, Default)]
+struct Soma {
+    funcionarios: usize,
+    valores: Vec<Option<Quantia>>,
+}
+
+impl Totais {
+    fn texto(&self, valor: Quantia) -> String {
+        match (self.formato_monetario, self.decimal) {
+            (Some(formato), _) => formato.formatar(valor),
+            (None, Some(Decimal::Virgula)) => format!("{:.2}", valor).replace('.', ","),
+            (None, _) => format!("{:.2}", valor),
+        }
+    }
+
+    /// Linha com `rotulo` na primeira coluna, o CNPJ da empresa (nos subtotais), a quantidade
+    /// de funcionários na coluna `CPF` e as somas.
+    fn linha(&self, largura: usize, rotulo: &str, cnpj: &str, soma: &Soma) -> Vec<String> {
+        let mut linha = vec![String::new(); largura];
+        for (indice, valor) in soma.valores.iter().enumerate() {
+            if let (Some(campo), Some(valor)) = (linha.get_mut(indice), valor) {
+                *campo = self.texto(*valor);
+            }
+        }
+        for (posicao, texto) in [(self.cnpj, cnpj.to_string()), (self.cpf, soma.funcionarios.to_string())] {
+            if let Some(campo) = posicao.and_then(|indice| linha.get_mut(indice)) {
+                *campo = texto;
+            }
+        }
+        if let Some(primeira) = linha.first_mut() {
+            *primeira = rotulo.to_string();
+        }
+        linha
+    }
+}
+
+impl Soma {
+    fn new(cabecalho: &[String]) -> Soma {
+        let valores = cabecalho.iter().map(|coluna| COLUNAS_SOMADAS.contains(&coluna.as_str()).then_some(Quantia::ZERO)).collect();
+        Soma { funcionarios: 0, valores }
+    }
+
+    fn somar(&mut self, registro: &[&str]) {
+        self.funcionarios += 1;
+        for (total, texto) in self.valores.iter_mut().zip(registro) {
+            if let Some(total) = total {
+                *total += Quantia::ler(texto, None).unwrap_or_default();
+            }
+        }
+    }
+}
+
+impl SaidaCsv {
+    /// A saída só é dividida quando a quantidade de linhas ultrapassa `max_linhas`;
+    /// caso contrário tudo é gravado em `caminho`.
+    pub fn new(caminho: PathBuf, max_linhas: Option<usize>, formato: FormatoCsv) -> SaidaCsv {
+        SaidaCsv {
+            caminho,
+            cabecalho: Vec::new(),
+            max_linhas,
+            formato,
+            writer: None,
+            partes: Vec::new(),
+            totais: None,
+            linha_cabecalho: Cabecalho::default(),
+        }
+    }
+
+    /// Troca ou omite a linha de cabeçalho de cada arquivo; as linhas de totais continuam
+    /// usando as colunas da conversão.
+    pub fn com_cabecalho(mut self, cabecalho: Cabecalho) -> SaidaCsv {
+        self.linha_cabecalho = cabecalho;
+        self
+    }
+
+    /// Acrescenta ao fim de cada arquivo uma linha em branco e a linha `Total`, com a soma
+    /// de `Valor` e `MetaPremio` e a quantidade de funcionários na coluna `CPF`; com mais de
+    /// uma empresa no arquivo, cada uma termina numa linha `Subtotal`. As somas são escritas
+    /// como os valores das linhas, conforme `formato_monetario` ou `decimal_saida`.
+    pub fn com_totais(mut self, opcoes: &Opcoes) -> SaidaCsv {
+        self.totais = Some(Totais {
+            decimal: opcoes.decimal_saida,
+            formato_monetario: opcoes.formato_monetario,
+            cnpj: None,
+            cpf: None,
+            parte: Soma::default(),
+            empresa: Soma::default(),
+            cnpj_atual: None,
+            subtotais: 0,
+        });
+        self
+    }
+
+    fn caminho_parte(&self, numero: usize) -> PathBuf {
+        let stem = self.caminho.file_stem().unwrap_or_default().to_string_lossy();
+        self.caminho.with_file_name(format!("{}_part{}.csv", stem, numero))
+    }
+
+    /// Abre o próximo arquivo. Como as linhas chegam em fluxo, a divisão só é conhecida
+    /// quando a primeira parte enche: nesse momento ela é renomeada para `_part1`.
+    fn abrir_parte(&mut self) -> Result<(), ErroConversor> {
+        self.gravar_totais()?;
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+
+        let caminho = match self.partes.len() {
+            0 => self.caminho.clone(),
+            1 => {
+                let primeira = self.caminho_parte(1);
+                fs::rename(caminho_temporario(&self.partes[0].0), caminho_temporario(&primeira))?;
+                self.partes[0].0 = primeira;
+                self.caminho_parte(2)
+            }
+            quantidade => self.caminho_parte(quantidade + 1),
+        };
+
+        let mut writer = novo_writer(File::create(caminho_temporario(&caminho))?, &self.formato);
+        if let Some(linha) = self.linha_cabecalho.linha(&self.cabecalho)? {
+            writer.write_record(linha)?;
+        }
+
+        self.writer = Some(writer);
+        self.partes.push((caminho, 0));
+        Ok(())
+    }
+
+    /// Conclui a gravação e retorna cada arquivo gerado com sua quantidade de linhas.
+    ///
+    /// Sem [`Destino::iniciar`] (empresa sem funcionários) nenhum arquivo é criado. Com erro,
+    /// os arquivos temporários são apagados como em [`SaidaCsv::descartar`].
+    pub fn finalizar(mut self) -> Result<Vec<(PathBuf, usize)>, ErroConversor> {
+        match Destino::finalizar(&mut self) {
+            Ok(()) => Ok(self.partes),
+            Err(erro) => {
+                self.descartar();
+                Err(erro)
+            }
+        }
+    }
+
+    /// Encerra a parte atual com o subtotal da última empresa, se houve mais de uma, e o total.
+    fn gravar_totais(&mut self) -> Result<(), ErroConversor> {
+        let Some(totais) = self.totais.as_mut() else {
+            return Ok(());
+        };
+        let Some(mut writer) = self.writer.take() else {
+            return Ok(());
+        };
+        let largura = self.cabecalho.len();
+        if totais.subtotais > 0 {
+            writer.write_record(totais.linha(largura, "Subtotal", totais.cnpj_atual.as_deref().unwrap_or_default(), &totais.empresa))?;
+        }
+        // O csv não grava uma linha sem campos; a linha em branco vai direto para o arquivo.
+        let mut arquivo = writer.into_inner().map_err(|erro| erro.into_error())?;
+        arquivo.write_all(if self.formato.crlf { b"\r\n" } else { b"\n" })?;
+        let mut writer = criar_writer(arquivo, &self.formato);
+        writer.write_record(totais.linha(largura, "Total", "", &totais.parte))?;
+        self.writer = Some(writer);
+
+        totais.parte = Soma::new(&self.cabecalho);
+        totais.empresa = Soma::new(&self.cabecalho);
+        totais.subtotais = 0;
+        totais.cnpj_atual = None;
+        Ok(())
+    }
+
+    /// Remove os arquivos temporários já gravados após um erro; um CSV anterior de mesmo nome
+    /// fica como estava.
+    pub fn descartar(mut self) {
+        self.writer.take();
+        for (caminho, _) in &self.partes {
+            let _ = fs::remove_file(caminho_temporario(caminho));
+        }
+    }
+}
+
+impl Destino for SaidaCsv {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.cabecalho = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        // Confere os nomes de --header-names antes de criar o arquivo.
+        self.linha_cabecalho.linha(&self.cabecalho)?;
+        if let Some(totais) = &mut self.totais {
+            totais.cnpj = cabecalho.iter().position(|coluna| *coluna == "CNPJ");
+            totais.cpf = cabecalho.iter().position(|coluna| *coluna == "CPF");
+            totais.parte = Soma::new(&self.cabecalho);
+            totais.empresa = Soma::new(&self.cabecalho);
+        }
+        self.abrir_parte()
+    }
+
+    /// Só aqui cada parte recebe o nome definitivo, substituindo o arquivo anterior de mesmo nome.
+    fn finalizar(&mut self) -> Result<(), ErroConversor> {
+        self.gravar_totais()?;
+        let Some(mut writer) = self.writer.take() else {
+            return Ok(());
+        };
+        writer.flush()?;
+        drop(writer);
+        for (caminho, _) in &self.partes {
+            fs::rename(caminho_temporario(caminho), caminho)?;
+        }
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        let parte_cheia = match (self.partes.last(), self.max_linhas) {
+            (Some((_, linhas)), Some(max)) => *linhas >= max,
+            _ => false,
+        };
+        if parte_cheia {
+            self.abrir_parte()?;
+        }
+
+        if let (Some(totais), Some(writer)) = (self.totais.as_mut(), self.writer.as_mut()) {
+            let cnpj = totais.cnpj.and_then(|indice| registro.get(indice)).copied().unwrap_or_default();
+            if let Some(anterior) = totais.cnpj_atual.as_deref().filter(|anterior| *anterior != cnpj) {
+                writer.write_record(totais.linha(self.cabecalho.len(), "Subtotal", anterior, &totais.empresa))?;
+                totais.empresa = Soma::new(&self.cabecalho);
+                totais.subtotais += 1;
+            }
+            totais.cnpj_atual = Some(cnpj.to_string());
+            totais.parte.somar(registro);
+            totais.empresa.somar(registro);
+        }
+
+        if let (Some(writer), Some((_, linhas))) = (self.writer.as_mut(), self.partes.last_mut()) {
+            writer.write_record(registro)?;
+            *linhas += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Formato dos arquivos gerados.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Formato {
+    #[default]
+    Csv,
+    /// Um objeto por arquivo, com os dados da empresa e a lista de funcionários (com
+    /// várias empresas, os dados de cada uma ficam nos seus funcionários).
+    Json,
+    /// Uma linha JSON por funcionário, com todas as colunas.
+    Ndjson,
+    /// Planilha do Excel, com uma linha de totais.
+    Xlsx,
+    /// Banco do SQLite, com uma tabela por tipo de XML.
+    Sqlite,
+    /// Relatório HTML para o navegador, com uma tabela e os totais de cada empresa.
+    Html,
+    /// Texto montado pelo modelo de `--template` (ver [`crate::Leiaute`]).
+    Texto,
+    /// Arquivo posicional, com os campos nas posições do leiaute de `--layout` (ver
+    /// [`crate::LeiautePosicional`]).
+    Posicional,
+}
+
+impl Formato {
+    /// Nomes aceitos por `--format`.
+    pub const NOMES: [&'static str; 8] = ["csv", "json", "ndjson", "xlsx", "sqlite", "html", "txt", "fixed"];
+
+    pub fn from_nome(nome: &str) -> Option<Formato> {
+        match nome.to_lowercase().as_str() {
+            "csv" => Some(Formato::Csv),
+            "json" => Some(Formato::Json),
+            "ndjson" => Some(Formato::Ndjson),
+            "xlsx" => Some(Formato::Xlsx),
+            "sqlite" => Some(Formato::Sqlite),
+            "html" => Some(Formato::Html),
+            "txt" => Some(Formato::Texto),
+            "fixed" => Some(Formato::Posicional),
+            _ => None,
+        }
+    }
+
+    /// Nome do formato em `--format`.
+    pub fn nome(self) -> &'static str {
+        match self {
+            Formato::Csv => "csv",
+            Formato::Json => "json",
+            Formato::Ndjson => "ndjson",
+            Formato::Xlsx => "xlsx",
+            Formato::Sqlite => "sqlite",
+            Formato::Html => "html",
+            Formato::Texto => "txt",
+            Formato::Posicional => "fixed",
+        }
+    }
+
+    /// Extensão dos arquivos gerados neste formato.
+    pub fn extensao(self) -> &'static str {
+        match self {
+            Formato::Csv => "csv",
+            Formato::Json => "json",
+            Formato::Ndjson => "ndjson",
+            Formato::Xlsx => "xlsx",
+            Formato::Sqlite => "db",
+            Formato::Html => "html",
+            Formato::Texto | Formato::Posicional => "txt",
+        }
+    }
+}
+
+/// Colunas que descrevem a empresa; no formato JSON aparecem uma única vez, fora da
+/// lista de funcionários.
+const COLUNAS_EMPRESA: [&str; 5] = ["Fantasia", "Razao", "CNPJ", "FantasiaOriginal", "RazaoOriginal"];
+
+/// Objeto JSON com as colunas de uma linha, na ordem do cabeçalho. As
+/// [`COLUNAS_NUMERICAS`] são gravadas como números, qualquer que seja o formato monetário
+/// do CSV, e as vazias ou não numéricas como `null`.
+struct Colunas<'a> {
+    cabecalho: &'a [String],
+    registro: &'a [String],
+    /// `Some(true)` mantém só as colunas da empresa, `Some(false)` só as do funcionário.
+    da_empresa: Option<bool>,
+}
+
+impl Serialize for Colunas<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut mapa = serializer.serialize_map(None)?;
+        for (coluna, valor) in self.cabecalho.iter().zip(self.registro) {
+            let da_empresa = COLUNAS_EMPRESA.contains(&coluna.as_str());
+            if self.da_empresa.is_none_or(|filtro| filtro == da_empresa) {
+                if COLUNAS_NUMERICAS.contains(&coluna.as_str()) {
+                    mapa.serialize_entry(coluna, &parse_valor(valor))?;
+                } else {
+                    mapa.serialize_entry(coluna, valor)?;
+                }
+            }
+        }
+        mapa.end()
+    }
+}
+
+/// Documento do formato [`Formato::Json`].
+struct Documento<'a> {
+    cabecalho: &'a [String],
+    linhas: &'a [Vec<String>],
+}
+
+impl Serialize for Documento<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Com várias empresas no mesmo XML, os dados da empresa ficam em cada funcionário.
+        let primeira = self.linhas.first();
+        let empresa_unica = self.cabecalho.iter().enumerate().all(|(indice, coluna)| {
+            !COLUNAS_EMPRESA.contains(&coluna.as_str()) || self.linhas.iter().all(|registro| registro.get(indice) == primeira.and_then(|primeira| primeira.get(indice)))
+        });
+        let funcionarios: Vec<Colunas> = self
+            .linhas
+            .iter()
+            .map(|registro| Colunas { cabecalho: self.cabecalho, registro, da_empresa: empresa_unica.then_some(false) })
+            .collect();
+
+        let mut mapa = serializer.serialize_map(None)?;
+        if let Some(primeira) = primeira.filter(|_| empresa_unica) {
+            for (coluna, valor) in self.cabecalho.iter().zip(primeira) {
+                if COLUNAS_EMPRESA.contains(&coluna.as_str()) {
+                    mapa.serialize_entry(coluna, valor)?;
+                }
+            }
+        }
+        mapa.serialize_entry("Funcionarios", &funcionarios)?;
+        mapa.end()
+    }
+}
+
+/// Grava as linhas em JSON ([`Formato::Json`]) ou NDJSON ([`Formato::Ndjson`]).
+///
+/// No NDJSON cada linha é gravada assim que chega; no JSON o documento só é gravado em
+/// [`SaidaJson::finalizar`], pois os dados da empresa vêm antes da lista de funcionários.
+pub struct SaidaJson<W: Write> {
+    writer: W,
+    ndjson: bool,
+    cabecalho: Vec<String>,
+    linhas: Vec<Vec<String>>,
+    iniciado: bool,
+}
+
+impl<W: Write> SaidaJson<W> {
+    pub fn new(writer: W, ndjson: bool) -> SaidaJson<W> {
+        SaidaJson {
+            writer,
+            ndjson,
+            cabecalho: Vec::new(),
+            linhas: Vec::new(),
+            iniciado: false,
+        }
+    }
+
+    /// Conclui a gravação e devolve o escritor. Sem [`Destino::iniciar`] nada é gravado.
+    pub fn finalizar(mut self) -> Result<W, ErroConversor> {
+        Destino::finalizar(&mut self)?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> Destino for SaidaJson<W> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.cabecalho = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        self.iniciado = true;
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        let registro: Vec<String> = registro.iter().map(|valor| valor.to_string()).collect();
+        if self.ndjson {
+            let colunas = Colunas { cabecalho: &self.cabecalho, registro: &registro, da_empresa: None };
+            serde_json::to_writer(&mut self.writer, &colunas)?;
+            writeln!(self.writer)?;
+        } else {
+            self.linhas.push(registro);
+        }
+        Ok(())
+    }
+
+    fn finalizar(&mut self) -> Result<(), ErroConversor> {
+        if self.iniciado && !self.ndjson {
+            let documento = Documento { cabecalho: &self.cabecalho, linhas: &self.linhas };
+            serde_json::to_writer_pretty(&mut self.writer, &documento)?;
+            writeln!(self.writer)?;
+        }
+        self.iniciado = false;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn gravar_atomico_mantem_link_simbolico_e_dispositivo() {
+        let pasta = std::env::temp_dir().join(format!("converterxmlcsv-saida-{}", std::process::id()));
+        fs::create_dir_all(&pasta).unwrap();
+        let real = pasta.join("real.csv");
+        let link = pasta.join("link.csv");
+        let _ = fs::remove_file(&link);
+        symlink("real.csv", &link).unwrap();
+
+        gravar_atomico(&link, b"CPF\n").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read(&real).unwrap(), b"CPF\n");
+
+        assert_eq!(destino_real(Path::new("/dev/null")).unwrap(), None);
+        gravar_atomico(Path::new("/dev/null"), b"CPF\n").unwrap();
+        assert!(!fs::metadata("/dev/null").unwrap().is_file());
+        fs::remove_dir_all(&pasta).unwrap();
+    }
+}
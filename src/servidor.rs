@@ -0,0 +1,340 @@
+use converterxmlcsv::Formato;
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Tamanho máximo da linha de requisição e dos cabeçalhos.
+const MAX_CABECALHOS: usize = 16 * 1024;
+
+/// Tempo máximo de espera pelos dados de uma conexão.
+const TEMPO_LEITURA: Duration = Duration::from_secs(30);
+
+/// Conexões atendidas ao mesmo tempo; as demais aguardam na fila do sistema até uma
+/// thread ficar livre. Cada uma pode reservar até o limite de tamanho do XML.
+pub const MAX_CONEXOES: usize = 8;
+
+/// Formatos que `POST /convert` pode devolver, com o tipo de conteúdo de cada um.
+const TIPOS_CONTEUDO: [(&str, Formato); 6] = [
+    ("text/csv", Formato::Csv),
+    ("application/json", Formato::Json),
+    ("application/x-ndjson", Formato::Ndjson),
+    ("application/ndjson", Formato::Ndjson),
+    ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", Formato::Xlsx),
+    ("text/html", Formato::Html),
+];
+
+/// Resposta HTTP de uma requisição.
+struct Resposta {
+    status: u16,
+    tipo: &'static str,
+    corpo: Vec<u8>,
+}
+
+impl Resposta {
+    fn texto(status: u16, mensagem: impl Into<String>) -> Resposta {
+        let mut corpo = mensagem.into().into_bytes();
+        corpo.push(b'\n');
+        Resposta { status, tipo: "text/plain; charset=utf-8", corpo }
+    }
+}
+
+/// Requisição já lida: método, caminho (sem a query), query e cabeçalhos, com os nomes
+/// em minúsculas.
+struct Requisicao {
+    metodo: String,
+    caminho: String,
+    query: String,
+    cabecalhos: Vec<(String, String)>,
+}
+
+impl Requisicao {
+    fn cabecalho(&self, nome: &str) -> Option<&str> {
+        self.cabecalhos.iter().find(|(atual, _)| atual == nome).map(|(_, valor)| valor.as_str())
+    }
+
+    fn parametro(&self, nome: &str) -> Option<&str> {
+        self.query.split('&').filter_map(|par| par.split_once('=')).find(|(atual, _)| *atual == nome).map(|(_, valor)| valor)
+    }
+}
+
+/// Servidor de `serve`: atende `POST /convert` com o XML no corpo e devolve o arquivo
+/// convertido no formato pedido em `?format=` ou no cabeçalho `Accept`, ou em `padrao`.
+///
+/// As conexões são atendidas por [`MAX_CONEXOES`] threads e fechadas após a resposta; a
+/// memória usada fica limitada a `MAX_CONEXOES * limite`, por mais clientes que se conectem.
+pub fn servir<F>(endereco: &str, limite: usize, padrao: Formato, converter: F) -> Result<(), Box<dyn Error>>
+where
+    F: Fn(&[u8], Formato) -> Result<Vec<u8>, String> + Sync,
+{
+    let ouvinte = TcpListener::bind(endereco).map_err(|erro| tr!("Não foi possível escutar em {}: {}", endereco, erro))?;
+    log::info!("Servidor escutando em {}", ouvinte.local_addr()?);
+    println!("{}", tr!("Servidor escutando em http://{} (POST /convert). Pressione Ctrl+C para encerrar.", ouvinte.local_addr()?));
+
+    let converter = &converter;
+    // Sem espaço no canal, o envio só termina quando uma thread pega a conexão; até lá, as
+    // novas conexões ficam na fila do `listen`.
+    let (fila, pendentes) = mpsc::sync_channel::<TcpStream>(0);
+    let pendentes = &Mutex::new(pendentes);
+    thread::scope(|escopo| {
+        for _ in 0..MAX_CONEXOES {
+            escopo.spawn(move || loop {
+                let conexao = match pendentes.lock() {
+                    Ok(pendentes) => pendentes.recv(),
+                    Err(_) => return,
+                };
+                let Ok(conexao) = conexao else {
+                    return;
+                };
+                if let Err(erro) = atender(conexao, limite, padrao, converter) {
+                    log::warn!("Erro na conexão: {}", erro);
+                }
+            });
+        }
+        for conexao in ouvinte.incoming() {
+            match conexao {
+                Ok(conexao) => {
+                    if fila.send(conexao).is_err() {
+                        break;
+                    }
+                }
+                Err(erro) => log::warn!("Erro ao aceitar conexão: {}", erro),
+            }
+        }
+        drop(fila);
+    });
+    Ok(())
+}
+
+fn atender<F>(conexao: TcpStream, limite: usize, padrao: Formato, converter: &F) -> io::Result<()>
+where
+    F: Fn(&[u8], Formato) -> Result<Vec<u8>, String>,
+{
+    conexao.set_read_timeout(Some(TEMPO_LEITURA))?;
+    let mut saida = conexao.try_clone()?;
+    let mut leitor = BufReader::new(conexao);
+    let resposta = match ler_requisicao(&mut leitor) {
+        Ok(requisicao) => responder(&requisicao, &mut leitor, &mut saida, limite, padrao, converter)?,
+        Err(mensagem) => Resposta::texto(400, mensagem),
+    };
+    let origem = saida.peer_addr().map(|endereco| endereco.to_string()).unwrap_or_default();
+    log::info!("{}: {} ({} bytes)", origem, resposta.status, resposta.corpo.len());
+    escrever(&mut saida, &resposta)
+}
+
+fn responder<F>(requisicao: &Requisicao, leitor: &mut impl Read, saida: &mut impl Write, limite: usize, padrao: Formato, converter: &F) -> io::Result<Resposta>
+where
+    F: Fn(&[u8], Formato) -> Result<Vec<u8>, String>,
+{
+    if requisicao.caminho != "/convert" {
+        return Ok(Resposta::texto(404, tr!("Caminho não encontrado; use POST /convert.")));
+    }
+    if requisicao.metodo != "POST" {
+        return Ok(Resposta::texto(405, tr!("Use POST /convert com o XML no corpo.")));
+    }
+    let formato = match negociar(requisicao, padrao) {
+        Ok(formato) => formato,
+        Err(mensagem) => return Ok(Resposta::texto(406, mensagem)),
+    };
+    if requisicao.cabecalho("transfer-encoding").is_some() {
+        return Ok(Resposta::texto(411, tr!("Envie o XML com o cabeçalho Content-Length.")));
+    }
+    let tamanho = match requisicao.cabecalho("content-length").map(str::parse::<usize>) {
+        Some(Ok(tamanho)) => tamanho,
+        Some(Err(_)) => return Ok(Resposta::texto(400, tr!("Content-Length inválido."))),
+        None => return Ok(Resposta::texto(411, tr!("Envie o XML com o cabeçalho Content-Length."))),
+    };
+    if tamanho > limite {
+        return Ok(Resposta::texto(413, tr!("O XML tem {} bytes, acima do limite de {} bytes.", tamanho, limite)));
+    }
+    if requisicao.cabecalho("expect").is_some_and(|valor| valor.eq_ignore_ascii_case("100-continue")) {
+        saida.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+    }
+
+    let mut corpo = vec![0; tamanho];
+    leitor.read_exact(&mut corpo)?;
+    Ok(match converter(&corpo, formato) {
+        Ok(conteudo) => Resposta { status: 200, tipo: tipo_conteudo(formato), corpo: conteudo },
+        Err(mensagem) => Resposta::texto(422, mensagem),
+    })
+}
+
+/// Formato de `?format=` ou, sem ele, o primeiro tipo de `Accept` que o servidor produz;
+/// `*/*` ou a falta do cabeçalho usam `padrao`.
+fn negociar(requisicao: &Requisicao, padrao: Formato) -> Result<Formato, String> {
+    if let Some(nome) = requisicao.parametro("format") {
+        return Formato::from_nome(nome)
+            .filter(|formato| TIPOS_CONTEUDO.iter().any(|(_, atual)| atual == formato))
+            .ok_or_else(|| tr!("Formato '{}' não suportado; use csv, json, ndjson, xlsx ou html.", nome));
+    }
+    let Some(aceitos) = requisicao.cabecalho("accept") else {
+        return Ok(padrao);
+    };
+    for aceito in aceitos.split(',') {
+        let tipo = aceito.split(';').next().unwrap_or_default().trim().to_lowercase();
+        if tipo == "*/*" {
+            return Ok(padrao);
+        }
+        if let Some((_, formato)) = TIPOS_CONTEUDO.iter().find(|(atual, _)| *atual == tipo) {
+            return Ok(*formato);
+        }
+    }
+    Err(tr!("Nenhum dos tipos de Accept é suportado; use um de: {}.", TIPOS_CONTEUDO.map(|(tipo, _)| tipo).join(", ")))
+}
+
+fn tipo_conteudo(formato: Formato) -> &'static str {
+    match formato {
+        Formato::Csv => "text/csv; charset=utf-8",
+        Formato::Json => "application/json",
+        Formato::Ndjson => "application/x-ndjson",
+        Formato::Html => "text/html; charset=utf-8",
+        _ => TIPOS_CONTEUDO.iter().find(|(_, atual)| *atual == formato).map_or("application/octet-stream", |(tipo, _)| tipo),
+    }
+}
+
+fn ler_requisicao(leitor: &mut impl BufRead) -> Result<Requisicao, String> {
+    let mut linhas = Vec::new();
+    let mut lidos = 0;
+    loop {
+        let mut linha = String::new();
+        let tamanho = leitor.read_line(&mut linha).map_err(|erro| tr!("Requisição inválida: {}", erro))?;
+        lidos += tamanho;
+        if tamanho == 0 || lidos > MAX_CABECALHOS {
+            return Err(tr!("Requisição incompleta ou com cabeçalhos grandes demais.").to_string());
+        }
+        let linha = linha.trim_end_matches(['\r', '\n']).to_string();
+        if linha.is_empty() {
+            break;
+        }
+        linhas.push(linha);
+    }
+
+    let mut partes = linhas.first().map(|linha| linha.split_whitespace()).ok_or(tr!("Requisição vazia."))?;
+    let metodo = partes.next().unwrap_or_default().to_string();
+    let alvo = partes.next().ok_or(tr!("Linha de requisição inválida."))?;
+    let (caminho, query) = alvo.split_once('?').unwrap_or((alvo, ""));
+    let cabecalhos = linhas[1..]
+        .iter()
+        .filter_map(|linha| linha.split_once(':'))
+        .map(|(nome, valor)| (nome.trim().to_lowercase(), valor.trim().to_string()))
+        .collect();
+    Ok(Requisicao { metodo, caminho: caminho.to_string(), query: query.to_string(), cabecalhos })
+}
+
+fn escrever(saida: &mut impl Write, resposta: &Resposta) -> io::Result<()> {
+    let motivo = match resposta.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        406 => "Not Acceptable",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        _ => "Unprocessable Entity",
+    };
+    write!(
+        saida,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        resposta.status,
+        motivo,
+        resposta.tipo,
+        resposta.corpo.len()
+    )?;
+    saida.write_all(&resposta.corpo)?;
+    saida.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Resposta de `responder` para a requisição completa em `bruta`, com um conversor que
+    /// devolve o formato pedido e o XML recebido.
+    fn atender_texto(bruta: &str, limite: usize) -> (Resposta, Vec<u8>) {
+        let mut leitor = bruta.as_bytes();
+        let mut saida = Vec::new();
+        let requisicao = ler_requisicao(&mut leitor).unwrap();
+        let converter = |xml: &[u8], formato: Formato| -> Result<Vec<u8>, String> {
+            if xml.starts_with(b"<") {
+                Ok(format!("{:?}:{}", formato, String::from_utf8_lossy(xml)).into_bytes())
+            } else {
+                Err("XML inválido".to_string())
+            }
+        };
+        let resposta = responder(&requisicao, &mut leitor, &mut saida, limite, Formato::Csv, &converter).unwrap();
+        (resposta, saida)
+    }
+
+    #[test]
+    fn le_linha_de_requisicao_e_cabecalhos() {
+        let mut leitor = &b"POST /convert?format=json&x=1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 3\r\n\r\n<a>"[..];
+        let requisicao = ler_requisicao(&mut leitor).unwrap();
+        assert_eq!(requisicao.metodo, "POST");
+        assert_eq!(requisicao.caminho, "/convert");
+        assert_eq!(requisicao.parametro("format"), Some("json"));
+        assert_eq!(requisicao.cabecalho("content-length"), Some("3"));
+        assert_eq!(leitor, b"<a>");
+
+        assert!(ler_requisicao(&mut &b"POST /convert HTTP/1.1\r\nHost: localhost\r\n"[..]).is_err());
+        assert!(ler_requisicao(&mut &b"\r\n"[..]).is_err());
+        let grande = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_CABECALHOS));
+        assert!(ler_requisicao(&mut grande.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn converte_no_formato_pedido() {
+        let (resposta, _) = atender_texto("POST /convert HTTP/1.1\r\nContent-Length: 3\r\n\r\n<a>", 100);
+        assert_eq!((resposta.status, resposta.tipo, resposta.corpo.as_slice()), (200, "text/csv; charset=utf-8", &b"Csv:<a>"[..]));
+
+        let (resposta, _) = atender_texto("POST /convert?format=ndjson HTTP/1.1\r\nAccept: text/html\r\nContent-Length: 3\r\n\r\n<a>", 100);
+        assert_eq!((resposta.status, resposta.tipo), (200, "application/x-ndjson"));
+
+        let (resposta, _) = atender_texto("POST /convert HTTP/1.1\r\nAccept: text/plain, application/json;q=0.9\r\nContent-Length: 3\r\n\r\n<a>", 100);
+        assert_eq!((resposta.status, resposta.tipo), (200, "application/json"));
+
+        let (resposta, _) = atender_texto("POST /convert HTTP/1.1\r\nAccept: */*\r\nContent-Length: 3\r\n\r\n<a>", 100);
+        assert_eq!(resposta.corpo, b"Csv:<a>");
+    }
+
+    #[test]
+    fn responde_100_continue() {
+        let (resposta, saida) = atender_texto("POST /convert HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 3\r\n\r\n<a>", 100);
+        assert_eq!(resposta.status, 200);
+        assert_eq!(saida, b"HTTP/1.1 100 Continue\r\n\r\n");
+    }
+
+    #[test]
+    fn recusa_requisicoes_invalidas() {
+        let casos = [
+            ("GET /outro HTTP/1.1\r\n\r\n", 404),
+            ("GET /convert HTTP/1.1\r\n\r\n", 405),
+            ("POST /convert?format=pdf HTTP/1.1\r\nContent-Length: 3\r\n\r\n<a>", 406),
+            ("POST /convert HTTP/1.1\r\nAccept: text/plain\r\nContent-Length: 3\r\n\r\n<a>", 406),
+            ("POST /convert HTTP/1.1\r\n\r\n", 411),
+            ("POST /convert HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\n<a>\r\n0\r\n\r\n", 411),
+            ("POST /convert HTTP/1.1\r\nContent-Length: três\r\n\r\n", 400),
+            ("POST /convert HTTP/1.1\r\nContent-Length: 101\r\n\r\n", 413),
+            ("POST /convert HTTP/1.1\r\nContent-Length: 3\r\n\r\nabc", 422),
+        ];
+        for (bruta, status) in casos {
+            let (resposta, saida) = atender_texto(bruta, 100);
+            assert_eq!(resposta.status, status, "{:?}", bruta);
+            assert_eq!(resposta.tipo, "text/plain; charset=utf-8", "{:?}", bruta);
+            assert!(saida.is_empty(), "{:?}", bruta);
+        }
+    }
+
+    #[test]
+    fn escreve_a_resposta() {
+        let mut saida = Vec::new();
+        escrever(&mut saida, &Resposta { status: 200, tipo: "text/csv; charset=utf-8", corpo: b"CPF\n".to_vec() }).unwrap();
+        assert_eq!(saida, b"HTTP/1.1 200 OK\r\nContent-Type: text/csv; charset=utf-8\r\nContent-Length: 4\r\nConnection: close\r\n\r\nCPF\n");
+
+        let mut saida = Vec::new();
+        escrever(&mut saida, &Resposta::texto(413, "grande")).unwrap();
+        assert!(saida.starts_with(b"HTTP/1.1 413 Payload Too Large\r\n"));
+        assert!(saida.ends_with(b"\r\n\r\ngrande\n"));
+    }
+}
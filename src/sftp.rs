@@ -0,0 +1,138 @@
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::compactado::eh_entrada;
+use crate::configuracao::ConfiguracaoSftp;
+
+/// Cliente do OpenSSH usado por `fetch`, sempre em modo de lote (`-b -`).
+const PROGRAMA: &str = "sftp";
+
+/// Arquivo, na pasta de destino de `fetch`, com os nomes dos XML já baixados; só os que
+/// não estão nele são baixados de novo.
+pub const ARQUIVO_BAIXADOS: &str = ".converterxmlcsv-sftp";
+
+/// Servidor da seção `[sftp]` da configuração.
+#[derive(Debug)]
+pub struct Sftp {
+    destino: String,
+    porta: Option<u16>,
+    chave: Option<PathBuf>,
+}
+
+impl Sftp {
+    pub fn from_config(config: &ConfiguracaoSftp) -> Result<Sftp, String> {
+        let host = config.host.as_deref().ok_or(tr!("Informe o servidor em host, na seção [sftp] do arquivo de configuração."))?;
+        let destino = match &config.user {
+            Some(usuario) => format!("{}@{}", usuario, host),
+            None => host.to_string(),
+        };
+        Ok(Sftp { destino, porta: config.port, chave: config.identity_file.clone() })
+    }
+
+    /// Nomes dos XML (também `.xml.gz` e `.zip`) da `pasta` remota.
+    pub fn listar(&self, pasta: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let saida = self.executar(&format!("cd {}\nls -1\n", aspas(pasta)))?;
+        // Em modo de lote o sftp repete cada comando na saída, precedido de "sftp>".
+        Ok(saida
+            .lines()
+            .filter(|linha| !linha.starts_with("sftp>"))
+            .map(str::trim)
+            .filter(|nome| eh_entrada(Path::new(nome)))
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Baixa os arquivos `nomes` da `pasta` remota para a pasta `local`.
+    pub fn baixar(&self, pasta: &str, nomes: &[String], local: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut comandos = format!("cd {}\n", aspas(pasta));
+        let arquivos: Vec<PathBuf> = nomes.iter().map(|nome| local.join(nome)).collect();
+        for (nome, arquivo) in nomes.iter().zip(&arquivos) {
+            comandos.push_str(&format!("get {} {}\n", aspas(nome), aspas(&arquivo.to_string_lossy())));
+        }
+        self.executar(&comandos)?;
+        Ok(arquivos)
+    }
+
+    /// Envia os `arquivos` para a `pasta` remota, com os mesmos nomes.
+    pub fn enviar(&self, arquivos: &[&Path], pasta: &str) -> Result<(), Box<dyn Error>> {
+        let mut comandos = format!("cd {}\n", aspas(pasta));
+        for arquivo in arquivos {
+            comandos.push_str(&format!("put {}\n", aspas(&arquivo.to_string_lossy())));
+        }
+        self.executar(&comandos)?;
+        Ok(())
+    }
+
+    /// Executa os `comandos` no servidor; com `-b`, o primeiro que falhar interrompe os demais.
+    fn executar(&self, comandos: &str) -> Result<String, Box<dyn Error>> {
+        let mut comando = Command::new(PROGRAMA);
+        comando.args(["-b", "-", "-o", "BatchMode=yes"]);
+        if let Some(porta) = self.porta {
+            comando.arg("-P").arg(porta.to_string());
+        }
+        if let Some(chave) = &self.chave {
+            comando.arg("-i").arg(chave);
+        }
+        let mut processo = comando
+            .arg(&self.destino)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|erro| tr!("Não foi possível executar o {}, necessário para fetch: {}. Instale o cliente do OpenSSH.", PROGRAMA, erro))?;
+
+        if let Some(mut entrada) = processo.stdin.take() {
+            entrada.write_all(comandos.as_bytes())?;
+        }
+        let saida = processo.wait_with_output()?;
+        if !saida.status.success() {
+            return Err(tr!("O {} com {} falhou ({}): {}", PROGRAMA, self.destino, saida.status, String::from_utf8_lossy(&saida.stderr).trim()).into());
+        }
+        Ok(String::from_utf8_lossy(&saida.stdout).into_owned())
+    }
+}
+
+/// Nomes já baixados para a `pasta`, lidos de [`ARQUIVO_BAIXADOS`].
+pub fn baixados(pasta: &Path) -> Vec<String> {
+    fs::read_to_string(pasta.join(ARQUIVO_BAIXADOS)).map(|conteudo| conteudo.lines().map(str::to_string).collect()).unwrap_or_default()
+}
+
+/// Acrescenta os `nomes` a [`ARQUIVO_BAIXADOS`] da `pasta`.
+pub fn registrar_baixados(pasta: &Path, nomes: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut arquivo = fs::OpenOptions::new().create(true).append(true).open(pasta.join(ARQUIVO_BAIXADOS))?;
+    for nome in nomes {
+        writeln!(arquivo, "{}", nome)?;
+    }
+    Ok(())
+}
+
+/// Caminho entre aspas duplas, como o sftp espera nos comandos de lote.
+fn aspas(caminho: &str) -> String {
+    format!("\"{}\"", caminho.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caminhos_entre_aspas() {
+        assert_eq!(aspas("/entrada/maio 2024"), "\"/entrada/maio 2024\"");
+        assert_eq!(aspas(r#"C:\xml\"a".xml"#), r#""C:\\xml\\\"a\".xml""#);
+    }
+
+    #[test]
+    fn registra_os_baixados() {
+        let pasta = std::env::temp_dir().join(format!("converterxmlcsv-sftp-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&pasta);
+        fs::create_dir_all(&pasta).unwrap();
+        assert!(baixados(&pasta).is_empty());
+        registrar_baixados(&pasta, &["a.xml".to_string()]).unwrap();
+        registrar_baixados(&pasta, &["b.xml.gz".to_string(), "c.zip".to_string()]).unwrap();
+        assert_eq!(baixados(&pasta), ["a.xml", "b.xml.gz", "c.zip"]);
+        fs::remove_dir_all(&pasta).unwrap();
+    }
+}
@@ -0,0 +1,136 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Constantes das rodadas do SHA-256 (FIPS 180-4).
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const INICIAL: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+
+/// SHA-256 do arquivo, em hexadecimal minúsculo, como o de `sha256sum`.
+pub fn sha256_arquivo(caminho: &Path) -> io::Result<String> {
+    sha256(File::open(caminho)?)
+}
+
+/// SHA-256 de todo o conteúdo de `leitor`, lido em blocos, sem ser carregado na memória.
+pub fn sha256<R: Read>(mut leitor: R) -> io::Result<String> {
+    let mut estado = INICIAL;
+    let mut bloco = [0u8; 64];
+    let mut preenchido = 0;
+    let mut tamanho: u64 = 0;
+    let mut buf = [0u8; 64 * 1024];
+
+    loop {
+        let lidos = leitor.read(&mut buf)?;
+        if lidos == 0 {
+            break;
+        }
+        tamanho += lidos as u64;
+        for byte in &buf[..lidos] {
+            bloco[preenchido] = *byte;
+            preenchido += 1;
+            if preenchido == 64 {
+                comprimir(&mut estado, &bloco);
+                preenchido = 0;
+            }
+        }
+    }
+
+    // Preenchimento: o bit 1, zeros e o tamanho da mensagem em bits, completando 64 bytes.
+    bloco[preenchido] = 0x80;
+    bloco[preenchido + 1..].fill(0);
+    if preenchido >= 56 {
+        comprimir(&mut estado, &bloco);
+        bloco.fill(0);
+    }
+    bloco[56..].copy_from_slice(&(tamanho * 8).to_be_bytes());
+    comprimir(&mut estado, &bloco);
+
+    Ok(estado.iter().map(|palavra| format!("{:08x}", palavra)).collect())
+}
+
+fn comprimir(estado: &mut [u32; 8], bloco: &[u8; 64]) {
+    let mut w = [0u32; 64];
+    for (indice, palavra) in bloco.chunks_exact(4).enumerate() {
+        w[indice] = u32::from_be_bytes([palavra[0], palavra[1], palavra[2], palavra[3]]);
+    }
+    for indice in 16..64 {
+        let s0 = w[indice - 15].rotate_right(7) ^ w[indice - 15].rotate_right(18) ^ (w[indice - 15] >> 3);
+        let s1 = w[indice - 2].rotate_right(17) ^ w[indice - 2].rotate_right(19) ^ (w[indice - 2] >> 10);
+        w[indice] = w[indice - 16].wrapping_add(s0).wrapping_add(w[indice - 7]).wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *estado;
+    for indice in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let escolha = (e & f) ^ (!e & g);
+        let t1 = h.wrapping_add(s1).wrapping_add(escolha).wrapping_add(K[indice]).wrapping_add(w[indice]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maioria = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maioria);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (valor, novo) in estado.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *valor = valor.wrapping_add(novo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vetores_conhecidos() {
+        assert_eq!(sha256(&b""[..]).unwrap(), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(sha256(&b"abc"[..]).unwrap(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(
+            sha256(&b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"[..]).unwrap(),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn um_milhao_de_a() {
+        let leitor = io::repeat(b'a').take(1_000_000);
+        assert_eq!(sha256(leitor).unwrap(), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+    }
+
+    #[test]
+    fn tamanhos_no_limite_do_bloco() {
+        // 55 bytes cabem com o preenchimento num só bloco; 56 e 64 precisam de um bloco a mais.
+        let esperados = [
+            (55, "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318"),
+            (56, "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a"),
+            (64, "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb"),
+        ];
+        for (tamanho, esperado) in esperados {
+            assert_eq!(sha256(io::repeat(b'a').take(tamanho)).unwrap(), esperado, "{} bytes", tamanho);
+        }
+    }
+
+    #[test]
+    fn arquivo() {
+        let caminho = std::env::temp_dir().join(format!("converterxmlcsv-sha256-{}.txt", std::process::id()));
+        std::fs::write(&caminho, "abc").unwrap();
+        assert_eq!(sha256_arquivo(&caminho).unwrap(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        std::fs::remove_file(&caminho).unwrap();
+        assert!(sha256_arquivo(&caminho).is_err());
+    }
+}
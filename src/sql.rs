@@ -0,0 +1,161 @@
+use std::io::Write;
+
+use crate::erro::ErroConversor;
+use crate::modelo::Tipo;
+use crate::saida::Destino;
+use crate::valor::{parse_valor, COLUNAS_NUMERICAS};
+
+/// Tabela que recebe as linhas do tipo; `None` (conversão genérica ou por mapeamento) usa
+/// `registros`.
+pub fn tabela(tipo: Option<Tipo>) -> &'static str {
+    match tipo {
+        Some(Tipo::Comissao) => "comissoes",
+        Some(Tipo::Vales) => "vales",
+        Some(Tipo::Ferias) => "ferias",
+        Some(Tipo::DecimoTerceiro) => "decimo_terceiro",
+        None => "registros",
+    }
+}
+
+/// Grava as linhas como um script SQL do SQLite: cria a tabela, se ela ainda não existir,
+/// e insere as linhas numa única transação, com o arquivo de origem e a data da importação
+/// nas colunas `ArquivoOrigem` e `ImportadoEm`.
+///
+/// As [`COLUNAS_NUMERICAS`] são criadas como `REAL` e gravadas como números; as vazias ou
+/// não numéricas ficam `NULL`.
+///
+/// A tabela só é conhecida ao final da conversão, por isso o script é gravado em
+/// [`SaidaSql::finalizar`].
+pub struct SaidaSql<W: Write> {
+    writer: W,
+    origem: String,
+    importado_em: String,
+    tabela: String,
+    cabecalho: Vec<String>,
+    linhas: Vec<Vec<String>>,
+    iniciado: bool,
+}
+
+impl<W: Write> SaidaSql<W> {
+    pub fn new(writer: W, origem: &str, importado_em: &str) -> SaidaSql<W> {
+        SaidaSql {
+            writer,
+            origem: origem.to_string(),
+            importado_em: importado_em.to_string(),
+            tabela: tabela(None).to_string(),
+            cabecalho: Vec::new(),
+            linhas: Vec::new(),
+            iniciado: false,
+        }
+    }
+
+    /// Tabela que recebe as linhas em [`Destino::finalizar`]; sem ela, `registros`.
+    pub fn definir_tabela(&mut self, tabela: &str) {
+        self.tabela = tabela.to_string();
+    }
+
+    /// Grava o script com as linhas na `tabela` e devolve o escritor. Sem
+    /// [`Destino::iniciar`] nada é gravado.
+    pub fn finalizar(mut self, tabela: &str) -> Result<W, ErroConversor> {
+        self.definir_tabela(tabela);
+        Destino::finalizar(&mut self)?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> Destino for SaidaSql<W> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        self.cabecalho = cabecalho.iter().map(|coluna| coluna.to_string()).collect();
+        self.iniciado = true;
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        self.linhas.push(registro.iter().map(|valor| valor.to_string()).collect());
+        Ok(())
+    }
+
+    fn finalizar(&mut self) -> Result<(), ErroConversor> {
+        if self.iniciado {
+            let colunas: Vec<String> = self
+                .cabecalho
+                .iter()
+                .map(|coluna| format!("{} {}", identificador(coluna), if COLUNAS_NUMERICAS.contains(&coluna.as_str()) { "REAL" } else { "TEXT" }))
+                .chain(["\"ArquivoOrigem\" TEXT".to_string(), "\"ImportadoEm\" TEXT".to_string()])
+                .collect();
+            let nomes: Vec<String> = self.cabecalho.iter().map(|coluna| identificador(coluna)).chain(["\"ArquivoOrigem\"".to_string(), "\"ImportadoEm\"".to_string()]).collect();
+
+            writeln!(self.writer, "BEGIN;")?;
+            writeln!(self.writer, "CREATE TABLE IF NOT EXISTS {} ({});", identificador(&self.tabela), colunas.join(", "))?;
+            for linha in &self.linhas {
+                let valores: Vec<String> = self
+                    .cabecalho
+                    .iter()
+                    .zip(linha)
+                    .map(|(coluna, valor)| {
+                        if !COLUNAS_NUMERICAS.contains(&coluna.as_str()) {
+                            return texto(valor);
+                        }
+                        match parse_valor(valor) {
+                            Some(numero) => numero.to_string(),
+                            None => "NULL".to_string(),
+                        }
+                    })
+                    .chain([texto(&self.origem), texto(&self.importado_em)])
+                    .collect();
+                writeln!(self.writer, "INSERT INTO {} ({}) VALUES ({});", identificador(&self.tabela), nomes.join(", "), valores.join(", "))?;
+            }
+            writeln!(self.writer, "COMMIT;")?;
+        }
+        self.iniciado = false;
+        self.linhas.clear();
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Nome de tabela ou coluna entre aspas duplas, mantendo as maiúsculas.
+pub fn identificador(nome: &str) -> String {
+    format!("\"{}\"", nome.replace('"', "\"\""))
+}
+
+/// Tabela e colunas, com o tipo, do `CREATE TABLE` de um script gravado por [`SaidaSql`].
+pub fn tabela_criada(script: &str) -> Option<(String, Vec<(String, String)>)> {
+    let resto = script.lines().find_map(|linha| linha.strip_prefix("CREATE TABLE IF NOT EXISTS "))?;
+    let (tabela, resto) = ler_identificador(resto)?;
+    let mut resto = resto.strip_prefix(" (")?;
+    let mut colunas = Vec::new();
+    loop {
+        let (coluna, depois) = ler_identificador(resto)?;
+        let depois = depois.strip_prefix(' ')?;
+        let fim = depois.find([',', ')'])?;
+        colunas.push((coluna, depois[..fim].to_string()));
+        match depois[fim..].strip_prefix(", ") {
+            Some(proxima) => resto = proxima,
+            None => return Some((tabela, colunas)),
+        }
+    }
+}
+
+/// Nome de um [`identificador`] no início de `texto`, seguido do restante do texto.
+fn ler_identificador(texto: &str) -> Option<(String, &str)> {
+    let mut resto = texto.strip_prefix('"')?;
+    let mut nome = String::new();
+    loop {
+        let fim = resto.find('"')?;
+        nome.push_str(&resto[..fim]);
+        resto = &resto[fim + 1..];
+        match resto.strip_prefix('"') {
+            Some(depois) => {
+                nome.push('"');
+                resto = depois;
+            }
+            None => return Some((nome, resto)),
+        }
+    }
+}
+
+/// Texto entre aspas simples.
+fn texto(valor: &str) -> String {
+    format!("'{}'", valor.replace('\'', "''"))
+}
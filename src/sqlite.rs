@@ -0,0 +1,82 @@
+use converterxmlcsv::sql::{identificador, tabela_criada};
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+/// Programa do SQLite que executa o script de `--format sqlite` no banco.
+const PROGRAMA: &str = "sqlite3";
+
+/// Executa o `script` SQL no `banco`, criado pelo `sqlite3` se ainda não existir. Com erro,
+/// a transação do script é desfeita e o banco fica como estava.
+pub fn executar(banco: &Path, script: &[u8]) -> Result<(), Box<dyn Error>> {
+    let script = completar_colunas(banco, script)?;
+    let saida = sqlite3(banco, &script)?;
+    if !saida.status.success() {
+        return Err(tr!("O {} não conseguiu gravar {} ({}): {}", PROGRAMA, banco.display(), saida.status, String::from_utf8_lossy(&saida.stderr).trim()).into());
+    }
+    Ok(())
+}
+
+/// `script` com um `ALTER TABLE ... ADD COLUMN` logo após o `CREATE TABLE` para cada coluna
+/// que a tabela, criada no `banco` por uma conversão anterior com outras colunas
+/// (`--columns`, `--split-mesano`...), ainda não possui; sem elas, os `INSERT` falhariam.
+/// Nas linhas já gravadas, as colunas novas ficam `NULL`.
+fn completar_colunas(banco: &Path, script: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let Some((texto, (tabela, colunas))) = std::str::from_utf8(script).ok().and_then(|texto| Some((texto, tabela_criada(texto)?))) else {
+        return Ok(script.to_vec());
+    };
+    if !banco.exists() {
+        return Ok(script.to_vec());
+    }
+
+    // Uma tabela que ainda não existe não possui colunas e é criada pelo próprio script.
+    let existentes = colunas_existentes(banco, &tabela)?;
+    if existentes.is_empty() {
+        return Ok(script.to_vec());
+    }
+    let alteracoes: String = colunas
+        .iter()
+        .filter(|(coluna, _)| !existentes.iter().any(|existente| existente.eq_ignore_ascii_case(coluna)))
+        .map(|(coluna, tipo)| format!("ALTER TABLE {} ADD COLUMN {} {};\n", identificador(&tabela), identificador(coluna), tipo))
+        .collect();
+    let Some(criacao) = texto.find("CREATE TABLE IF NOT EXISTS ") else {
+        return Ok(script.to_vec());
+    };
+    let posicao = texto[criacao..].find('\n').map_or(texto.len(), |fim| criacao + fim + 1);
+    Ok([&texto[..posicao], &alteracoes, &texto[posicao..]].concat().into_bytes())
+}
+
+/// Colunas da `tabela` no `banco`, lidas de `PRAGMA table_info`; nenhuma quando ela não existe.
+fn colunas_existentes(banco: &Path, tabela: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    // Em hexadecimal, nomes com `|` ou quebras de linha não se confundem com a separação das colunas.
+    let consulta = format!("SELECT hex(name) FROM pragma_table_info('{}');\n", tabela.replace('\'', "''"));
+    let saida = sqlite3(banco, consulta.as_bytes())?;
+    if !saida.status.success() {
+        return Err(tr!("O {} não conseguiu ler as colunas da tabela {} em {} ({}): {}", PROGRAMA, tabela, banco.display(), saida.status, String::from_utf8_lossy(&saida.stderr).trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&saida.stdout).lines().filter_map(hexadecimal).collect())
+}
+
+/// Texto UTF-8 de uma sequência de dígitos hexadecimais, como a de `hex()` do SQLite.
+fn hexadecimal(texto: &str) -> Option<String> {
+    let bytes = (0..texto.len()).step_by(2).map(|indice| u8::from_str_radix(texto.get(indice..indice + 2)?, 16).ok()).collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Executa o `sqlite3` no `banco` com `entrada` como script, interrompendo no primeiro erro.
+fn sqlite3(banco: &Path, entrada: &[u8]) -> Result<Output, Box<dyn Error>> {
+    let mut processo = Command::new(PROGRAMA)
+        .arg("-bail")
+        .arg(banco)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|erro| tr!("Não foi possível executar o {} para gravar {}: {}. Instale o SQLite ou use --output - para obter o script SQL.", PROGRAMA, banco.display(), erro))?;
+
+    if let Some(mut stdin) = processo.stdin.take() {
+        stdin.write_all(entrada)?;
+    }
+    Ok(processo.wait_with_output()?)
+}
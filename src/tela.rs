@@ -0,0 +1,158 @@
+use colored::*;
+use console::{pad_str, truncate_str, Alignment, Key, Term};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use converterxmlcsv::Tipo;
+
+/// Largura máxima da lista de arquivos, à esquerda da prévia.
+const LARGURA_LISTA: usize = 42;
+
+/// Situação de cada arquivo na sessão de `--tui`.
+#[derive(Clone, Copy, PartialEq)]
+enum Situacao {
+    Pendente,
+    Convertido,
+    Falhou,
+    Pulado,
+}
+
+/// Mensagens da conversão de um arquivo, em `Err` quando ela falhou.
+pub type Mensagens = Result<Vec<String>, Vec<String>>;
+
+/// Ações de `--tui` que dependem da conversão, feitas pelo chamador.
+pub struct Acoes<'a> {
+    /// Tipo do XML, para a etiqueta da lista; `None` quando ele não é reconhecido.
+    pub tipo: &'a dyn Fn(&Path) -> Option<Tipo>,
+    /// Linhas da prévia do arquivo: as primeiras linhas convertidas e os totais.
+    pub previa: &'a dyn Fn(&Path) -> Vec<String>,
+    /// Converte o arquivo e devolve as mensagens da conversão.
+    pub converter: &'a mut dyn FnMut(&Path) -> Mensagens,
+    /// Pasta onde os arquivos gerados a partir do XML são gravados.
+    pub pasta_saida: &'a dyn Fn(&Path) -> PathBuf,
+}
+
+/// Sessão de `--tui`: lista os `arquivos` à esquerda, com o tipo de cada um, e a prévia
+/// do arquivo escolhido à direita.
+///
+/// ↑/↓ escolhem o arquivo, Enter converte, `s` pula, `o` abre a pasta de saída e `q` (ou
+/// Esc) encerra.
+pub fn executar(arquivos: &[PathBuf], acoes: Acoes) -> io::Result<()> {
+    let term = Term::stdout();
+    let etiquetas: Vec<&str> = arquivos.iter().map(|arquivo| etiqueta((acoes.tipo)(arquivo))).collect();
+    let mut situacoes = vec![Situacao::Pendente; arquivos.len()];
+    let mut previas: Vec<Option<Vec<String>>> = vec![None; arquivos.len()];
+    let mut atual = 0;
+    let mut mensagens: Vec<String> = Vec::new();
+
+    term.hide_cursor()?;
+    let resultado = loop {
+        if previas[atual].is_none() {
+            previas[atual] = Some((acoes.previa)(&arquivos[atual]));
+        }
+        desenhar(&term, arquivos, &etiquetas, &situacoes, atual, previas[atual].as_deref().unwrap_or_default(), &mensagens)?;
+
+        match term.read_key()? {
+            Key::ArrowUp | Key::Char('k') => atual = atual.saturating_sub(1),
+            Key::ArrowDown | Key::Char('j') => atual = (atual + 1).min(arquivos.len() - 1),
+            Key::Enter => {
+                mensagens = vec![tr!("Convertendo {}...", arquivos[atual].display())];
+                desenhar(&term, arquivos, &etiquetas, &situacoes, atual, previas[atual].as_deref().unwrap_or_default(), &mensagens)?;
+                (mensagens, situacoes[atual]) = match (acoes.converter)(&arquivos[atual]) {
+                    Ok(mensagens) => (mensagens, Situacao::Convertido),
+                    Err(mensagens) => (mensagens, Situacao::Falhou),
+                };
+                atual = (atual + 1).min(arquivos.len() - 1);
+            }
+            Key::Char('s') => {
+                situacoes[atual] = Situacao::Pulado;
+                mensagens = vec![tr!("{} pulado.", arquivos[atual].display())];
+                atual = (atual + 1).min(arquivos.len() - 1);
+            }
+            Key::Char('o') => {
+                let pasta = (acoes.pasta_saida)(&arquivos[atual]);
+                mensagens = match abrir_pasta(&pasta) {
+                    Ok(()) => vec![tr!("Abrindo {}...", pasta.display())],
+                    Err(erro) => vec![tr!("Não foi possível abrir {}: {}", pasta.display(), erro)],
+                };
+            }
+            Key::Char('q') | Key::Escape => break Ok(()),
+            _ => {}
+        }
+    };
+    term.clear_screen()?;
+    term.show_cursor()?;
+    resultado
+}
+
+fn etiqueta(tipo: Option<Tipo>) -> &'static str {
+    match tipo {
+        Some(Tipo::Comissao) => tr!("COM"),
+        Some(Tipo::Vales) => tr!("VAL"),
+        Some(Tipo::Ferias) => tr!("FER"),
+        Some(Tipo::DecimoTerceiro) => tr!("13º"),
+        None => "???",
+    }
+}
+
+fn desenhar(term: &Term, arquivos: &[PathBuf], etiquetas: &[&str], situacoes: &[Situacao], atual: usize, previa: &[String], mensagens: &[String]) -> io::Result<()> {
+    let (linhas, colunas) = term.size();
+    let (linhas, colunas) = (linhas as usize, colunas as usize);
+    let largura_lista = LARGURA_LISTA.min(colunas / 3);
+    let largura_previa = colunas.saturating_sub(largura_lista + 3);
+    // Título, legenda e mensagens ficam fora da área das listas.
+    let altura = linhas.saturating_sub(4 + mensagens.len().min(3));
+
+    // A lista rola para manter o arquivo escolhido visível.
+    let inicio = (atual + 1).saturating_sub(altura);
+    let itens: Vec<String> = arquivos
+        .iter()
+        .enumerate()
+        .skip(inicio)
+        .take(altura)
+        .map(|(indice, arquivo)| {
+            let marca = match situacoes[indice] {
+                Situacao::Pendente => " ".normal(),
+                Situacao::Convertido => "✔".bright_green(),
+                Situacao::Falhou => "✖".bright_red(),
+                Situacao::Pulado => "-".bright_black(),
+            };
+            let nome = arquivo.file_name().unwrap_or_default().to_string_lossy();
+            let texto = format!("{} [{}] {}", marca, etiquetas[indice], nome);
+            let texto = truncate_str(&texto, largura_lista, "…");
+            if indice == atual {
+                pad_str(&texto, largura_lista, Alignment::Left, None).reversed().to_string()
+            } else {
+                pad_str(&texto, largura_lista, Alignment::Left, None).to_string()
+            }
+        })
+        .collect();
+
+    term.clear_screen()?;
+    term.write_line(&format!("{}", tr!("Conversor XML para CSV").bright_green().bold()))?;
+    term.write_line(&format!("{}", tr!("↑/↓ escolhe · Enter converte · s pula · o abre a pasta de saída · q sai").bright_yellow()))?;
+    for linha in 0..altura {
+        let item = itens.get(linha).map_or_else(|| " ".repeat(largura_lista), String::clone);
+        let texto = previa.get(linha).map_or("", String::as_str);
+        term.write_line(&format!("{} │ {}", item, truncate_str(texto, largura_previa, "…")))?;
+    }
+    term.write_line("")?;
+    for mensagem in mensagens.iter().take(3) {
+        term.write_line(&truncate_str(mensagem, colunas, "…"))?;
+    }
+    Ok(())
+}
+
+/// Abre `pasta` no gerenciador de arquivos do sistema.
+pub fn abrir_pasta(pasta: &Path) -> io::Result<()> {
+    let programa = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    process::Command::new(programa).arg(pasta).stdout(process::Stdio::null()).stderr(process::Stdio::null()).spawn()?;
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::conversao::Resumo;
+use crate::modelo::{Funcionario, Tipo};
+use crate::opcoes::Opcoes;
+use crate::valor::Quantia;
+
+/// Leitor de um tipo de XML: como ele é reconhecido e quais colunas tem além das comuns a
+/// todos (`Fantasia`, `Razao`, `CNPJ`, `MesAno`, `CPF` e `Valor`).
+///
+/// Os tipos suportados são os de [`LEITORES`]; um tipo novo (rescisão, adiantamento...)
+/// precisa só de uma variante em [`Tipo`] e de um leitor registrado ali, sem mudanças na
+/// leitura do XML nem na conversão.
+pub trait LeitorTipo: Sync {
+    fn tipo(&self) -> Tipo;
+
+    /// Nome usado em `--type`, no manifest e nas mensagens, seguido das outras grafias
+    /// aceitas (ex.: `comissão`).
+    fn nomes(&self) -> &'static [&'static str];
+
+    /// Elemento raiz do XML, comparado sem diferenciar maiúsculas.
+    fn raiz(&self) -> &'static str;
+
+    /// Colunas gravadas entre `CPF` e `Valor`.
+    fn colunas_antes(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Colunas gravadas depois de `Valor`.
+    fn colunas_depois(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Valores de [`LeitorTipo::colunas_antes`] e de [`LeitorTipo::colunas_depois`] do funcionário.
+    fn valores<'a>(&self, _funcionario: &'a Funcionario, _opcoes: &Opcoes) -> (Vec<Cow<'a, str>>, Vec<Cow<'a, str>>) {
+        (Vec::new(), Vec::new())
+    }
+
+    /// Acumula no resumo os totais próprios do tipo; `valor` é o do funcionário, em reais.
+    fn somar(&self, _resumo: &mut Resumo, _funcionario: &Funcionario, _valor: Quantia, _opcoes: &Opcoes) {}
+}
+
+/// Leitores dos tipos suportados, na ordem em que são listados em `--type`.
+pub static LEITORES: [&dyn LeitorTipo; 4] = [&Comissao, &Vales, &Ferias, &DecimoTerceiro];
+
+/// Leitor do tipo; todo [`Tipo`] tem um em [`LEITORES`].
+pub fn leitor(tipo: Tipo) -> &'static dyn LeitorTipo {
+    LEITORES.iter().copied().find(|leitor| leitor.tipo() == tipo).expect("todo tipo possui um leitor")
+}
+
+/// Leitor de um dos nomes de [`LeitorTipo::nomes`], sem diferenciar maiúsculas.
+pub fn por_nome(nome: &str) -> Option<&'static dyn LeitorTipo> {
+    let nome = nome.to_lowercase();
+    LEITORES.iter().copied().find(|leitor| leitor.nomes().contains(&nome.as_str()))
+}
+
+/// Leitor do elemento raiz do XML (`<Comissao>`, `<Vales>`...).
+pub fn por_raiz(raiz: &str) -> Option<&'static dyn LeitorTipo> {
+    LEITORES.iter().copied().find(|leitor| leitor.raiz().eq_ignore_ascii_case(raiz)).or_else(|| por_nome(raiz))
+}
+
+/// Leitor indicado pelo prefixo do nome do arquivo, antes do primeiro `_` (ex.: `comissao_0124.xml`).
+pub fn por_caminho(caminho: &Path) -> Option<&'static dyn LeitorTipo> {
+    let stem = caminho.file_stem()?.to_str()?;
+    por_nome(stem.split('_').next()?)
+}
+
+struct Comissao;
+
+impl LeitorTipo for Comissao {
+    fn tipo(&self) -> Tipo {
+        Tipo::Comissao
+    }
+
+    fn nomes(&self) -> &'static [&'static str] {
+        &["comissao", "comissão"]
+    }
+
+    fn raiz(&self) -> &'static str {
+        "Comissao"
+    }
+
+    fn colunas_depois(&self) -> &'static [&'static str] {
+        &["MetaPremio"]
+    }
+
+    fn valores<'a>(&self, funcionario: &'a Funcionario, opcoes: &Opcoes) -> (Vec<Cow<'a, str>>, Vec<Cow<'a, str>>) {
+        let meta_premio = funcionario.meta_premio.as_deref().unwrap_or("");
+        // A meta é gravada como veio no XML, a menos que o separador ou o formato de saída
+        // tenham sido escolhidos.
+        let meta_premio = if opcoes.decimal_saida.is_some() || opcoes.formato_monetario.is_some() {
+            Cow::Owned(opcoes.texto_numero(meta_premio))
+        } else {
+            Cow::Borrowed(meta_premio)
+        };
+        (Vec::new(), vec![meta_premio])
+    }
+
+    fn somar(&self, resumo: &mut Resumo, funcionario: &Funcionario, _valor: Quantia, opcoes: &Opcoes) {
+        resumo.total_meta += opcoes.ler_quantia(funcionario.meta_premio.as_deref().unwrap_or("")).unwrap_or_default();
+    }
+}
+
+struct Vales;
+
+impl LeitorTipo for Vales {
+    fn tipo(&self) -> Tipo {
+        Tipo::Vales
+    }
+
+    fn nomes(&self) -> &'static [&'static str] {
+        &["vales"]
+    }
+
+    fn raiz(&self) -> &'static str {
+        "Vales"
+    }
+}
+
+struct Ferias;
+
+impl LeitorTipo for Ferias {
+    fn tipo(&self) -> Tipo {
+        Tipo::Ferias
+    }
+
+    fn nomes(&self) -> &'static [&'static str] {
+        &["ferias", "férias"]
+    }
+
+    fn raiz(&self) -> &'static str {
+        "Ferias"
+    }
+
+    fn colunas_antes(&self) -> &'static [&'static str] {
+        &["DataInicio", "DataFim"]
+    }
+
+    fn valores<'a>(&self, funcionario: &'a Funcionario, _opcoes: &Opcoes) -> (Vec<Cow<'a, str>>, Vec<Cow<'a, str>>) {
+        let data_inicio = funcionario.data_inicio.as_deref().unwrap_or("");
+        let data_fim = funcionario.data_fim.as_deref().unwrap_or("");
+        (vec![Cow::Borrowed(data_inicio), Cow::Borrowed(data_fim)], Vec::new())
+    }
+}
+
+struct DecimoTerceiro;
+
+impl LeitorTipo for DecimoTerceiro {
+    fn tipo(&self) -> Tipo {
+        Tipo::DecimoTerceiro
+    }
+
+    fn nomes(&self) -> &'static [&'static str] {
+        &["decimoterceiro", "décimoterceiro"]
+    }
+
+    fn raiz(&self) -> &'static str {
+        "DecimoTerceiro"
+    }
+
+    fn colunas_antes(&self) -> &'static [&'static str] {
+        &["Parcela"]
+    }
+
+    fn valores<'a>(&self, funcionario: &'a Funcionario, _opcoes: &Opcoes) -> (Vec<Cow<'a, str>>, Vec<Cow<'a, str>>) {
+        (vec![Cow::Borrowed(funcionario.parcela.as_deref().unwrap_or(""))], Vec::new())
+    }
+
+    fn somar(&self, resumo: &mut Resumo, funcionario: &Funcionario, valor: Quantia, _opcoes: &Opcoes) {
+        let parcela = funcionario.parcela.as_deref().unwrap_or("");
+        match resumo.totais_parcela.iter_mut().find(|(nome, _)| nome == parcela) {
+            Some((_, total)) => *total += valor,
+            None => resumo.totais_parcela.push((parcela.to_string(), valor)),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+
+/// Arquivo, em cada pasta travada, que só uma execução do conversor por vez pode travar.
+/// Ele é apagado quando a trava é liberada.
+pub const ARQUIVO_TRAVA: &str = ".converterxmlcsv.lock";
+
+/// Trava exclusiva das pastas em que o conversor grava, para que duas execuções na mesma
+/// pasta (comum numa unidade de rede compartilhada) não sobrescrevam os CSVs, os arquivados
+/// e a lista de convertidos uma da outra. A trava é liberada quando é descartada, inclusive
+/// se o processo terminar de forma inesperada; só neste caso o [`ARQUIVO_TRAVA`] fica na
+/// pasta, e é reaproveitado pela próxima execução.
+pub struct Trava {
+    arquivos: Vec<(PathBuf, File)>,
+}
+
+impl Trava {
+    /// Trava as `pastas`; se outra execução já travou alguma delas, avisa em `console` e
+    /// espera até que ela termine. As pastas são travadas sempre na mesma ordem, para que duas
+    /// execuções com as mesmas pastas não fiquem esperando uma pela outra.
+    ///
+    /// Num sistema de arquivos sem suporte a travas, a conversão segue sem ela, com um aviso
+    /// no registro.
+    pub fn adquirir(pastas: &[&Path], console: &mut dyn Write) -> io::Result<Trava> {
+        let mut caminhos: Vec<PathBuf> = Vec::new();
+        for pasta in pastas {
+            fs::create_dir_all(pasta)?;
+            caminhos.push(fs::canonicalize(pasta)?.join(ARQUIVO_TRAVA));
+        }
+        caminhos.sort();
+        caminhos.dedup();
+
+        let mut trava = Trava { arquivos: Vec::new() };
+        for caminho in caminhos {
+            if let Some(arquivo) = travar(&caminho, console)? {
+                trava.arquivos.push((caminho, arquivo));
+            }
+        }
+        Ok(trava)
+    }
+}
+
+impl Drop for Trava {
+    /// Apaga cada [`ARQUIVO_TRAVA`]. No Unix ele é apagado ainda travado e quem esperava
+    /// percebe pelo [`mesmo_arquivo`]; no Windows só depois de fechado, e continua na pasta se
+    /// outra execução já o abriu.
+    fn drop(&mut self) {
+        for (caminho, arquivo) in self.arquivos.drain(..).rev() {
+            if cfg!(unix) {
+                let _ = fs::remove_file(&caminho);
+                drop(arquivo);
+            } else {
+                drop(arquivo);
+                let _ = fs::remove_file(&caminho);
+            }
+        }
+    }
+}
+
+/// Trava o arquivo `caminho`, criando-o. Quem esperava pela trava de outra execução confere
+/// se o arquivo travado ainda é o da pasta: se ela o apagou ao terminar, tenta de novo com o
+/// arquivo novo. `None` quando o sistema de arquivos não suporta travas.
+fn travar(caminho: &Path, console: &mut dyn Write) -> io::Result<Option<File>> {
+    let mut avisado = false;
+    loop {
+        let arquivo = abrir(caminho)?;
+        match arquivo.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                if !avisado {
+                    let pasta = caminho.parent().unwrap_or(Path::new("."));
+                    writeln!(console, "{}", tr!("Outra execução do conversor está gravando em {}; aguardando...", pasta.display()).bright_yellow())?;
+                    avisado = true;
+                }
+                arquivo.lock()?;
+            }
+            Err(TryLockError::Error(erro)) => {
+                log::warn!("Não foi possível travar {}: {}; a conversão segue sem a trava", caminho.display(), erro);
+                return Ok(None);
+            }
+        }
+        if mesmo_arquivo(&arquivo, caminho) {
+            return Ok(Some(arquivo));
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn abrir(caminho: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).truncate(false).write(true).open(caminho)
+}
+
+/// No Windows o arquivo fica oculto, como os que começam com `.` nos demais sistemas, e é
+/// aberto sem permitir que outra execução o apague enquanto estiver aberto.
+#[cfg(windows)]
+fn abrir(caminho: &Path) -> io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_SHARE_READ: u32 = 0x1;
+    const FILE_SHARE_WRITE: u32 = 0x2;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+        .attributes(FILE_ATTRIBUTE_HIDDEN)
+        .open(caminho)
+}
+
+/// Indica se `arquivo` aberto ainda é o que está em `caminho`.
+#[cfg(unix)]
+fn mesmo_arquivo(arquivo: &File, caminho: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (arquivo.metadata(), fs::metadata(caminho)) {
+        (Ok(aberto), Ok(atual)) => aberto.dev() == atual.dev() && aberto.ino() == atual.ino(),
+        _ => false,
+    }
+}
+
+/// Nos demais sistemas um arquivo aberto não é apagado por outra execução; basta ele existir.
+#[cfg(not(unix))]
+fn mesmo_arquivo(_arquivo: &File, caminho: &Path) -> bool {
+    caminho.exists()
+}
@@ -0,0 +1,397 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub};
+
+/// Símbolo de moeda aceito antes dos valores, como em `R$ 1.234,56`.
+const SIMBOLO_REAL: &str = "R$";
+
+/// Colunas com valores monetários, gravadas como números no JSON e no SQL.
+pub const COLUNAS_NUMERICAS: [&str; 2] = ["Valor", "MetaPremio"];
+
+/// `Valor` ou `MetaPremio` de um funcionário que não é número. A linha é gravada com o
+/// texto do XML, mas o valor entra como zero nos totais.
+#[derive(Debug, Clone)]
+pub struct ValorNaoNumerico {
+    /// Posição do funcionário no XML (a partir de 1).
+    pub funcionario: usize,
+    pub cpf: String,
+    /// `Valor` ou `MetaPremio`.
+    pub campo: &'static str,
+    pub valor: String,
+}
+
+impl fmt::Display for ValorNaoNumerico {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", tr!("funcionário {} (CPF {}): {} não numérico '{}'", self.funcionario, self.cpf, self.campo, self.valor))
+    }
+}
+
+/// Separador decimal dos valores, escolhido por `--decimal-comma`, `--decimal-dot` e `--out-decimal`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decimal {
+    /// `1.234,56`: vírgula decimal e ponto como separador de milhar.
+    Virgula,
+    /// `1,234.56`: ponto decimal e vírgula como separador de milhar.
+    Ponto,
+}
+
+impl Decimal {
+    pub const NOMES: [&'static str; 2] = ["comma", "dot"];
+
+    pub fn from_nome(nome: &str) -> Option<Decimal> {
+        match nome {
+            "comma" => Some(Decimal::Virgula),
+            "dot" => Some(Decimal::Ponto),
+            _ => None,
+        }
+    }
+}
+
+/// Escrita de `Valor` e `MetaPremio` escolhida por `--money-format`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormatoMonetario {
+    /// O texto exatamente como veio no XML.
+    Original,
+    /// `1234.56`: ponto decimal, duas casas e sem separador de milhar.
+    Decimal,
+    /// `1.234,56`.
+    PtBr,
+    /// `R$ 1.234,56`.
+    Real,
+}
+
+impl FormatoMonetario {
+    pub const NOMES: [&'static str; 4] = ["raw", "decimal", "pt-br", "brl"];
+
+    pub fn from_nome(nome: &str) -> Option<FormatoMonetario> {
+        match nome {
+            "raw" => Some(FormatoMonetario::Original),
+            "decimal" => Some(FormatoMonetario::Decimal),
+            "pt-br" => Some(FormatoMonetario::PtBr),
+            "brl" => Some(FormatoMonetario::Real),
+            _ => None,
+        }
+    }
+
+    /// Texto do valor arredondado para duas casas. [`FormatoMonetario::Original`] grava o
+    /// texto do XML e não passa por aqui; se passar, fica como em `Decimal`.
+    pub fn formatar(self, valor: Quantia) -> String {
+        let texto = format!("{:.2}", valor);
+        match self {
+            FormatoMonetario::Original | FormatoMonetario::Decimal => texto,
+            FormatoMonetario::PtBr => com_milhares(&texto),
+            FormatoMonetario::Real => match com_milhares(&texto).strip_prefix('-') {
+                Some(positivo) => format!("-{} {}", SIMBOLO_REAL, positivo),
+                None => format!("{} {}", SIMBOLO_REAL, com_milhares(&texto)),
+            },
+        }
+    }
+}
+
+/// `-1234567.89` como `-1.234.567,89`.
+fn com_milhares(texto: &str) -> String {
+    let (sinal, texto) = match texto.strip_prefix('-') {
+        Some(resto) => ("-", resto),
+        None => ("", texto),
+    };
+    let (inteira, decimais) = texto.split_once('.').unwrap_or((texto, "00"));
+    let mut agrupada = String::new();
+    for (indice, digito) in inteira.chars().enumerate() {
+        if indice > 0 && (inteira.len() - indice) % 3 == 0 {
+            agrupada.push('.');
+        }
+        agrupada.push(digito);
+    }
+    format!("{}{},{}", sinal, agrupada, decimais)
+}
+
+/// Interpreta um valor monetário, aceitando o símbolo `R$` e separadores de milhar.
+///
+/// Quando há vírgula e ponto, o último deles é o separador decimal; só com vírgula, ela é
+/// o separador decimal; só com ponto, vale a notação do Rust (`1234.56`).
+pub fn parse_valor(texto: &str) -> Option<f64> {
+    parse_valor_decimal(texto, None)
+}
+
+/// Como [`parse_valor`], mas com o separador decimal informado em vez de deduzido do texto.
+///
+/// Com [`Decimal::Virgula`], `1.234` é lido como mil duzentos e trinta e quatro, e não
+/// como um vírgula duzentos e trinta e quatro. `NaN`, `inf` e a notação científica (`1e3`),
+/// aceitos pelo `f64`, não são valores.
+pub fn parse_valor_decimal(texto: &str, decimal: Option<Decimal>) -> Option<f64> {
+    Some(normalizar_numero(texto, decimal)).filter(|texto| eh_decimal(texto))?.parse::<f64>().ok().filter(|valor| valor.is_finite())
+}
+
+/// Texto com sinal opcional, dígitos e, talvez, ponto decimal (`-1234.5`), sem expoente.
+fn eh_decimal(texto: &str) -> bool {
+    let texto = texto.strip_prefix(['-', '+']).unwrap_or(texto);
+    let (inteira, fracao) = texto.split_once('.').unwrap_or((texto, ""));
+    !(inteira.is_empty() && fracao.is_empty()) && inteira.chars().chain(fracao.chars()).all(|c| c.is_ascii_digit())
+}
+
+/// Texto do valor sem `R$` e sem separador de milhar, com ponto decimal.
+fn normalizar_numero(texto: &str, decimal: Option<Decimal>) -> String {
+    let texto = texto.trim();
+    let texto = texto.strip_prefix(SIMBOLO_REAL).unwrap_or(texto).trim();
+
+    match (decimal, texto.rfind(','), texto.rfind('.')) {
+        (Some(Decimal::Virgula), _, _) => texto.replace('.', "").replace(',', "."),
+        (Some(Decimal::Ponto), _, _) => texto.replace(',', ""),
+        (None, Some(virgula), Some(ponto)) if virgula > ponto => texto.replace('.', "").replace(',', "."),
+        (None, Some(_), Some(_)) => texto.replace(',', ""),
+        (None, Some(_), None) => texto.replace(',', "."),
+        _ => texto.to_string(),
+    }
+}
+
+/// Casas decimais guardadas em [`Quantia`].
+const CASAS_QUANTIA: u32 = 8;
+
+/// Valor de `1` em [`Quantia`].
+const UNIDADE: i128 = 10i128.pow(CASAS_QUANTIA);
+
+/// Valor em decimal exato, usado nos totais de `Valor` e `MetaPremio`.
+///
+/// Somas em `f64` acumulam erros de arredondamento em arquivos grandes (`0.1 + 0.2` não dá
+/// `0.3`); aqui o valor é guardado como um inteiro de centésimos de milionésimo, lido
+/// direto do texto do XML, e a soma é exata. Na formatação (`{:.2}`), o arredondamento é o
+/// comercial: metade para longe do zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Quantia(i128);
+
+impl Quantia {
+    pub const ZERO: Quantia = Quantia(0);
+
+    /// Como [`parse_valor_decimal`], sem passar por `f64`. Casas além da oitava são arredondadas.
+    pub fn ler(texto: &str, decimal: Option<Decimal>) -> Option<Quantia> {
+        Quantia::exata(&normalizar_numero(texto, decimal))
+    }
+
+    /// Texto aceito por [`eh_decimal`].
+    fn exata(texto: &str) -> Option<Quantia> {
+        if !eh_decimal(texto) {
+            return None;
+        }
+        let (negativo, texto) = match texto.strip_prefix('-') {
+            Some(resto) => (true, resto),
+            None => (false, texto.strip_prefix('+').unwrap_or(texto)),
+        };
+        let (inteira, fracao) = texto.split_once('.').unwrap_or((texto, ""));
+
+        let mut unidades: i128 = 0;
+        for digito in inteira.bytes().chain(fracao.bytes().chain(std::iter::repeat(b'0')).take(CASAS_QUANTIA as usize)) {
+            unidades = unidades.checked_mul(10)?.checked_add(i128::from(digito - b'0'))?;
+        }
+        if fracao.as_bytes().get(CASAS_QUANTIA as usize).is_some_and(|digito| *digito >= b'5') {
+            unidades = unidades.checked_add(1)?;
+        }
+        Some(Quantia(if negativo { -unidades } else { unidades }))
+    }
+
+    /// Texto com todas as casas da quantia, sem os zeros à direita depois da segunda
+    /// (`0.505`, `10.50`), para gravar uma soma sem arredondá-la.
+    pub fn completa(self) -> String {
+        let texto = format!("{:.*}", CASAS_QUANTIA as usize, self);
+        let minimo = texto.len() - CASAS_QUANTIA as usize + 2;
+        texto[..texto.trim_end_matches('0').len().max(minimo)].to_string()
+    }
+
+    pub fn from_f64(valor: f64) -> Quantia {
+        Quantia((valor * UNIDADE as f64).round() as i128)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / UNIDADE as f64
+    }
+
+    /// Valor multiplicado por `fator` (uma taxa de câmbio), arredondado na oitava casa.
+    pub fn multiplicar(self, fator: f64) -> Quantia {
+        let fator = Quantia::from_f64(fator);
+        self.vezes(fator).unwrap_or_else(|| Quantia::from_f64(self.to_f64() * fator.to_f64()))
+    }
+
+    /// Produto arredondado na oitava casa; `None` quando ele não cabe na `Quantia`.
+    pub fn vezes(self, outra: Quantia) -> Option<Quantia> {
+        Some(Quantia(dividir_arredondando(self.0.checked_mul(outra.0)?, UNIDADE)))
+    }
+
+    /// Quociente arredondado na oitava casa; `None` na divisão por zero ou quando ele não
+    /// cabe na `Quantia`.
+    pub fn dividida_por(self, outra: Quantia) -> Option<Quantia> {
+        if outra.0 == 0 {
+            return None;
+        }
+        Some(Quantia(dividir_arredondando(self.0.checked_mul(UNIDADE)?, outra.0)))
+    }
+
+    /// Valor dividido em `partes` iguais (ex.: a média de `partes` valores), arredondado na
+    /// oitava casa.
+    pub fn dividir(self, partes: usize) -> Quantia {
+        Quantia(dividir_arredondando(self.0, (partes as i128).max(1)))
+    }
+
+    /// Valor em centavos, arredondado.
+    pub fn centavos(self) -> i128 {
+        dividir_arredondando(self.0, 10i128.pow(CASAS_QUANTIA - 2))
+    }
+}
+
+/// `dividendo / divisor` arredondado com metade para longe do zero.
+fn dividir_arredondando(dividendo: i128, divisor: i128) -> i128 {
+    let quociente = dividendo / divisor;
+    let resto = (dividendo % divisor).abs();
+    if resto >= divisor.abs() - resto {
+        quociente + dividendo.signum() * divisor.signum()
+    } else {
+        quociente
+    }
+}
+
+/// Sem precisão no formato, duas casas decimais, como os totais em `f64` eram gravados.
+impl fmt::Display for Quantia {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let casas = f.precision().map_or(2, |casas| casas.min(CASAS_QUANTIA as usize)) as u32;
+        let valor = dividir_arredondando(self.0, 10i128.pow(CASAS_QUANTIA - casas));
+        let sinal = if valor < 0 { "-" } else { "" };
+        let (inteira, fracao) = (valor.abs() / 10i128.pow(casas), valor.abs() % 10i128.pow(casas));
+        if casas == 0 {
+            write!(f, "{}{}", sinal, inteira)
+        } else {
+            write!(f, "{}{}.{:0casas$}", sinal, inteira, fracao, casas = casas as usize)
+        }
+    }
+}
+
+/// Gravada como número nos JSON (resumo, manifest), como os totais em `f64`.
+impl Serialize for Quantia {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+impl Add for Quantia {
+    type Output = Quantia;
+
+    fn add(self, outra: Quantia) -> Quantia {
+        Quantia(self.0.saturating_add(outra.0))
+    }
+}
+
+impl AddAssign for Quantia {
+    fn add_assign(&mut self, outra: Quantia) {
+        *self = *self + outra;
+    }
+}
+
+impl Sub for Quantia {
+    type Output = Quantia;
+
+    fn sub(self, outra: Quantia) -> Quantia {
+        Quantia(self.0.saturating_sub(outra.0))
+    }
+}
+
+impl Neg for Quantia {
+    type Output = Quantia;
+
+    fn neg(self) -> Quantia {
+        Quantia(-self.0)
+    }
+}
+
+impl Sum for Quantia {
+    fn sum<I: Iterator<Item = Quantia>>(valores: I) -> Quantia {
+        valores.fold(Quantia::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Quantia> for Quantia {
+    fn sum<I: Iterator<Item = &'a Quantia>>(valores: I) -> Quantia {
+        valores.copied().sum()
+    }
+}
+
+/// Texto do número com o separador decimal escolhido e sem separador de milhar.
+pub fn formatar_valor(valor: f64, decimal: Decimal) -> String {
+    let texto = valor.to_string();
+    match decimal {
+        Decimal::Virgula => texto.replace('.', ","),
+        Decimal::Ponto => texto,
+    }
+}
+
+/// Texto gravado na coluna `Valor`: o próprio XML quando já é numérico, ou o número
+/// obtido por [`parse_valor`] quando vem formatado (ex.: `R$ 1.234,56` vira `1234.56`).
+pub fn normalizar_valor(texto: &str) -> String {
+    if eh_decimal(texto.trim()) {
+        return texto.to_string();
+    }
+    match parse_valor(texto) {
+        Some(valor) => valor.to_string(),
+        None => texto.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valor_com_simbolo_do_real() {
+        assert_eq!(parse_valor("R$ 1.234,56"), Some(1234.56));
+        assert_eq!(parse_valor("R$1234.56"), Some(1234.56));
+        assert_eq!(parse_valor("  R$  1.234,56 "), Some(1234.56));
+        assert_eq!(normalizar_valor("R$ 1.234,56"), "1234.56");
+        assert_eq!(normalizar_valor("R$1234.56"), "1234.56");
+    }
+
+    #[test]
+    fn valor_sem_simbolo() {
+        assert_eq!(parse_valor("1234.56"), Some(1234.56));
+        assert_eq!(parse_valor("1234,56"), Some(1234.56));
+        assert_eq!(parse_valor("1,234.56"), Some(1234.56));
+        assert_eq!(parse_valor("-10"), Some(-10.0));
+        assert_eq!(normalizar_valor("1234.56"), "1234.56");
+        assert_eq!(normalizar_valor("0100"), "0100");
+    }
+
+    #[test]
+    fn valor_nao_numerico() {
+        for texto in ["", "abc", "R$", "NaN", "nan", "inf", "-inf", "infinity", "1e3", "1E3", "2.5e-2", "1,5e2"] {
+            assert_eq!(parse_valor(texto), None, "{}", texto);
+            assert_eq!(Quantia::ler(texto, None), None, "{}", texto);
+            assert_eq!(normalizar_valor(texto), texto);
+        }
+    }
+
+    #[test]
+    fn quantia_arredonda_metade_para_longe_do_zero() {
+        let quantia = |texto| Quantia::ler(texto, None).unwrap();
+        assert_eq!(format!("{:.2}", quantia("1.005")), "1.01");
+        assert_eq!(format!("{:.2}", quantia("-1.005")), "-1.01");
+        assert_eq!(format!("{:.2}", quantia("1.004")), "1.00");
+        assert_eq!(format!("{}", quantia("R$ 1.234,5")), "1234.50");
+    }
+
+    #[test]
+    fn quantia_completa() {
+        let quantia = |texto| Quantia::ler(texto, None).unwrap();
+        assert_eq!(quantia("0.505").completa(), "0.505");
+        assert_eq!(quantia("10.5").completa(), "10.50");
+        assert_eq!(quantia("-3").completa(), "-3.00");
+        assert_eq!(quantia("0.00000001").completa(), "0.00000001");
+    }
+
+    #[test]
+    fn quantia_opera_sem_desvio() {
+        let quantia = |texto| Quantia::ler(texto, None).unwrap();
+        let soma: Quantia = std::iter::repeat_n(quantia("0.10"), 10).sum();
+        assert_eq!(soma, quantia("1"));
+        assert_eq!(quantia("0.1").vezes(quantia("3")), Some(quantia("0.3")));
+        assert_eq!(quantia("1").dividida_por(quantia("3")), Some(quantia("0.33333333")));
+        assert_eq!(quantia("2").dividida_por(quantia("-3")), Some(quantia("-0.66666667")));
+        assert_eq!(quantia("1").dividida_por(Quantia::ZERO), None);
+        assert_eq!(quantia("10").dividir(4), quantia("2.5"));
+    }
+}
@@ -0,0 +1,144 @@
+use colored::*;
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use converterxmlcsv::valor::parse_valor_decimal;
+use converterxmlcsv::{Destino, ErroConversor, Opcoes};
+
+use crate::compactado::{self, Fonte};
+use crate::{abrir_entrada, converter_entrada, validar_esquema, Convertido, ErroSaida, OpcoesCli, SAIDA_ERRO_CONVERSAO, SAIDA_FALHA_PARCIAL, SAIDA_SEM_ARQUIVOS};
+
+/// Colunas que precisam ser números; `MetaPremio` vazia é aceita.
+const COLUNAS_NUMERICAS: [&str; 2] = ["Valor", "MetaPremio"];
+
+/// Problemas exibidos por arquivo; os demais são apenas contados.
+const PROBLEMAS_EXIBIDOS: usize = 20;
+
+/// Destino de `check`: não grava nada, apenas confere os valores numéricos e os CPFs
+/// repetidos numa mesma empresa.
+struct Verificacao<'a> {
+    opcoes: &'a Opcoes,
+    numericas: Vec<(usize, &'static str)>,
+    cnpj: Option<usize>,
+    cpf: Option<usize>,
+    vistos: HashSet<(String, String)>,
+    /// Funcionário atual (a partir de 1), na numeração de [`DocumentoInvalido`](converterxmlcsv::DocumentoInvalido)
+    /// quando não há filtros.
+    funcionario: usize,
+    problemas: Vec<String>,
+}
+
+impl<'a> Verificacao<'a> {
+    fn new(opcoes: &'a Opcoes) -> Verificacao<'a> {
+        Verificacao { opcoes, numericas: Vec::new(), cnpj: None, cpf: None, vistos: HashSet::new(), funcionario: 0, problemas: Vec::new() }
+    }
+}
+
+impl Destino for Verificacao<'_> {
+    fn iniciar(&mut self, cabecalho: &[&str]) -> Result<(), ErroConversor> {
+        let posicao = |nome: &str| cabecalho.iter().position(|coluna| *coluna == nome);
+        self.numericas = COLUNAS_NUMERICAS.iter().filter_map(|nome| Some((posicao(nome)?, *nome))).collect();
+        self.cnpj = posicao("CNPJ");
+        self.cpf = posicao("CPF");
+        Ok(())
+    }
+
+    fn gravar(&mut self, registro: &[&str]) -> Result<(), ErroConversor> {
+        self.funcionario += 1;
+        for (indice, nome) in &self.numericas {
+            let texto = registro.get(*indice).copied().unwrap_or_default();
+            let vazio_aceito = *nome == "MetaPremio" && texto.trim().is_empty();
+            if !vazio_aceito && parse_valor_decimal(texto, self.opcoes.decimal_entrada).is_none() {
+                self.problemas.push(tr!("funcionário {}: {} não numérico '{}'", self.funcionario, nome, texto));
+            }
+        }
+        if let Some(cpf) = self.cpf.and_then(|indice| registro.get(indice)) {
+            let digitos: String = cpf.chars().filter(char::is_ascii_digit).collect();
+            let cnpj = self.cnpj.and_then(|indice| registro.get(indice)).copied().unwrap_or_default();
+            if !digitos.is_empty() && !self.vistos.insert((cnpj.to_string(), digitos)) {
+                self.problemas.push(tr!("funcionário {}: CPF {} repetido na empresa {}", self.funcionario, cpf, cnpj));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `check`: valida cada arquivo sem gravar nada e informa se passou ou falhou.
+///
+/// Um arquivo falha quando não pode ser lido ou convertido (XML mal formado, tipo não
+/// reconhecido, violações do `--xsd`), quando tem CPF ou CNPJ com dígitos verificadores
+/// inválidos, valores que não são números ou funcionários repetidos.
+pub fn verificar(arquivos: &[PathBuf], opcoes: &OpcoesCli) -> Result<(), Box<dyn Error>> {
+    if arquivos.is_empty() {
+        return Err(ErroSaida::criar(SAIDA_SEM_ARQUIVOS, tr!("Não foram encontrados arquivos XML na pasta.")));
+    }
+    let mut falhas = 0;
+    for arquivo in arquivos {
+        let (problemas, avisos) = match verificar_arquivo(arquivo, opcoes) {
+            Ok(resultado) => resultado,
+            Err(erro) => (vec![erro.to_string()], Vec::new()),
+        };
+        if problemas.is_empty() {
+            println!("{} {}", "OK".bright_green().bold(), arquivo.display());
+        } else {
+            falhas += 1;
+            println!("{} {}", tr!("FALHOU").bright_red().bold(), arquivo.display());
+            for problema in problemas.iter().take(PROBLEMAS_EXIBIDOS) {
+                println!("  - {}", problema);
+            }
+            if problemas.len() > PROBLEMAS_EXIBIDOS {
+                println!("  {}", tr!("... e mais {}.", problemas.len() - PROBLEMAS_EXIBIDOS));
+            }
+        }
+        for aviso in avisos {
+            println!("  {}", tr!("Atenção: {}", aviso).bright_yellow());
+        }
+    }
+
+    println!("{}", tr!("{} de {} arquivo(s) aprovado(s).", arquivos.len() - falhas, arquivos.len()).bright_green());
+    if falhas > 0 {
+        let codigo = if falhas == arquivos.len() { SAIDA_ERRO_CONVERSAO } else { SAIDA_FALHA_PARCIAL };
+        return Err(ErroSaida::criar(codigo, tr!("{} arquivo(s) não passaram na verificação.", falhas)));
+    }
+    Ok(())
+}
+
+/// Problemas e avisos de um arquivo, somando os XML de um `.zip`.
+fn verificar_arquivo(arquivo: &Path, opcoes: &OpcoesCli) -> Result<(Vec<String>, Vec<String>), Box<dyn Error>> {
+    let mut problemas = Vec::new();
+    let mut avisos = Vec::new();
+    for fonte in compactado::fontes(arquivo)? {
+        let prefixo = match &fonte {
+            Fonte::Zip { nome, .. } => format!("{}: ", nome),
+            _ => String::new(),
+        };
+        match verificar_fonte(&fonte, opcoes) {
+            Ok((novos, novos_avisos)) => {
+                problemas.extend(novos.into_iter().map(|problema| format!("{}{}", prefixo, problema)));
+                avisos.extend(novos_avisos.into_iter().map(|aviso| format!("{}{}", prefixo, aviso)));
+            }
+            Err(erro) => problemas.push(format!("{}{}", prefixo, erro)),
+        }
+    }
+    Ok((problemas, avisos))
+}
+
+fn verificar_fonte(fonte: &Fonte, opcoes: &OpcoesCli) -> Result<(Vec<String>, Vec<String>), Box<dyn Error>> {
+    if let Some(esquema) = &opcoes.esquema {
+        validar_esquema(esquema, fonte.abrir(None)?)?;
+    }
+    let abrir = || -> Result<_, Box<dyn Error>> { Ok(fonte.abrir(None)?) };
+    let entrada = abrir_entrada(abrir, Some(&fonte.caminho()), opcoes)?;
+    let mut verificacao = Verificacao::new(&opcoes.conversao);
+    let convertido = converter_entrada(entrada, opcoes, &mut verificacao)?;
+
+    let mut problemas: Vec<String> = Vec::new();
+    let mut avisos = Vec::new();
+    if let Convertido::Tipado(resumo) = &convertido {
+        problemas.extend(resumo.documentos_invalidos.iter().map(ToString::to_string));
+        avisos.extend(resumo.avisos.iter().map(|aviso| aviso.mensagem.clone()));
+    }
+    problemas.extend(verificacao.problemas);
+    Ok((problemas, avisos))
+}
@@ -0,0 +1,133 @@
+//! Execução pela linha de comando: `--output` e `--stdin` com as opções que gravam o CSV em
+//! partes ou com totais, a coluna de `--currency-column`, o comando de `--after-hook` e o
+//! idioma de `--lang`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn fixture(nome: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(nome)
+}
+
+fn pasta(nome: &str) -> PathBuf {
+    let pasta = std::env::temp_dir().join(format!("converterxmlcsv-cli-{}-{}", nome, std::process::id()));
+    let _ = fs::remove_dir_all(&pasta);
+    fs::create_dir_all(&pasta).unwrap();
+    pasta
+}
+
+fn executar(pasta: &Path, argumentos: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_converterxmlcsv")).current_dir(pasta).args(argumentos).output().unwrap()
+}
+
+#[test]
+fn output_com_totais_e_partes() {
+    let pasta = pasta("partes");
+    let xml = fixture("comissao_varios_funcionarios.xml");
+    let saida = executar(&pasta, &[xml.to_str().unwrap(), "-o", "saida.csv", "--with-totals", "--max-rows-per-file", "2", "--lang", "pt"]);
+    assert!(saida.status.success(), "{}", String::from_utf8_lossy(&saida.stderr));
+
+    let primeira = fs::read_to_string(pasta.join("saida_part1.csv")).unwrap();
+    assert!(primeira.ends_with("\nTotal;;;;2;1234.66;100.20\n"), "{}", primeira);
+    let segunda = fs::read_to_string(pasta.join("saida_part2.csv")).unwrap();
+    assert!(segunda.ends_with("\nTotal;;;;1;0.20;0.10\n"), "{}", segunda);
+    assert!(!pasta.join("saida.csv").exists());
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[test]
+fn saida_padrao_recusa_totais() {
+    let pasta = pasta("stdout");
+    let xml = fixture("comissao_um_funcionario.xml");
+    let saida = executar(&pasta, &[xml.to_str().unwrap(), "-o", "-", "--with-totals", "--lang", "pt"]);
+    assert_eq!(saida.status.code(), Some(1));
+    assert!(saida.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&saida.stderr).contains("--with-totals"));
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[test]
+fn xml_invalido_sai_com_codigo_de_conversao() {
+    let pasta = pasta("invalido");
+    fs::write(pasta.join("comissao.xml"), "<Comissao><Empresa><CNPJ>11222333000181</CNPJ>").unwrap();
+    let saida = executar(&pasta, &["comissao.xml", "-o", "saida.csv", "--lang", "pt"]);
+    assert_eq!(saida.status.code(), Some(3), "{}", String::from_utf8_lossy(&saida.stderr));
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn after_hook_nao_executa_o_nome_do_arquivo() {
+    let pasta = pasta("hook");
+    fs::copy(fixture("comissao_um_funcionario.xml"), pasta.join("a;touch invadido.xml")).unwrap();
+    let saida = executar(&pasta, &["a;touch invadido.xml", "--after-hook", "cp {arquivo} copia.csv", "--lang", "pt"]);
+    assert!(saida.status.success(), "{}", String::from_utf8_lossy(&saida.stderr));
+    assert!(pasta.join("copia.csv").exists());
+    assert!(!pasta.join("invadido").exists());
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[test]
+fn resumo_de_avisos_conta_documentos_e_valores_invalidos() {
+    let pasta = pasta("avisos");
+    let funcionarios = [("52998224725", "abc"), ("52998224725", "xyz"), ("52998224724", "10.00")]
+        .map(|(cpf, valor)| format!("<Funcionario><CPF>{}</CPF><Valor>{}</Valor></Funcionario>", cpf, valor))
+        .concat();
+    let xml = format!("<Comissao><Empresa><Fantasia>Loja</Fantasia><Razao>Loja Ltda</Razao><CNPJ>11222333000181</CNPJ><MesAno>01/2024</MesAno>{}</Empresa></Comissao>", funcionarios);
+    fs::write(pasta.join("comissao.xml"), xml).unwrap();
+
+    let saida = executar(&pasta, &["comissao.xml", "--lang", "pt"]);
+    assert!(saida.status.success(), "{}", String::from_utf8_lossy(&saida.stderr));
+    let console = String::from_utf8_lossy(&saida.stdout);
+    assert!(console.contains("==== Resumo de avisos (3) ===="), "{}", console);
+    assert!(console.contains("  2 valores não numéricos"), "{}", console);
+    assert!(console.contains("  1 CPFs inválidos"), "{}", console);
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[test]
+fn coluna_moeda_apenas_com_currency_column() {
+    let pasta = pasta("moeda");
+    let xml = "<Comissao><Empresa><Fantasia>Loja</Fantasia><Razao>Loja Ltda</Razao><CNPJ>11222333000181</CNPJ><MesAno>01/2024</MesAno><Funcionario><CPF>52998224725</CPF><Valor moeda=\"USD\">10.00</Valor><MetaPremio>5</MetaPremio></Funcionario></Empresa></Comissao>";
+    fs::write(pasta.join("comissao.xml"), xml).unwrap();
+
+    let saida = executar(&pasta, &["comissao.xml", "-o", "padrao.csv", "--lang", "pt"]);
+    assert!(saida.status.success(), "{}", String::from_utf8_lossy(&saida.stderr));
+    let padrao = fs::read_to_string(pasta.join("padrao.csv")).unwrap();
+    assert!(padrao.starts_with("Fantasia;Razao;CNPJ;MesAno;CPF;Valor;MetaPremio\n"), "{}", padrao);
+
+    let saida = executar(&pasta, &["comissao.xml", "-o", "moeda.csv", "--currency-column", "--lang", "pt"]);
+    assert!(saida.status.success(), "{}", String::from_utf8_lossy(&saida.stderr));
+    let moeda = fs::read_to_string(pasta.join("moeda.csv")).unwrap();
+    assert!(moeda.starts_with("Fantasia;Razao;CNPJ;MesAno;CPF;Valor;MetaPremio;Moeda\n"), "{}", moeda);
+    assert!(moeda.contains(";52998224725;10.00;5;USD\n"), "{}", moeda);
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[test]
+fn columns_desconhecida_lista_as_colunas_calculadas() {
+    let pasta = pasta("colunas");
+    let xml = fixture("comissao_um_funcionario.xml");
+    let saida = executar(&pasta, &[xml.to_str().unwrap(), "-o", "saida.csv", "--computed", "Dobro=Valor*2", "--columns", "CPF,Nada", "--lang", "pt"]);
+    assert_eq!(saida.status.code(), Some(1));
+    let erro = String::from_utf8_lossy(&saida.stderr);
+    assert!(erro.contains("'Nada'") && erro.contains("Alteracao, Dobro."), "{}", erro);
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[test]
+fn ajuda_e_html_no_idioma_de_lang() {
+    let pasta = pasta("idioma");
+    let ajuda = executar(&pasta, &["--lang", "en", "--help"]);
+    let ajuda = String::from_utf8_lossy(&ajuda.stdout);
+    assert!(ajuda.contains("Converts commission, voucher, vacation or 13th salary XML files to CSV"), "{}", ajuda);
+    assert!(ajuda.contains("Exit codes: 0 success"), "{}", ajuda);
+
+    let xml = fixture("comissao_varios_funcionarios.xml");
+    let saida = executar(&pasta, &[xml.to_str().unwrap(), "--format", "html", "-o", "saida.html", "--lang=es"]);
+    assert!(saida.status.success(), "{}", String::from_utf8_lossy(&saida.stderr));
+    let html = fs::read_to_string(pasta.join("saida.html")).unwrap();
+    assert!(html.contains("<html lang=\"es\">") && html.contains("Total (3 empleado(s))"), "{}", html);
+    fs::remove_dir_all(&pasta).unwrap();
+}
@@ -0,0 +1,32 @@
+//! `--dedupe-strategy sum-values`: soma dos funcionários repetidos sem arredondar e sem
+//! esconder valores que não são números.
+
+use converterxmlcsv::{converter_comissao, Deduplicacao, ErroConversor, Opcoes};
+
+fn xml(valores: &[&str]) -> String {
+    let funcionarios: String = valores.iter().map(|valor| format!("<Funcionario><CPF>52998224725</CPF><Valor>{}</Valor></Funcionario>", valor)).collect();
+    format!("<Comissao><Empresa><Fantasia>Loja</Fantasia><Razao>Loja Ltda</Razao><CNPJ>11222333000181</CNPJ><MesAno>01/2024</MesAno>{}</Empresa></Comissao>", funcionarios)
+}
+
+fn somar(valores: &[&str]) -> Result<String, ErroConversor> {
+    let opcoes = Opcoes { deduplicar: Some(Deduplicacao::Somar), ..Opcoes::default() };
+    let mut csv = Vec::new();
+    converter_comissao(xml(valores).as_bytes(), &mut csv, &opcoes)?;
+    Ok(String::from_utf8(csv).unwrap())
+}
+
+#[test]
+fn soma_sem_arredondar_os_centavos() {
+    let csv = somar(&["0.5", "0.005"]).unwrap();
+    let linhas: Vec<&str> = csv.lines().collect();
+    assert_eq!(linhas.len(), 2, "{}", csv);
+    assert!(linhas[1].contains(";0.505;"), "{}", csv);
+}
+
+#[test]
+fn valor_nao_numerico_nao_vira_zero() {
+    match somar(&["abc", "10.00"]) {
+        Err(ErroConversor::ValorInvalido(mensagem)) => assert!(mensagem.contains("'abc'"), "{}", mensagem),
+        outro => panic!("esperado erro de valor não numérico: {:?}", outro),
+    }
+}
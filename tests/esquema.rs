@@ -0,0 +1,142 @@
+//! Validação de `--xsd`: um XML de acordo com o esquema, as violações com a posição de cada
+//! uma e os recursos do XSD que não são verificados.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use converterxmlcsv::{Esquema, Violacao};
+
+const XSD: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:simpleType name="CPF">
+    <xs:restriction base="xs:string">
+      <xs:length value="11"/>
+      <xs:pattern value="[0-9]{11}"/>
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:simpleType name="Situacao">
+    <xs:restriction base="xs:string">
+      <xs:enumeration value="Ativo"/>
+      <xs:enumeration value="Afastado"/>
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:complexType name="Funcionario">
+    <xs:sequence>
+      <xs:element name="CPF" type="CPF"/>
+      <xs:element name="Valor">
+        <xs:simpleType>
+          <xs:restriction base="xs:decimal">
+            <xs:minInclusive value="0"/>
+          </xs:restriction>
+        </xs:simpleType>
+      </xs:element>
+      <xs:element name="Situacao" type="Situacao" minOccurs="0"/>
+    </xs:sequence>
+    <xs:attribute name="id" type="xs:integer" use="required"/>
+  </xs:complexType>
+  <xs:element name="Comissao">
+    <xs:complexType>
+      <xs:sequence>
+        <xs:element name="CNPJ" type="xs:string"/>
+        <xs:element name="Data" type="xs:date"/>
+        <xs:element name="Funcionario" type="Funcionario" maxOccurs="unbounded"/>
+      </xs:sequence>
+    </xs:complexType>
+  </xs:element>
+</xs:schema>
+"#;
+
+fn pasta(nome: &str) -> PathBuf {
+    let pasta = std::env::temp_dir().join(format!("converterxmlcsv-esquema-{}-{}", nome, std::process::id()));
+    let _ = fs::remove_dir_all(&pasta);
+    fs::create_dir_all(&pasta).unwrap();
+    pasta
+}
+
+fn carregar(pasta: &Path, xsd: &str) -> Esquema {
+    let caminho = pasta.join("comissao.xsd");
+    fs::write(&caminho, xsd).unwrap();
+    Esquema::carregar(&caminho).expect("XSD válido")
+}
+
+fn validar(esquema: &Esquema, xml: &str) -> Vec<Violacao> {
+    esquema.validar(xml.as_bytes()).expect("leitura do XML")
+}
+
+/// Linha e coluna de cada violação.
+fn posicoes(violacoes: &[Violacao]) -> Vec<(usize, usize)> {
+    violacoes.iter().map(|violacao| (violacao.linha, violacao.coluna)).collect()
+}
+
+#[test]
+fn xml_valido() {
+    let pasta = pasta("valido");
+    let esquema = carregar(&pasta, XSD);
+    let xml = "<Comissao>\n  <CNPJ>11222333000181</CNPJ>\n  <Data>2024-02-01</Data>\n  <Funcionario id=\"1\"><CPF>52998224725</CPF><Valor>1234.56</Valor><Situacao>Ativo</Situacao></Funcionario>\n  <Funcionario id=\"2\"><CPF>11144477735</CPF><Valor>0</Valor></Funcionario>\n</Comissao>\n";
+    assert_eq!(validar(&esquema, xml), []);
+    assert_eq!(esquema.ignorados.len(), 1, "{:?}", esquema.ignorados);
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[test]
+fn violacoes_com_a_posicao() {
+    let pasta = pasta("violacoes");
+    let esquema = carregar(&pasta, XSD);
+    let xml = concat!(
+        "<Comissao>\n",
+        "  <CNPJ>11222333000181</CNPJ>\n",
+        "  <Data>01/02/2024</Data>\n",
+        "  <Funcionario><CPF>123</CPF><Valor>-1</Valor></Funcionario>\n",
+        "  <Funcionario id=\"x\"><Valor>abc</Valor><CPF>52998224725</CPF><Situacao>Demitido</Situacao></Funcionario>\n",
+        "  <Extra/>\n",
+        "</Comissao>\n",
+    );
+    // Data; id ausente, CPF curto e Valor negativo; id não numérico, CPF faltando antes do
+    // Valor, Valor não numérico, CPF fora de ordem e Situacao fora da lista; e <Extra>.
+    assert_eq!(
+        posicoes(&validar(&esquema, xml)),
+        [(3, 3), (4, 3), (4, 16), (4, 30), (5, 3), (5, 23), (5, 23), (5, 41), (5, 63), (6, 3)]
+    );
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[test]
+fn elemento_obrigatorio_e_raiz() {
+    let pasta = pasta("obrigatorio");
+    let esquema = carregar(&pasta, XSD);
+    let violacoes = validar(&esquema, "<Comissao>\n  <CNPJ>11222333000181</CNPJ>\n  <Data>2024-02-01</Data>\n</Comissao>\n");
+    assert_eq!(violacoes.len(), 1, "{:#?}", violacoes);
+    assert!(violacoes[0].mensagem.contains("Funcionario"), "{:#?}", violacoes);
+
+    let violacoes = validar(&esquema, "<Vales/>");
+    assert_eq!(posicoes(&violacoes), [(1, 1)]);
+    assert!(violacoes[0].mensagem.contains("Comissao"), "{:#?}", violacoes);
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[test]
+fn xml_mal_formado() {
+    let pasta = pasta("mal-formado");
+    let esquema = carregar(&pasta, XSD);
+    let violacoes = validar(&esquema, "<Comissao>\n  <CNPJ>11222333000181</Data>\n</Comissao>\n");
+    assert!(!violacoes.is_empty());
+    assert_eq!(violacoes.last().unwrap().linha, 2, "{:#?}", violacoes);
+    fs::remove_dir_all(&pasta).unwrap();
+}
+
+#[test]
+fn xsd_invalido() {
+    let pasta = pasta("xsd-invalido");
+    for xsd in [
+        "<Comissao/>",
+        r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"/>"#,
+        r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"><xs:element name="Comissao" type="Desconhecido"/></xs:schema>"#,
+        r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"><xs:element name="Comissao" minOccurs="um"/></xs:schema>"#,
+    ] {
+        let caminho = pasta.join("invalido.xsd");
+        fs::write(&caminho, xsd).unwrap();
+        assert!(Esquema::carregar(&caminho).is_err(), "{}", xsd);
+    }
+    assert!(Esquema::carregar(&pasta.join("inexistente.xsd")).is_err());
+    fs::remove_dir_all(&pasta).unwrap();
+}
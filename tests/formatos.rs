@@ -0,0 +1,78 @@
+//! `Valor` e `MetaPremio` gravados como números no JSON e no SQL, qualquer que seja o
+//! formato monetário escolhido para o CSV, e as colunas do `CREATE TABLE` do SQL.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use converterxmlcsv::sql::tabela_criada;
+use converterxmlcsv::{abrir_xml, converter_empresas, Destino, FormatoMonetario, Opcoes, SaidaJson, SaidaSql};
+
+fn converter(nome: &str, opcoes: &Opcoes, destino: &mut dyn Destino) {
+    let arquivo = File::open(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(nome)).expect("fixture");
+    let arquivo = abrir_xml(BufReader::new(arquivo), None, None).expect("XML válido");
+    converter_empresas(arquivo, opcoes, destino).expect("conversão");
+}
+
+fn json(opcoes: &Opcoes) -> serde_json::Value {
+    let mut saida = SaidaJson::new(Vec::new(), false);
+    converter("comissao_varios_funcionarios.xml", opcoes, &mut saida);
+    serde_json::from_slice(&saida.finalizar().unwrap()).expect("JSON válido")
+}
+
+#[test]
+fn json_com_valores_numericos() {
+    for formato in [None, Some(FormatoMonetario::PtBr), Some(FormatoMonetario::Real)] {
+        let documento = json(&Opcoes { formato_monetario: formato, ..Opcoes::default() });
+        let funcionario = &documento["Funcionarios"][0];
+        assert_eq!(funcionario["Valor"], serde_json::json!(1234.56), "{:?}", formato);
+        assert_eq!(funcionario["MetaPremio"], serde_json::json!(100.0), "{:?}", formato);
+        assert_eq!(funcionario["CPF"], serde_json::json!("52998224725"));
+    }
+}
+
+#[test]
+fn json_sem_valor_numerico() {
+    let mut saida = SaidaJson::new(Vec::new(), true);
+    saida.iniciar(&["CPF", "Valor", "MetaPremio"]).unwrap();
+    saida.gravar(&["52998224725", "abc", ""]).unwrap();
+    let linha: serde_json::Value = serde_json::from_slice(&saida.finalizar().unwrap()).unwrap();
+    assert_eq!(linha, serde_json::json!({ "CPF": "52998224725", "Valor": null, "MetaPremio": null }));
+}
+
+fn sql(opcoes: &Opcoes) -> String {
+    let mut saida = SaidaSql::new(Vec::new(), "comissao.xml", "2024-02-01 10:00:00");
+    converter("comissao_varios_funcionarios.xml", opcoes, &mut saida);
+    String::from_utf8(saida.finalizar("comissoes").unwrap()).unwrap()
+}
+
+#[test]
+fn sql_com_valores_numericos() {
+    for formato in [None, Some(FormatoMonetario::PtBr), Some(FormatoMonetario::Real)] {
+        let script = sql(&Opcoes { formato_monetario: formato, ..Opcoes::default() });
+        assert!(script.contains(r#""Valor" REAL"#), "{}", script);
+        let insercao = script.lines().find(|linha| linha.starts_with("INSERT")).unwrap();
+        assert!(insercao.contains("'52998224725', 1234.56, 100, "), "{:?}: {}", formato, insercao);
+    }
+}
+
+#[test]
+fn sql_sem_valor_numerico() {
+    let mut saida = SaidaSql::new(Vec::new(), "comissao.xml", "2024-02-01 10:00:00");
+    saida.iniciar(&["CPF", "Valor", "MetaPremio"]).unwrap();
+    saida.gravar(&["52998224725", "abc", ""]).unwrap();
+    let script = String::from_utf8(saida.finalizar("comissoes").unwrap()).unwrap();
+    assert!(script.contains("VALUES ('52998224725', NULL, NULL, 'comissao.xml', '2024-02-01 10:00:00');"), "{}", script);
+}
+
+#[test]
+fn sql_informa_as_colunas_criadas() {
+    let mut saida = SaidaSql::new(Vec::new(), "comissao.xml", "2024-02-01 10:00:00");
+    saida.iniciar(&["CPF", "Valor", "Nome \"social\", completo"]).unwrap();
+    saida.gravar(&["52998224725", "10", "Ana"]).unwrap();
+    let script = String::from_utf8(saida.finalizar("comissoes").unwrap()).unwrap();
+    let (tabela, colunas) = tabela_criada(&script).unwrap();
+    assert_eq!(tabela, "comissoes");
+    let colunas: Vec<(&str, &str)> = colunas.iter().map(|(nome, tipo)| (nome.as_str(), tipo.as_str())).collect();
+    assert_eq!(colunas, [("CPF", "TEXT"), ("Valor", "REAL"), ("Nome \"social\", completo", "TEXT"), ("ArquivoOrigem", "TEXT"), ("ImportadoEm", "TEXT")]);
+}
@@ -0,0 +1,65 @@
+//! Arquivos gerados por `--template`: as variáveis, a repetição dos funcionários, os filtros
+//! de largura fixa e os erros do modelo.
+
+use converterxmlcsv::{Destino, Leiaute, SaidaLeiaute};
+
+const CABECALHO: [&str; 4] = ["CNPJ", "Fantasia", "CPF", "Valor"];
+
+fn gerar(modelo: &str, linhas: &[[&str; 4]]) -> Result<String, String> {
+    let leiaute = Leiaute::from_texto(modelo)?;
+    let mut saida = SaidaLeiaute::new(Vec::new(), leiaute, "comissao.xml", "2024-02-01");
+    saida.iniciar(&CABECALHO).map_err(|erro| erro.to_string())?;
+    for linha in linhas {
+        saida.gravar(linha).map_err(|erro| erro.to_string())?;
+    }
+    let texto = saida.finalizar().map_err(|erro| erro.to_string())?;
+    Ok(String::from_utf8(texto).unwrap())
+}
+
+const LINHAS: [[&str; 4]; 2] = [
+    ["11.222.333/0001-81", "Loja Centro", "52998224725", "1234.56"],
+    ["11.222.333/0001-81", "Loja Centro", "11144477735", "0.1"],
+];
+
+#[test]
+fn variaveis_e_repeticao() {
+    let modelo = "{# remessa #}\n{{ Fantasia | upper }} {{ arquivo }} {{ data }}\n{% for f in funcionarios %}\n{{ loop.index }}/{{ loop.index0 }} {{ f.CPF }} {{ f.Valor }}\n{% endfor %}\n{{ quantidade }} {{ total }}\n";
+    assert_eq!(
+        gerar(modelo, &LINHAS).unwrap(),
+        "LOJA CENTRO comissao.xml 2024-02-01\n1/0 52998224725 1234.56\n2/1 11144477735 0.1\n2 1234.66\n"
+    );
+}
+
+#[test]
+fn filtros_de_largura_fixa() {
+    let modelo = "{{ CNPJ | digits }}{{ Fantasia | ljust(15) }}|\n{% for f in funcionarios %}\n{{ loop.index | add(1) | rjust(3, \"0\") }}{{ f.Valor | cents | rjust(13, \"0\") }}{{ Fantasia | lower | truncate(4) }}\n{% endfor %}\n";
+    assert_eq!(
+        gerar(modelo, &LINHAS).unwrap(),
+        "11222333000181Loja Centro    |\n0020000000123456loja\n0030000000000010loja\n"
+    );
+}
+
+#[test]
+fn sem_funcionarios() {
+    assert_eq!(gerar("{% for f in funcionarios %}{{ f.CPF }}\n{% endfor %}{{ quantidade }};{{ total }};{{ CPF }}\n", &[]).unwrap(), "0;0.00;\n");
+}
+
+#[test]
+fn modelos_invalidos() {
+    for modelo in [
+        "{{ CPF",
+        "{{ CPF | reverse }}",
+        "{{ CPF | rjust }}",
+        "{{ CPF | rjust(5, \"00\") }}",
+        "{{ CPF + 1 }}",
+        "{% if CPF %}",
+        "{% for f in funcionarios %}",
+        "{% endfor %}",
+        "{% for f in funcionarios %}{% for g in funcionarios %}{% endfor %}{% endfor %}",
+    ] {
+        assert!(Leiaute::from_texto(modelo).is_err(), "{}", modelo);
+    }
+    for modelo in ["{{ Salario }}", "{% for f in funcionarios %}{{ f.Salario }}{% endfor %}", "{{ f.CPF }}"] {
+        assert!(gerar(modelo, &LINHAS).is_err(), "{}", modelo);
+    }
+}
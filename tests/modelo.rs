@@ -0,0 +1,57 @@
+//! Leitura de `<Empresa>` com nenhum, um ou vários `<Funcionario>`.
+
+use std::fs;
+use std::path::Path;
+
+use converterxmlcsv::modelo::{Comissao, Empresa};
+use converterxmlcsv::{ler_arquivo, TipoArquivo};
+
+fn fixture(nome: &str) -> String {
+    fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(nome)).expect("fixture")
+}
+
+/// Empresa lida pelo serde-xml-rs, como no `Deserialize` de [`Empresa`].
+fn empresa_serde(nome: &str) -> Empresa {
+    let mut comissao: Comissao = serde_xml_rs::from_str(&fixture(nome)).expect("XML válido");
+    assert_eq!(comissao.empresas.len(), 1);
+    comissao.empresas.remove(0)
+}
+
+/// Empresa lida em fluxo por [`ler_arquivo`].
+fn empresa_lida(nome: &str) -> Empresa {
+    match ler_arquivo(&fixture(nome), None, None).expect("XML válido") {
+        TipoArquivo::Comissao(mut comissao) => comissao.empresas.remove(0),
+        outro => panic!("tipo inesperado: {:?}", outro),
+    }
+}
+
+fn cpfs(empresa: &Empresa) -> Vec<&str> {
+    empresa.funcionarios.iter().flatten().map(|funcionario| funcionario.cpf.as_str()).collect()
+}
+
+#[test]
+fn empresa_sem_funcionarios() {
+    let empresa = empresa_serde("comissao_sem_funcionarios.xml");
+    assert_eq!(empresa.fantasia, "Loja Centro");
+    assert!(empresa.funcionarios.is_none());
+
+    assert!(cpfs(&empresa_lida("comissao_sem_funcionarios.xml")).is_empty());
+}
+
+#[test]
+fn empresa_com_um_funcionario() {
+    let empresa = empresa_serde("comissao_um_funcionario.xml");
+    assert_eq!(cpfs(&empresa), ["52998224725"]);
+    let funcionario = &empresa.funcionarios.as_ref().unwrap()[0];
+    assert_eq!(funcionario.valor.valor, "1234.56");
+    assert_eq!(funcionario.meta_premio.as_deref(), Some("100.00"));
+
+    assert_eq!(cpfs(&empresa_lida("comissao_um_funcionario.xml")), ["52998224725"]);
+}
+
+#[test]
+fn empresa_com_varios_funcionarios_intercalados() {
+    let esperados = ["52998224725", "11144477735", "39053344705"];
+    assert_eq!(cpfs(&empresa_serde("comissao_varios_funcionarios.xml")), esperados);
+    assert_eq!(cpfs(&empresa_lida("comissao_varios_funcionarios.xml")), esperados);
+}
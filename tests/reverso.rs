@@ -0,0 +1,23 @@
+//! Tipo do XML reconstruído a partir de um CSV com as colunas fora da ordem gerada.
+
+use converterxmlcsv::csv_para_xml;
+
+fn xml(csv: &str) -> String {
+    let mut xml = Vec::new();
+    csv_para_xml(csv.as_bytes(), &mut xml, None, b';').expect("CSV válido");
+    String::from_utf8(xml).unwrap()
+}
+
+#[test]
+fn ferias_com_colunas_reordenadas() {
+    let xml = xml("DataFim;CPF;Valor;Fantasia;Razao;CNPJ;MesAno\n2024-01-31;52998224725;10.00;Loja;Loja Ltda;11222333000181;01/2024\n");
+    assert!(xml.contains("<Ferias>"), "{}", xml);
+    assert!(xml.contains("<DataFim>2024-01-31</DataFim>"), "{}", xml);
+}
+
+#[test]
+fn comissao_com_meta_antes_do_valor() {
+    let xml = xml("MetaPremio;CPF;Valor;MesAno;CNPJ;Razao;Fantasia\n5;52998224725;10.00;01/2024;11222333000181;Loja Ltda;Loja\n");
+    assert!(xml.contains("<Comissao>"), "{}", xml);
+    assert!(xml.contains("<MetaPremio>5</MetaPremio>"), "{}", xml);
+}
@@ -0,0 +1,35 @@
+//! Totais de `Valor` e `MetaPremio` somados sem os erros de arredondamento do `f64`.
+
+use std::fs::File;
+use std::path::Path;
+
+use converterxmlcsv::{converter_comissao, FormatoMonetario, Opcoes, Quantia, Resumo};
+
+fn converter(nome: &str) -> Resumo {
+    let arquivo = File::open(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(nome)).expect("fixture");
+    converter_comissao(arquivo, Vec::new(), &Opcoes::default()).expect("conversão")
+}
+
+fn quantia(texto: &str) -> Quantia {
+    Quantia::ler(texto, None).unwrap()
+}
+
+#[test]
+fn totais_exatos() {
+    let resumo = converter("comissao_varios_funcionarios.xml");
+    assert_eq!(resumo.funcionarios, 3);
+    assert_eq!(resumo.total_valor, quantia("1234.86"));
+    assert_eq!(resumo.total_meta, quantia("100.30"));
+}
+
+#[test]
+fn totais_sem_desvio_do_f64() {
+    // Dez vezes 0.10 somados em `f64` dão 0.9999999999999999, e 0.5 + 0.505 fica abaixo de
+    // 1.005, arredondado para 1.00.
+    let resumo = converter("comissao_centavos.xml");
+    assert_eq!(resumo.funcionarios, 10);
+    assert_eq!(resumo.total_valor, quantia("1"));
+    assert_eq!(resumo.total_meta, quantia("1.005"));
+    assert_eq!(format!("{:.2}", resumo.total_meta), "1.01");
+    assert_eq!(FormatoMonetario::PtBr.formatar(resumo.total_meta), "1,01");
+}